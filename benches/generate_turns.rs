@@ -1,5 +1,5 @@
-use chive::engine::game::Game;
-use criterion::{Criterion, criterion_group, criterion_main};
+use chive::engine::game::{Game, Turn};
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
 
 fn complex_game() -> Game {
     let map_str = r#"
@@ -79,10 +79,79 @@ fn bench_generate_turns(c: &mut Criterion) {
                 g.turns().collect::<Vec<_>>()
             })
         });
+        group.bench_with_input(format!("for_each_turn {}", name), game, |b, g| {
+            b.iter(|| {
+                let mut turns = vec![];
+                g.for_each_turn(|turn| turns.push(turn));
+                turns
+            })
+        });
+    }
+
+    group.finish();
+}
+
+/// Checking whether the player to move has to pass. Each check starts from a fresh copy of the
+/// game, so neither side gets to reuse placement hexes worked out by an earlier iteration.
+fn bench_has_any_turn(c: &mut Criterion) {
+    let mut group = c.benchmark_group("has_any_turn");
+
+    let games = [
+        ("complex", complex_game()),
+        ("mid", mid_game()),
+        ("high_density", high_density_game()),
+    ];
+
+    for (name, game) in games.iter() {
+        let fresh = || {
+            Game::from_hive_with_reserves(
+                game.hive().clone(),
+                game.active_player,
+                game.white_reserve.clone(),
+                game.black_reserve.clone(),
+            )
+        };
+        group.bench_function(format!("has_any_turn {}", name), |b| {
+            b.iter_batched(fresh, |g| g.has_any_turn(), BatchSize::SmallInput)
+        });
+        group.bench_function(format!("turns {}", name), |b| {
+            b.iter_batched(
+                fresh,
+                |g| g.turns().next() != Some(Turn::Skip),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+/// Applying every turn copies the board once per turn, like a search does at each node. Compare
+/// with and without the `persistent-hive` feature.
+fn bench_apply_turns(c: &mut Criterion) {
+    let mut group = c.benchmark_group("apply_turns");
+
+    let games = [("complex", complex_game()), ("mid", mid_game())];
+
+    for (name, game) in games.iter() {
+        let turns: Vec<_> = game.turns().collect();
+        group.bench_with_input(format!("with_turn_applied {}", name), game, |b, g| {
+            b.iter(|| {
+                turns
+                    .iter()
+                    .map(|turn| g.with_turn_applied(*turn))
+                    .collect::<Vec<_>>()
+            })
+        });
     }
 
     group.finish();
 }
 
-criterion_group!(benches, bench_generate_turns);
+criterion_group!(
+    benches,
+    bench_generate_turns,
+    bench_has_any_turn,
+    bench_apply_turns
+);
 criterion_main!(benches);
@@ -15,13 +15,13 @@ fn main() {
     .unwrap();
     let start = Game::from_hive(hive, Color::White);
 
-    println!("{}", start.hive);
+    println!("{}", start.hive());
     let pondering_time = Duration::from_secs(10);
     let mut ai = Ai::new(pondering_time, pondering_time * 3);
     let mut game = start;
     while let Ok(turn) = ai.choose_turn(&game) {
         game = game.with_turn_applied(turn);
-        println!("{}", game.hive);
+        println!("{}", game.hive());
     }
-    println!("{}", game.hive);
+    println!("{}", game.hive());
 }
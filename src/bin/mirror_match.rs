@@ -0,0 +1,262 @@
+use chive::config::{Binary, evaluator_parser, parse_args};
+use chive::engine::ai::{
+    Ai, PiecesAroundQueenAndAvailableMoves, RandomOpening, SearchStats, TimeManagement,
+};
+use chive::engine::game::{Game, GameResult, Turn};
+use chive::engine::hive::Color;
+use clap::Parser;
+use rand::SeedableRng;
+use rand::prelude::IndexedRandom;
+use rand::rngs::StdRng;
+use std::time::Duration;
+
+/// Play a challenger evaluator against the default one to see whether new weights are better
+///
+/// The challenger starts from the weights of `--evaluator`, with any weights passed on the command
+/// line replacing those. The two sides swap colors every game, and each pair of games starts from
+/// the same random opening so neither side gets the easier half.
+#[derive(Debug, Parser)]
+pub struct Config {
+    #[clap(default_value = "10")]
+    #[arg(short, long)]
+    games: usize,
+
+    #[clap(value_parser = humantime::parse_duration, default_value = "1s")]
+    #[arg(short, long)]
+    pondering_time: Duration,
+
+    /// How many random turns to play before handing the game to the AIs
+    #[clap(default_value = "2")]
+    #[arg(long)]
+    opening_turns: usize,
+
+    /// Seed for the random openings
+    #[clap(default_value = "0")]
+    #[arg(long)]
+    seed: u64,
+
+    /// Call the game a draw after this many turns
+    #[clap(default_value = "200")]
+    #[arg(long)]
+    max_turns: usize,
+
+    /// Print every turn of every game
+    #[arg(long)]
+    transcripts: bool,
+
+    /// Print how many positions the AIs searched per turn, and the turn that took the most
+    #[arg(long)]
+    search_stats: bool,
+
+    /// Spend less time on simple positions and more on complicated ones, between a quarter of and
+    /// twice the pondering time
+    #[arg(long)]
+    scaled_time: bool,
+
+    /// After the random opening, have both AIs pick randomly among their best few turns until
+    /// this many pieces are on the board, so games don't all play out the same way
+    #[clap(default_value = "0")]
+    #[arg(long)]
+    varied_opening_plies: usize,
+
+    /// How many of their best turns the AIs pick between during the varied opening
+    #[clap(default_value = "3")]
+    #[arg(long)]
+    varied_opening_top: usize,
+
+    /// Which evaluator the challenger starts from. The baseline always uses the default one.
+    #[clap(value_parser = evaluator_parser(), default_value = "default")]
+    #[arg(long)]
+    evaluator: PiecesAroundQueenAndAvailableMoves,
+
+    #[arg(long)]
+    piece_around_queen_value: Option<i16>,
+
+    #[arg(long)]
+    available_move_value: Option<i16>,
+
+    #[arg(long)]
+    queen_escape_value: Option<i16>,
+
+    #[arg(long)]
+    covered_queen_value: Option<i16>,
+
+    #[arg(long)]
+    development_value: Option<i16>,
+
+    #[arg(long)]
+    blocked_escape_value: Option<i16>,
+}
+
+impl Config {
+    fn challenger_evaluator(&self) -> PiecesAroundQueenAndAvailableMoves {
+        let base = self.evaluator.clone();
+        PiecesAroundQueenAndAvailableMoves {
+            piece_around_queen_value: self
+                .piece_around_queen_value
+                .unwrap_or(base.piece_around_queen_value),
+            available_move_value: self
+                .available_move_value
+                .unwrap_or(base.available_move_value),
+            queen_escape_value: self.queen_escape_value.unwrap_or(base.queen_escape_value),
+            covered_queen_value: self.covered_queen_value.unwrap_or(base.covered_queen_value),
+            development_value: self.development_value.unwrap_or(base.development_value),
+            blocked_escape_value: self
+                .blocked_escape_value
+                .unwrap_or(base.blocked_escape_value),
+        }
+    }
+
+    fn ai(&self, evaluator: PiecesAroundQueenAndAvailableMoves, seed: u64) -> Ai {
+        let ai = Ai::with_evaluator(self.pondering_time, self.pondering_time * 3, evaluator)
+            .with_random_opening(RandomOpening {
+                plies: self.varied_opening_plies,
+                top: self.varied_opening_top,
+                seed,
+            });
+        let ai = if self.search_stats {
+            ai.with_search_stats()
+        } else {
+            ai
+        };
+        if self.scaled_time {
+            ai.with_time_management(TimeManagement::Scaled {
+                min: self.pondering_time / 4,
+                max: self.pondering_time * 2,
+            })
+        } else {
+            ai
+        }
+    }
+}
+
+#[derive(Default)]
+struct Tally {
+    wins: usize,
+    draws: usize,
+    losses: usize,
+}
+
+fn random_opening(turns: usize, seed: u64) -> Game {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut game = Game::default();
+    for _ in 0..turns {
+        let turns: Vec<Turn> = game.turns().collect();
+        game = game.with_turn_applied(*turns.choose(&mut rng).unwrap());
+    }
+    game
+}
+
+/// Play one game and return the winner, `None` for a draw
+fn play(
+    config: &Config,
+    challenger_color: Color,
+    game: Game,
+    seed: u64,
+    stats: &mut Vec<SearchStats>,
+) -> Option<Color> {
+    let mut challenger = config.ai(config.challenger_evaluator(), seed);
+    let mut baseline = config.ai(PiecesAroundQueenAndAvailableMoves::default(), seed);
+    let winner = play_out(
+        config,
+        challenger_color,
+        game,
+        &mut challenger,
+        &mut baseline,
+    );
+    stats.extend(challenger.search_stats());
+    stats.extend(baseline.search_stats());
+    winner
+}
+
+fn play_out(
+    config: &Config,
+    challenger_color: Color,
+    mut game: Game,
+    challenger: &mut Ai,
+    baseline: &mut Ai,
+) -> Option<Color> {
+    for _ in 0..config.max_turns {
+        match game.game_result() {
+            GameResult::None => {}
+            GameResult::Draw => return None,
+            GameResult::Winner { color } => return Some(color),
+        }
+
+        let ai = if game.active_player == challenger_color {
+            &mut *challenger
+        } else {
+            &mut *baseline
+        };
+        let Ok(turn) = ai.choose_turn(&game) else {
+            // Running out of time forfeits the game
+            return Some(game.active_player.opposite());
+        };
+        if config.transcripts {
+            println!("{}: {:?}", game.active_player, turn);
+        }
+        game = game.with_turn_applied(turn);
+    }
+    None
+}
+
+fn main() {
+    let config: Config = match parse_args(Binary::MirrorMatch) {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("{error}");
+            std::process::exit(2);
+        }
+    };
+    let mut tally = Tally::default();
+    let mut stats = vec![];
+
+    for game_number in 0..config.games {
+        let challenger_color = if game_number % 2 == 0 {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let seed = config.seed.wrapping_add(game_number as u64 / 2);
+        let opening = random_opening(config.opening_turns, seed);
+        if config.transcripts {
+            println!(
+                "Game {}, challenger is {challenger_color}\n{}",
+                game_number + 1,
+                opening.hive()
+            );
+        }
+
+        let winner = play(&config, challenger_color, opening, seed, &mut stats);
+        match winner {
+            Some(color) if color == challenger_color => tally.wins += 1,
+            Some(_) => tally.losses += 1,
+            None => tally.draws += 1,
+        }
+        println!(
+            "Game {}: {}",
+            game_number + 1,
+            winner.map_or("draw".to_string(), |color| format!("{color} won"))
+        );
+    }
+
+    println!(
+        "Challenger {} wins, {} draws, {} losses over {} games",
+        tally.wins, tally.draws, tally.losses, config.games
+    );
+
+    if let Some(slowest) = stats.iter().max_by_key(|stats| stats.nodes) {
+        let nodes: u64 = stats.iter().map(|stats| stats.nodes).sum();
+        println!(
+            "Searched {} positions per turn on average, at most {} in {:?} reaching depth {}",
+            nodes / stats.len() as u64,
+            slowest.nodes,
+            slowest.time,
+            slowest.depth
+        );
+    }
+    let timeouts = stats.iter().filter(|stats| stats.timed_out).count();
+    if timeouts > 0 {
+        println!("Ran out of time before finding a turn {timeouts} times");
+    }
+}
@@ -1,12 +1,13 @@
 use crate::AppError::AiError;
 use crate::SelectionState::{PieceSelected, PushingPiece};
-use chive::engine::ai::Ai;
+use chive::config::{Binary, evaluator_parser, parse_args};
+use chive::engine::ai::{Ai, PiecesAroundQueenAndAvailableMoves};
 use chive::engine::bug::Bug;
-use chive::engine::game::{Game, GameResult, Turn};
+use chive::engine::game::{Game, GameResult, MoveKind, Turn};
 use chive::engine::hex::Hex;
 use chive::engine::hive::{Color, Tile};
 use chive::engine::row_col::{RowCol, RowColDimensions};
-use chive::engine::save_game::{list_save_games, load_game, save_game};
+use chive::engine::save_game::{list_save_games_detailed, load_game_auto, save_game};
 use chive::engine::{ai, row_col};
 use clap::Parser;
 use itertools::Itertools;
@@ -20,7 +21,8 @@ use ratatui::{DefaultTerminal, Frame};
 use std::cmp::max;
 use std::io;
 use std::path::PathBuf;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use strum::{Display, EnumString};
 use thiserror::Error;
 
 enum SelectionState {
@@ -36,21 +38,62 @@ struct App {
     player_color: Color,
     selection: SelectionState,
     last_ai_move_pos: Option<RowCol>,
+    turn_started: Instant,
+    color_scheme: ColorScheme,
+    glyphs: Glyphs,
+    teaching: bool,
+    /// A turn that lets the opponent win right away, waiting for the player to confirm it
+    blunder_warning: Option<Turn>,
+    /// The turn a quick search suggested, shown until the player makes a turn
+    hint: Option<Turn>,
 }
 
+/// How many plies deep to search for a hint, kept shallow since the search runs on the UI thread
+const HINT_DEPTH: u8 = 2;
+
+/// How often to check the clock while waiting for the player, so it can run out between keys
+const CLOCK_TICK: Duration = Duration::from_millis(200);
+
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("Failed to interact with terminal")]
     IoError(#[from] io::Error),
-    #[error("AI Failed to find a valid move")]
+    #[error("AI Failed to find a valid move: {0}")]
     AiError(#[from] ai::AiError),
 }
 
-fn tile_to_span<'a>(tile: Tile) -> Span<'a> {
-    if tile.color == Color::White {
-        Span::from(tile.to_string()).black().on_white()
-    } else {
-        Span::from(tile.to_string()).white().on_black()
+/// How tiles are colored on the board and in the reserves
+#[derive(Debug, Clone, Copy, Default, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum ColorScheme {
+    /// White pieces are black on white, black pieces are white on black
+    #[default]
+    Classic,
+    /// Your pieces are on blue and the computer's are on yellow, whichever color you're playing
+    Hues,
+}
+
+/// How bugs are drawn
+#[derive(Debug, Clone, Copy, Default, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum Glyphs {
+    /// The first letter of the bug, uppercase for white and lowercase for black
+    #[default]
+    Letters,
+    /// A unicode symbol for each bug, leaving the color scheme to tell the players apart
+    Symbols,
+}
+
+fn bug_symbol(bug: Bug) -> char {
+    match bug {
+        Bug::Ant => '⁂',
+        Bug::Beetle => '◆',
+        Bug::Grasshopper => '»',
+        Bug::Queen => '♛',
+        Bug::Spider => '✲',
+        Bug::Ladybug => '●',
+        Bug::Mosquito => '✕',
+        Bug::Pillbug => '◎',
     }
 }
 
@@ -68,16 +111,21 @@ fn wrapping_add(left: i32, right: i32, min: i32, max: i32) -> i32 {
 }
 
 impl App {
-    fn last_affected_row_col(&self, turn: &Turn) -> Option<RowCol> {
-        match turn {
-            Turn::Placement { hex, tile: _ } => Some(RowCol::from_hex(hex)),
-            Turn::Move { to, .. } => Some(RowCol::from_hex(to)),
-            Turn::Skip => self.last_ai_move_pos,
+    fn tile_to_span<'a>(&self, tile: Tile) -> Span<'a> {
+        let span = match self.glyphs {
+            Glyphs::Letters => Span::from(tile.to_string()),
+            Glyphs::Symbols => Span::from(bug_symbol(tile.bug).to_string()),
+        };
+        match self.color_scheme {
+            ColorScheme::Classic if tile.color == Color::White => span.black().on_white(),
+            ColorScheme::Classic => span.white().on_black(),
+            ColorScheme::Hues if tile.color == self.player_color => span.black().on_light_blue(),
+            ColorScheme::Hues => span.black().on_light_yellow(),
         }
     }
 
     fn board_dimensions(&self) -> RowColDimensions {
-        let map_dimensions = row_col::dimensions(self.game.hive.to_hex_map().keys());
+        let map_dimensions = row_col::dimensions(self.game.hive().to_hex_map().keys());
         RowColDimensions {
             row_min: map_dimensions.row_min - 1,
             row_max: map_dimensions.row_max + 1,
@@ -89,7 +137,7 @@ impl App {
     }
 
     fn board_string(&self) -> String {
-        self.game.hive.to_string()
+        self.game.hive().to_string()
     }
 
     fn game(&self) -> Game {
@@ -97,11 +145,7 @@ impl App {
     }
 
     fn game_result(&self) -> Option<String> {
-        match self.game.game_result() {
-            GameResult::None => None,
-            GameResult::Draw => Some(format!("Draw!\n{}", self.game.hive)),
-            GameResult::Winner { color } => Some(format!("{} Won!\n{}", color, self.game.hive)),
-        }
+        describe_result(&self.game)
     }
 
     fn run(&mut self, mut terminal: DefaultTerminal) -> Result<String, AppError> {
@@ -118,6 +162,10 @@ impl App {
                 terminal.draw(|frame| self.draw(frame))?;
             }
 
+            if !event::poll(CLOCK_TICK)? {
+                self.run_out_clock();
+                continue;
+            }
             if let Some(key) = event::read()?.as_key_press_event() {
                 match key {
                     KeyEvent {
@@ -140,7 +188,11 @@ impl App {
                     }
                     KeyEvent {
                         code: KeyCode::Esc, ..
-                    } => self.selection = SelectionState::None,
+                    } => {
+                        self.selection = SelectionState::None;
+                        self.blunder_warning = None;
+                        self.hint = None;
+                    }
                     KeyEvent {
                         code: KeyCode::Enter,
                         ..
@@ -148,7 +200,21 @@ impl App {
                     KeyEvent {
                         code: KeyCode::F(1),
                         ..
-                    } => return Ok(self.game.hive.to_string()),
+                    } => return Ok(self.game.hive().to_string()),
+                    KeyEvent {
+                        code: KeyCode::F(2),
+                        ..
+                    } => {
+                        self.game.resign(self.player_color);
+                    }
+                    KeyEvent {
+                        code: KeyCode::F(3),
+                        ..
+                    } => self.teaching = !self.teaching,
+                    KeyEvent {
+                        code: KeyCode::Char('?'),
+                        ..
+                    } => self.show_hint(),
                     KeyEvent {
                         code: KeyCode::Char(char),
                         ..
@@ -188,11 +254,11 @@ impl App {
             SelectionState::None => {
                 self.selection = self
                     .game
-                    .hive
-                    .topmost_occupied_hex(&self.cursor_pos.to_hex())
+                    .hive()
+                    .topmost_occupied_hex_rc(self.cursor_pos)
                     .filter(|hex| {
                         self.game
-                            .hive
+                            .hive()
                             .tile_at(hex)
                             .is_some_and(|tile| tile.color == self.player_color)
                     })
@@ -202,17 +268,14 @@ impl App {
                 self.selection = SelectionState::None;
             }
             PieceSelected { pos } => {
-                let pillbug_selected = self
-                    .game
-                    .hive
-                    .tile_at(&pos)
-                    .is_some_and(|tile| tile.bug == Bug::Pillbug);
-
-                let is_pushable_piece = pillbug_selected
-                    && self.game.moves_for_piece(&pos).any(|mv| match mv {
-                        Turn::Move { from, .. } if self.cursor_pos.to_hex() == from => true,
-                        _ => false,
-                    });
+                // Pillbugs, and mosquitoes next to a pillbug, can push their neighbors
+                let is_pushable_piece = self.game.moves_for_piece(&pos).any(|mv| {
+                    matches!(
+                        mv,
+                        Turn::Move { from, freezes_piece: true, .. }
+                            if self.cursor_pos.to_hex() == from
+                    )
+                });
 
                 if is_pushable_piece {
                     self.selection = PushingPiece {
@@ -224,13 +287,13 @@ impl App {
                         from: pos,
                         to: self
                             .game
-                            .hive
-                            .bottommost_unoccupied_hex(&self.cursor_pos.to_hex()),
+                            .hive()
+                            .bottommost_unoccupied_hex_rc(self.cursor_pos),
                         freezes_piece: false,
                     };
 
-                    if self.game.turn_is_valid(turn) {
-                        self.game = self.game.with_turn_applied(turn);
+                    if self.game.turn_is_valid(turn) && self.confirm_turn(turn) {
+                        self.apply_turn(turn);
                         self.selection = SelectionState::None;
                     }
                 }
@@ -244,8 +307,8 @@ impl App {
                         to: self.cursor_pos.to_hex(),
                         freezes_piece: true,
                     };
-                    if self.game.turn_is_valid(turn) {
-                        self.game = self.game.with_turn_applied(turn);
+                    if self.game.turn_is_valid(turn) && self.confirm_turn(turn) {
+                        self.apply_turn(turn);
                         self.selection = SelectionState::None;
                     }
                 }
@@ -266,16 +329,74 @@ impl App {
                     color: self.player_color,
                 },
             };
-            if self.game.turn_is_valid(turn) {
-                self.game = self.game.with_turn_applied(turn);
+            if self.game.turn_is_valid(turn) && self.confirm_turn(turn) {
+                self.apply_turn(turn);
             }
         }
     }
 
+    /// Whether the player's turn should go ahead. A turn that hands the opponent an immediate win
+    /// only goes ahead the second time in a row it's tried, the first time it's just a warning.
+    fn confirm_turn(&mut self, turn: Turn) -> bool {
+        if self.blunder_warning.take() == Some(turn) {
+            return true;
+        }
+        if self.game.opponent_winning_replies(turn).is_empty() {
+            return true;
+        }
+        self.blunder_warning = Some(turn);
+        false
+    }
+
+    /// End the game if the player to move has used up their time, without waiting for their turn
+    fn run_out_clock(&mut self) {
+        let elapsed = self.turn_started.elapsed();
+        let clock = match self.game.active_player {
+            Color::White => &mut self.game.white_clock,
+            Color::Black => &mut self.game.black_clock,
+        };
+        if clock.is_some_and(|remaining| elapsed >= remaining) {
+            *clock = Some(Duration::ZERO);
+        }
+    }
+
+    fn apply_turn(&mut self, turn: Turn) {
+        self.game = self
+            .game
+            .with_timed_turn_applied(turn, self.turn_started.elapsed());
+        self.turn_started = Instant::now();
+        self.hint = None;
+    }
+
+    /// Suggest a turn for the player with a shallow single threaded search. The search gets its
+    /// own AI, so the opponent's settings and search state stay as they were.
+    fn show_hint(&mut self) {
+        if self.game.active_player != self.player_color {
+            return;
+        }
+        let mut ai = Ai::deterministic(0, HINT_DEPTH);
+        self.hint = ai.choose_turn(&self.game).ok();
+    }
+
+    /// The hexes the hinted turn places a piece on or moves one between
+    fn hint_hexes(&self) -> Vec<RowCol> {
+        match self.hint {
+            Some(Turn::Placement { hex, .. }) => vec![RowCol::from_hex(&hex)],
+            Some(Turn::Move { from, to, .. }) => [from, to]
+                .iter()
+                .map(|hex| RowCol::from_hex(&hex.base_level()))
+                .collect(),
+            Some(Turn::Skip) | None => vec![],
+        }
+    }
+
     fn make_ai_move(&mut self) -> Result<(), AppError> {
         let turn = self.ai.choose_turn(&self.game)?;
-        self.last_ai_move_pos = self.last_affected_row_col(&turn);
-        self.game = self.game.with_turn_applied(turn);
+        self.apply_turn(turn);
+        // Keep highlighting the previous AI move if the AI skipped
+        if let Some((_, to)) = self.game.last_move_hexes() {
+            self.last_ai_move_pos = Some(RowCol::from_hex(&to));
+        }
         Ok(())
     }
 
@@ -286,14 +407,51 @@ impl App {
                 Constraint::Length(1),
                 Constraint::Length(1),
                 Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Length(self.status_height()),
                 Constraint::Min(3),
             ])
             .split(frame.area());
 
-        self.draw_reserve(Color::White, frame, layout[0]);
-        self.draw_reserve(Color::Black, frame, layout[1]);
-        self.draw_stack(frame, layout[2]);
-        self.draw_map(frame, &layout[3])
+        self.draw_clocks(frame, layout[0]);
+        self.draw_reserve(Color::White, frame, layout[1]);
+        self.draw_reserve(Color::Black, frame, layout[2]);
+        self.draw_stack(frame, layout[3]);
+        if self.blunder_warning.is_some() {
+            let warning = "Warning: this lets your opponent win next turn, do it again to confirm";
+            frame.render_widget(Line::from(warning).red(), layout[4]);
+        } else if let Some(hint) = self.hint {
+            let hint = format!("Hint: {}", self.game.to_move_string(hint));
+            frame.render_widget(Line::from(hint).cyan(), layout[4]);
+        } else if self.teaching {
+            self.draw_explanation(frame, layout[4]);
+        }
+        self.draw_map(frame, &layout[5])
+    }
+
+    fn draw_clocks(&self, frame: &mut Frame, area: Rect) {
+        if self.game.white_clock.is_none() && self.game.black_clock.is_none() {
+            return;
+        }
+
+        // The player to move is using up their time as the clock is drawn
+        let elapsed = self.turn_started.elapsed();
+        let format_clock = |color: Color, clock: Option<Duration>| {
+            clock.map_or("-".to_string(), |clock| {
+                let clock = if color == self.game.active_player {
+                    clock.saturating_sub(elapsed)
+                } else {
+                    clock
+                };
+                format!("{}:{:02}", clock.as_secs() / 60, clock.as_secs() % 60)
+            })
+        };
+        let clocks = format!(
+            "White Clock: {}  Black Clock: {}",
+            format_clock(Color::White, self.game.white_clock),
+            format_clock(Color::Black, self.game.black_clock)
+        );
+        frame.render_widget(Line::from(clocks), area);
     }
 
     fn draw_reserve(&self, color: Color, frame: &mut Frame, area: Rect) {
@@ -306,7 +464,7 @@ impl App {
         #[allow(unstable_name_collisions)]
         let pieces = reserve
             .iter()
-            .map(|b| tile_to_span(Tile { bug: *b, color }))
+            .map(|b| self.tile_to_span(Tile { bug: *b, color }))
             .intersperse(Span::from(", "));
         let reserve: Vec<Span> = [Span::from(format!("{name} Reserve: "))]
             .into_iter()
@@ -319,12 +477,8 @@ impl App {
         let cursor_hex_pos = self.cursor_pos.to_hex();
 
         let mut spans: Vec<Span> = vec![Span::raw("Stack: ")];
-        for (i, tile) in self.game.hive.stack_at(&cursor_hex_pos).enumerate() {
-            if tile.color == Color::White {
-                spans.push(Span::raw(tile.to_string()).black().on_white())
-            } else {
-                spans.push(Span::raw(tile.to_string()).white().on_black())
-            }
+        for (i, tile) in self.game.hive().stack_at(&cursor_hex_pos).enumerate() {
+            spans.push(self.tile_to_span(*tile));
 
             if i % 2 == 0 {
                 spans.push(Span::raw(" "));
@@ -334,8 +488,65 @@ impl App {
         frame.render_widget(stack_text, area);
     }
 
+    fn status_height(&self) -> u16 {
+        if self.teaching || self.blunder_warning.is_some() || self.hint.is_some() {
+            1
+        } else {
+            0
+        }
+    }
+
+    /// Hexes the selected piece could reach if moving it didn't have to keep the hive in one piece
+    fn blocked_destinations(&self) -> Vec<RowCol> {
+        let PieceSelected { pos } = self.selection else {
+            return vec![];
+        };
+        let legal: Vec<Hex> = self.game.valid_destinations_for_piece(&pos).collect();
+        self.game
+            .destinations_ignoring_hive(&pos)
+            .into_iter()
+            .filter(|hex| !legal.contains(hex))
+            .map(|hex| RowCol::from_hex(&hex.base_level()))
+            .collect()
+    }
+
+    /// Why the selected piece can or can't go where the cursor is
+    fn draw_explanation(&self, frame: &mut Frame, area: Rect) {
+        let cursor = self.cursor_pos;
+        let (piece, pushing) = match self.selection {
+            SelectionState::None => {
+                let text = "Select a piece to see where it can go";
+                frame.render_widget(Line::from(text), area);
+                return;
+            }
+            PieceSelected { pos } => (pos, false),
+            PushingPiece { push_target, .. } => (push_target, true),
+        };
+
+        let kind = self
+            .game
+            .annotated_destinations(&piece)
+            .into_iter()
+            .find(|(to, kind)| {
+                RowCol::from_hex(&to.base_level()) == cursor
+                    && (*kind == MoveKind::PillbugPush) == pushing
+            })
+            .map(|(_, kind)| kind);
+        let text = match kind {
+            Some(MoveKind::Slide) => "Slide: the piece fits through the gaps along the ground",
+            Some(MoveKind::Climb) => "Climb: the piece moves up onto, across or down off the hive",
+            Some(MoveKind::Jump) => "Jump: leaps in a straight line over a row of pieces",
+            Some(MoveKind::PillbugPush) => "Pillbug push: carried over the pillbug to here",
+            None if self.blocked_destinations().contains(&cursor) => {
+                "Blocked: moving the piece would split the hive in two"
+            }
+            None => "The selected piece can't move here",
+        };
+        frame.render_widget(Line::from(text), area);
+    }
+
     fn draw_map(&self, frame: &mut Frame, area: &Rect) {
-        let hex_map = self.game.hive.to_hex_map();
+        let hex_map = self.game.hive().to_hex_map();
         let map_dimensions = row_col::dimensions(hex_map.keys());
         let board_dimensions = self.board_dimensions();
         let col_constraints = (0..board_dimensions.width()).map(|_| Constraint::Length(1));
@@ -360,36 +571,43 @@ impl App {
             });
 
         let mut possible_destinations = vec![];
+        let mut climb_destinations = vec![];
         let mut pushable_pieces = vec![];
+        let blocked_destinations = if self.teaching {
+            self.blocked_destinations()
+        } else {
+            vec![]
+        };
+        let hint_hexes = self.hint_hexes();
 
         match self.selection {
             SelectionState::None => {}
             PieceSelected { pos } => {
                 for mv in self.game.moves_for_piece(&pos) {
-                    match mv {
-                        Turn::Move { from, to, .. } => {
-                            if from == pos {
-                                possible_destinations.push(RowCol::from_hex(&to))
-                            } else {
-                                pushable_pieces.push(RowCol::from_hex(&from))
-                            }
+                    if let Turn::Move { from, to, .. } = mv {
+                        if from == pos {
+                            possible_destinations.push(RowCol::from_hex(&to.base_level()))
+                        } else {
+                            pushable_pieces.push(RowCol::from_hex(&from))
                         }
-                        _ => {}
                     }
                 }
+                climb_destinations = self
+                    .game
+                    .beetle_climb_destinations(&pos)
+                    .iter()
+                    .map(|hex| RowCol::from_hex(&hex.base_level()))
+                    .collect();
             }
             PushingPiece {
                 pillbug_pos,
                 push_target,
             } => {
                 for mv in self.game.moves_for_piece(&pillbug_pos) {
-                    match mv {
-                        Turn::Move { from, to, .. } => {
-                            if from == push_target {
-                                possible_destinations.push(RowCol::from_hex(&to))
-                            }
-                        }
-                        _ => {}
+                    if let Turn::Move { from, to, .. } = mv
+                        && from == push_target
+                    {
+                        possible_destinations.push(RowCol::from_hex(&to))
                     }
                 }
             }
@@ -414,9 +632,9 @@ impl App {
 
             let mut text = self
                 .game
-                .hive
-                .top_tile_at(&hex)
-                .map(tile_to_span)
+                .hive()
+                .top_tile_at_rc(row_col)
+                .map(|tile| self.tile_to_span(tile))
                 .unwrap_or(default.clone());
 
             match self.selection {
@@ -425,13 +643,19 @@ impl App {
                 _ => {}
             }
 
-            if self.game.hive.stack_height(&hex) > 1 {
+            if self.game.hive().stack_height_rc(row_col) > 1 {
                 text = text.underlined()
             }
-            if possible_destinations.contains(&row_col) {
+            if climb_destinations.contains(&row_col) {
+                text = text.on_green().underlined();
+            } else if possible_destinations.contains(&row_col) {
                 text = text.on_green();
             } else if pushable_pieces.contains(&row_col) {
                 text = text.underlined();
+            } else if blocked_destinations.contains(&row_col) {
+                text = text.on_red();
+            } else if hint_hexes.contains(&row_col) {
+                text = text.on_cyan();
             } else if Some(row_col) == self.last_ai_move_pos {
                 text = text.on_magenta()
             }
@@ -440,6 +664,23 @@ impl App {
     }
 }
 
+/// Who won and the final board, or None if the game is still going
+fn describe_result(game: &Game) -> Option<String> {
+    match game.game_result() {
+        GameResult::None => None,
+        GameResult::Draw => Some(format!("Draw!\n{}", game.hive())),
+        GameResult::Winner { color } => match game.resigned {
+            Some(resigned) => Some(format!(
+                "{} resigned, {} Won!\n{}",
+                resigned,
+                color,
+                game.hive()
+            )),
+            None => Some(format!("{} Won!\n{}", color, game.hive())),
+        },
+    }
+}
+
 /// Play hive against the computer
 ///
 /// - Arrow keys to move around
@@ -448,9 +689,21 @@ impl App {
 ///
 /// - Enter to select tile, enter again to move piece to cursor
 ///
+/// - With a pillbug (or a mosquito next to one) selected, enter on an underlined neighbor to pick
+///   it up, then enter again to push it to the cursor
+///
 /// - Escape to deselect
 ///
+/// - A turn that lets the computer win right away has to be made twice to go through
+///
 /// - f1 to quit
+///
+/// - f2 to resign, the game is still saved
+///
+/// - f3 to toggle teaching mode, which explains why the selected piece can or can't move to the
+///   cursor and shows moves blocked by the one hive rule in red
+///
+/// - ? to have the computer suggest a turn, which is highlighted in cyan until you make a turn
 #[derive(Debug, Parser)]
 pub struct Config {
     #[clap(value_parser = humantime::parse_duration, default_value = "5s")]
@@ -470,18 +723,64 @@ pub struct Config {
     #[clap(default_value = "white")]
     #[arg(short = 'c', long)]
     player_color: Color,
+
+    /// Give each player this much total time, untimed if not set
+    #[clap(value_parser = humantime::parse_duration)]
+    #[arg(long)]
+    clock: Option<Duration>,
+
+    /// How to color tiles, either classic or hues
+    #[clap(default_value = "classic")]
+    #[arg(long)]
+    color_scheme: ColorScheme,
+
+    /// How to draw bugs, either letters or symbols
+    #[clap(default_value = "letters")]
+    #[arg(long)]
+    glyphs: Glyphs,
+
+    /// Start with teaching mode on
+    #[arg(long)]
+    teaching: bool,
+
+    /// How the AI scores positions
+    #[clap(value_parser = evaluator_parser(), default_value = "default")]
+    #[arg(long)]
+    evaluator: PiecesAroundQueenAndAvailableMoves,
 }
 
 fn main() {
-    let args = Config::parse();
+    let args: Config = match parse_args(Binary::Tui) {
+        Ok(args) => args,
+        Err(error) => {
+            eprintln!("{error}");
+            std::process::exit(2);
+        }
+    };
     if args.list_saves {
-        let saves = list_save_games(args.save_directory).unwrap();
-        println!("{}", saves.iter().join("\n"));
+        let listing = list_save_games_detailed(args.save_directory).unwrap();
+        for (file_name, error) in listing.skipped {
+            eprintln!("Skipping save {file_name}: {error}");
+        }
+        for save in listing.saves {
+            let status = match save.result {
+                GameResult::None => format!("{} to move", save.active_player),
+                GameResult::Draw => "draw".to_string(),
+                GameResult::Winner { color } => format!("{color} won"),
+            };
+            println!(
+                "{}  {}  {} pieces, {}",
+                save.file_name,
+                humantime::format_rfc3339_seconds(save.modified),
+                save.piece_count,
+                status
+            );
+        }
         return;
     }
 
-    let game = if let Some(save) = args.load_save_file {
-        load_game(
+    let mut game = if let Some(save) = args.load_save_file {
+        load_game_auto(
             [args.save_directory.clone(), save]
                 .iter()
                 .collect::<PathBuf>(),
@@ -491,18 +790,37 @@ fn main() {
         Default::default()
     };
 
+    // A finished save has nothing left to play, and it's already saved
+    if game.is_game_over() {
+        println!("This game is already over");
+        println!("{}", describe_result(&game).unwrap_or_default());
+        return;
+    }
+
+    if let Some(clock) = args.clock {
+        game.white_clock = Some(clock);
+        game.black_clock = Some(clock);
+    }
+
     let terminal = ratatui::init();
     let pondering_time = args.pondering_time;
     let mut app = App {
         game,
-        ai: Ai::new(
+        ai: Ai::with_evaluator(
             pondering_time,
             max(pondering_time * 3, Duration::from_secs(5)),
+            args.evaluator,
         ),
         cursor_pos: Default::default(),
         player_color: args.player_color,
         selection: SelectionState::None,
         last_ai_move_pos: None,
+        turn_started: Instant::now(),
+        color_scheme: args.color_scheme,
+        glyphs: args.glyphs,
+        teaching: args.teaching,
+        blunder_warning: None,
+        hint: None,
     };
     let result = app.run(terminal);
     ratatui::restore();
@@ -512,8 +830,14 @@ fn main() {
             let game_path = save_game(&app.game(), args.save_directory).unwrap();
             println!("Saved game to {}", game_path.display());
         }
-        Err(AiError(_)) => {
-            println!("AI Failed to find move in time :(");
+        Err(AiError(error)) => {
+            match error {
+                ai::AiError::Timeout => println!("AI Failed to find move in time :("),
+                ai::AiError::NoMoves => println!("AI had no moves left to make, the game is over"),
+                ai::AiError::SearchFailed(turn) => {
+                    println!("AI search came back with an illegal turn: {:?}", turn)
+                }
+            }
             println!("{}", app.board_string());
             let game_path = save_game(&app.game(), args.save_directory).unwrap();
             println!("Saved game to {}", game_path.display());
@@ -0,0 +1,212 @@
+//! Defaults for the binaries' command line flags, read from a `chive.toml` file so a tournament
+//! organizer can ship one standard setup.
+//!
+//! Each binary has its own section, keyed by the long name of its flags:
+//!
+//! ```toml
+//! [tui]
+//! pondering-time = "10s"
+//! color-scheme = "hues"
+//!
+//! [mirror-match]
+//! games = 50
+//! ```
+//!
+//! A setting is taken from, in order of precedence: the command line flag, the config file, then
+//! the binary's built-in default. The file is `chive.toml` in the working directory, or whatever
+//! the `CHIVE_CONFIG` environment variable points at. Without a file the built-in defaults apply.
+
+use crate::engine::ai::{EVALUATORS, PiecesAroundQueenAndAvailableMoves, evaluator_named};
+use clap::builder::{OsStr, PossibleValue, PossibleValuesParser, TypedValueParser};
+use clap::{Command, Parser};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::{env, fs, io};
+use strum::Display;
+use thiserror::Error;
+
+pub const CONFIG_FILE_NAME: &str = "chive.toml";
+pub const CONFIG_PATH_VARIABLE: &str = "CHIVE_CONFIG";
+
+/// Flag defaults for one binary, by the long name of the flag
+pub type Settings = BTreeMap<String, toml::Value>;
+
+/// The binaries that can be configured, named the same as their section in the config file
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Display)]
+#[strum(serialize_all = "kebab-case")]
+pub enum Binary {
+    Tui,
+    MirrorMatch,
+}
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Failed to read config file '{0}': {1}")]
+    ReadFileError(String, #[source] io::Error),
+    #[error("Failed to parse config file: {0}")]
+    ParseError(#[from] toml::de::Error),
+    #[error("Unknown setting '{key}' in the [{binary}] section of the config file")]
+    UnknownSetting { binary: Binary, key: String },
+    #[error("Setting '{key}' must be a string, number or boolean")]
+    UnsupportedValue { key: String },
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ConfigFile {
+    pub tui: Settings,
+    pub mirror_match: Settings,
+}
+
+impl FromStr for ConfigFile {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(toml::from_str(s)?)
+    }
+}
+
+impl ConfigFile {
+    pub fn load(path: impl AsRef<Path>) -> Result<ConfigFile, ConfigError> {
+        let path = path.as_ref();
+        fs::read_to_string(path)
+            .map_err(|e| ConfigError::ReadFileError(path.display().to_string(), e))?
+            .parse()
+    }
+
+    /// Load the file `CHIVE_CONFIG` points at, or `chive.toml` in the working directory if the
+    /// variable isn't set. A missing `chive.toml` is an empty config, but a missing file that was
+    /// asked for by name is an error.
+    pub fn find() -> Result<ConfigFile, ConfigError> {
+        match env::var_os(CONFIG_PATH_VARIABLE) {
+            Some(path) => ConfigFile::load(PathBuf::from(path)),
+            None if Path::new(CONFIG_FILE_NAME).exists() => ConfigFile::load(CONFIG_FILE_NAME),
+            None => Ok(ConfigFile::default()),
+        }
+    }
+
+    pub fn settings(&self, binary: Binary) -> &Settings {
+        match binary {
+            Binary::Tui => &self.tui,
+            Binary::MirrorMatch => &self.mirror_match,
+        }
+    }
+
+    /// Replace the built-in defaults of `command`'s flags with the ones in `binary`'s section.
+    /// Values go through the same parsing as the command line, so `"5s"` or `"white"` mean the
+    /// same thing in both places.
+    pub fn apply_defaults(&self, binary: Binary, command: Command) -> Result<Command, ConfigError> {
+        let mut command = command;
+        for (key, value) in self.settings(binary) {
+            let id = command
+                .get_arguments()
+                .find(|arg| arg.get_long() == Some(key.as_str()))
+                .ok_or_else(|| ConfigError::UnknownSetting {
+                    binary,
+                    key: key.clone(),
+                })?
+                .get_id()
+                .clone();
+            let value = match value {
+                toml::Value::String(value) => value.clone(),
+                toml::Value::Integer(_) | toml::Value::Float(_) | toml::Value::Boolean(_) => {
+                    value.to_string()
+                }
+                _ => return Err(ConfigError::UnsupportedValue { key: key.clone() }),
+            };
+            command = command.mut_arg(id, |arg| arg.default_value(OsStr::from(value)));
+        }
+        Ok(command)
+    }
+}
+
+/// Parse the command line of `binary`, with defaults from the config file. Exits with a usage
+/// message if the flags are invalid, like `Parser::parse`.
+pub fn parse_args<T: Parser>(binary: Binary) -> Result<T, ConfigError> {
+    let command = ConfigFile::find()?.apply_defaults(binary, T::command())?;
+    Ok(T::from_arg_matches(&command.get_matches()).unwrap_or_else(|e| e.exit()))
+}
+
+/// Value parser for an `--evaluator` flag, taking the name of one of `EVALUATORS`. The names and
+/// descriptions show up in `--help`, and an unknown name is rejected with the list of known ones.
+pub fn evaluator_parser() -> impl TypedValueParser<Value = PiecesAroundQueenAndAvailableMoves> {
+    PossibleValuesParser::new(
+        EVALUATORS
+            .iter()
+            .map(|evaluator| PossibleValue::new(evaluator.name).help(evaluator.description)),
+    )
+    .map(|name| evaluator_named(&name).expect("only known names get past the possible values"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{CommandFactory, FromArgMatches};
+    use std::time::Duration;
+
+    #[derive(Debug, Parser)]
+    struct Args {
+        #[clap(value_parser = humantime::parse_duration, default_value = "5s")]
+        #[arg(short, long)]
+        pondering_time: Duration,
+
+        #[clap(default_value = "10")]
+        #[arg(long)]
+        games: usize,
+
+        #[arg(long)]
+        transcripts: bool,
+    }
+
+    fn parse(config: &str, args: &[&str]) -> Result<Args, ConfigError> {
+        let config: ConfigFile = config.parse()?;
+        let command = config.apply_defaults(Binary::Tui, Args::command())?;
+        let matches = command
+            .try_get_matches_from(["chive"].iter().chain(args))
+            .unwrap();
+        Ok(Args::from_arg_matches(&matches).unwrap())
+    }
+
+    #[test]
+    fn command_line_beats_config_file_beats_built_in_default() {
+        let config = r#"
+            [tui]
+            pondering-time = "1m"
+            transcripts = true
+
+            [mirror-match]
+            games = 3
+        "#;
+
+        let args = parse(config, &[]).unwrap();
+        assert_eq!(args.pondering_time, Duration::from_secs(60));
+        assert!(args.transcripts);
+        // Only the [tui] section applies
+        assert_eq!(args.games, 10);
+
+        let args = parse(config, &["--pondering-time", "2s"]).unwrap();
+        assert_eq!(args.pondering_time, Duration::from_secs(2));
+
+        let args = parse("", &[]).unwrap();
+        assert_eq!(args.pondering_time, Duration::from_secs(5));
+        assert!(!args.transcripts);
+    }
+
+    #[test]
+    fn rejects_unknown_settings() {
+        assert!(matches!(
+            parse("[tui]\npondering-tim = \"1s\"", &[]),
+            Err(ConfigError::UnknownSetting { .. })
+        ));
+        assert!(matches!(
+            parse("[tui]\ngames = [1, 2]", &[]),
+            Err(ConfigError::UnsupportedValue { .. })
+        ));
+        assert!(matches!(
+            parse("[chess]\ngames = 1", &[]),
+            Err(ConfigError::ParseError(_))
+        ));
+    }
+}
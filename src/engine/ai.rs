@@ -1,61 +1,597 @@
 use crate::engine::bug::Bug;
 use crate::engine::game::{Game, GameResult, Turn};
+use crate::engine::hex::{Hex, flat_distance};
+use crate::engine::hive::Color;
+use itertools::Itertools;
 use minimax::{
-    Evaluation, Evaluator, IterativeOptions, ParallelOptions, ParallelSearch, Strategy, Winner,
+    BEST_EVAL, Evaluation, Evaluator, IterativeOptions, ParallelOptions, ParallelSearch, Strategy,
+    WORST_EVAL, Winner,
 };
+use rand::SeedableRng;
+use rand::prelude::IndexedRandom;
+use rand::rngs::StdRng;
 use rustc_hash::FxHashMap;
-use std::time::Duration;
-use strum::Display;
+use std::cell::Cell;
+use std::cmp::{Reverse, max};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 use thiserror::Error;
-use AiError::RanOutOfTime;
 
-#[derive(Error, Debug, Display)]
+#[derive(Error, Debug, Eq, PartialEq)]
 pub enum AiError {
-    RanOutOfTime,
+    /// The search ran out of pondering time before finishing even its shallowest depth
+    #[error("AI ran out of time before finding a turn")]
+    Timeout,
+    /// The game is already over, so there is no turn to take
+    #[error("AI has no turns to choose from, the game is over")]
+    NoMoves,
+    /// The search returned a turn that isn't legal in the current position
+    #[error("AI search returned an illegal turn: {0:?}")]
+    SearchFailed(Turn),
+}
+
+/// No evaluator in `EVALUATORS` has the given name
+#[derive(Error, Debug, Eq, PartialEq)]
+#[error("Unknown evaluator '{name}', expected one of: {known}")]
+pub struct UnknownEvaluatorError {
+    pub name: String,
+    pub known: String,
+}
+
+/// How the AI picks between turns that score the same. Turns are tied when searching them as deep
+/// as the search that picked the best turn gives them the same score.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum TieBreak {
+    /// Take whichever turn the search returned, which makes the AI deterministic
+    #[default]
+    First,
+    /// Pick randomly among the tied turns, seeded so games can be replayed
+    Random { seed: u64 },
+    /// Prefer placing new pieces over moving old ones, then turns closer to the middle of the hive
+    Developing,
+}
+
+/// How long the AI thinks about each turn
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum TimeManagement {
+    /// Always start with the default pondering time
+    #[default]
+    Fixed,
+    /// Scale the default pondering time by how complicated the position is, staying between `min`
+    /// and `max`. Positions with more turns to choose from get more time, as does the middle of
+    /// the game, and a forced turn gets `min`. Timed games also keep each turn to a tenth of the
+    /// clock that's left.
+    Scaled { min: Duration, max: Duration },
+}
+
+/// Vary the AI's openings by picking randomly among its best few turns early in the game, for
+/// generating a spread of self-play games. Unlike `TieBreak::Random`, which only picks between
+/// turns that score exactly the same, this deliberately plays turns that score a bit worse.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct RandomOpening {
+    /// How many plies to randomize. A `Game` doesn't keep its history, so plies are counted by the
+    /// number of pieces on the board, which matches while both players are still placing.
+    pub plies: usize,
+    /// How many of the best scoring turns to pick between, judged by the evaluator one ply deep
+    pub top: usize,
+    pub seed: u64,
+}
+
+/// How much work the AI put into choosing one turn, for finding the positions where the search
+/// blows up
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct SearchStats {
+    /// How many positions the search evaluated
+    pub nodes: u64,
+    /// How many plies deep the search looked. For the timed search this is the length of its
+    /// principal variation, which is short of the iteration it reached when the game ends sooner.
+    pub depth: usize,
+    pub time: Duration,
+    /// The search ran out of time without a turn, so the AI gave up with `AiError::Timeout`
+    pub timed_out: bool,
+}
+
+/// The AI's evaluator as the search sees it once `with_search_stats` is on, counting every
+/// position it evaluates. Normal play searches with the bare evaluator so it doesn't pay for this.
+#[derive(Clone)]
+struct CountingEvaluator {
+    evaluator: PiecesAroundQueenAndAvailableMoves,
+    nodes: Arc<AtomicU64>,
+}
+
+impl Evaluator for CountingEvaluator {
+    type G = HiveGame;
+
+    fn evaluate(&self, s: &<Self::G as minimax::Game>::S) -> Evaluation {
+        self.nodes.fetch_add(1, Ordering::Relaxed);
+        self.evaluator.evaluate(s)
+    }
+}
+
+/// Which search the AI runs, kept so `with_search_stats` can build it again around a
+/// `CountingEvaluator`
+#[derive(Debug, Clone, Copy)]
+enum SearchKind {
+    /// Iterative deepening on every core until the pondering time runs out
+    Parallel,
+    /// `FixedDepthSearch`, for `Ai::deterministic`
+    FixedDepth { depth: u8, seed: u64 },
+}
+
+impl SearchKind {
+    fn strategy<E>(self, evaluator: E) -> Box<dyn Strategy<HiveGame>>
+    where
+        E: Evaluator<G = HiveGame> + Clone + Send + Sync + 'static,
+    {
+        match self {
+            SearchKind::Parallel => Box::new(ParallelSearch::new(
+                evaluator,
+                IterativeOptions::new(),
+                ParallelOptions::new(),
+            )),
+            SearchKind::FixedDepth { depth, seed } => Box::new(FixedDepthSearch {
+                evaluator,
+                depth,
+                seed,
+                deadline: None,
+                timed_out: Cell::default(),
+            }),
+        }
+    }
 }
 
 pub struct Ai {
     default_pondering_time: Duration,
     max_pondering_time: Duration,
-    strategy: ParallelSearch<PiecesAroundQueenAndAvailableMoves>,
+    time_management: TimeManagement,
+    strategy: Box<dyn Strategy<HiveGame>>,
+    evaluator: PiecesAroundQueenAndAvailableMoves,
+    tie_break: TieBreak,
+    rng: StdRng,
+    random_opening: Option<RandomOpening>,
+    opening_rng: StdRng,
+    search: SearchKind,
+    nodes: Arc<AtomicU64>,
+    search_stats: Option<Vec<SearchStats>>,
 }
 
 impl Ai {
     pub fn new(default_pondering_time: Duration, max_pondering_time: Duration) -> Ai {
+        Self::with_evaluator(
+            default_pondering_time,
+            max_pondering_time,
+            PiecesAroundQueenAndAvailableMoves::default(),
+        )
+    }
+
+    pub fn with_evaluator(
+        default_pondering_time: Duration,
+        max_pondering_time: Duration,
+        evaluator: PiecesAroundQueenAndAvailableMoves,
+    ) -> Ai {
+        Self::with_search(
+            default_pondering_time,
+            max_pondering_time,
+            evaluator,
+            SearchKind::Parallel,
+        )
+    }
+
+    /// An AI that always picks the same turn in the same position, for tests and analysis rather
+    /// than timed play. It searches every line exactly `depth` plies deep on a single thread, with
+    /// no time limit, so anything past a few plies takes a very long time. When several turns score
+    /// the best, `seed` and the position decide between them.
+    pub fn deterministic(seed: u64, depth: u8) -> Ai {
+        Self::with_search(
+            Duration::ZERO,
+            Duration::ZERO,
+            PiecesAroundQueenAndAvailableMoves::default(),
+            SearchKind::FixedDepth { depth, seed },
+        )
+    }
+
+    fn with_search(
+        default_pondering_time: Duration,
+        max_pondering_time: Duration,
+        evaluator: PiecesAroundQueenAndAvailableMoves,
+        search: SearchKind,
+    ) -> Ai {
         Ai {
             default_pondering_time,
             max_pondering_time,
-            strategy: ParallelSearch::new(
-                PiecesAroundQueenAndAvailableMoves {
-                    piece_around_queen_value: 100,
-                    available_move_value: 1,
-                },
-                IterativeOptions::new(),
-                ParallelOptions::new(),
-            ),
+            strategy: search.strategy(evaluator.clone()),
+            evaluator,
+            time_management: TimeManagement::default(),
+            tie_break: TieBreak::default(),
+            rng: StdRng::seed_from_u64(0),
+            random_opening: None,
+            opening_rng: StdRng::seed_from_u64(0),
+            search,
+            nodes: Arc::default(),
+            search_stats: None,
         }
     }
 
-    pub fn choose_turn(&mut self, game: &Game) -> Result<Turn, AiError> {
-        self.strategy.set_timeout(self.default_pondering_time);
-        if let Some(turn) = self.strategy.choose_move(game) {
-            Ok(turn)
+    pub fn with_tie_break(mut self, tie_break: TieBreak) -> Ai {
+        if let TieBreak::Random { seed } = tie_break {
+            self.rng = StdRng::seed_from_u64(seed);
+        }
+        self.tie_break = tie_break;
+        self
+    }
+
+    pub fn with_random_opening(mut self, random_opening: RandomOpening) -> Ai {
+        self.opening_rng = StdRng::seed_from_u64(random_opening.seed);
+        self.random_opening = Some(random_opening);
+        self
+    }
+
+    pub fn with_time_management(mut self, time_management: TimeManagement) -> Ai {
+        self.time_management = time_management;
+        self
+    }
+
+    /// Record `SearchStats` for every turn the AI chooses, and for every time it runs out of time
+    /// before it can choose one. Off by default, since counting nodes slows the search down a little.
+    pub fn with_search_stats(mut self) -> Ai {
+        self.strategy = self.search.strategy(CountingEvaluator {
+            evaluator: self.evaluator.clone(),
+            nodes: self.nodes.clone(),
+        });
+        self.search_stats = Some(vec![]);
+        self
+    }
+
+    /// The stats for each turn chosen so far, oldest first. Empty unless the AI was built
+    /// `with_search_stats`.
+    pub fn search_stats(&self) -> &[SearchStats] {
+        self.search_stats.as_deref().unwrap_or_default()
+    }
+
+    /// How long to search the given position before settling for the best turn so far
+    pub fn pondering_time(&self, game: &Game) -> Duration {
+        let TimeManagement::Scaled { min, max } = self.time_management else {
+            return self.default_pondering_time;
+        };
+
+        let branching_factor = game.branching_factor();
+        let budget = if branching_factor <= 1 {
+            min
         } else {
-            self.strategy
-                .set_timeout(self.max_pondering_time - self.default_pondering_time);
-            self.strategy.choose_move(game).ok_or(RanOutOfTime)
+            // Peaks at 1.5x halfway through the game and falls to 0.5x at either end
+            let phase = game.phase();
+            let phase_scale = 0.5 + 4.0 * phase * (1.0 - phase);
+            // A typical midgame position has around 40 turns to pick from
+            let branching_scale = (branching_factor as f32 / 40.0).sqrt().clamp(0.5, 2.0);
+            self.default_pondering_time
+                .mul_f32(phase_scale * branching_scale)
+                .clamp(min, max)
+        };
+
+        let clock = match game.active_player {
+            Color::White => game.white_clock,
+            Color::Black => game.black_clock,
+        };
+        match clock {
+            Some(clock) => budget.min(clock / 10),
+            None => budget,
+        }
+    }
+
+    pub fn choose_turn(&mut self, game: &Game) -> Result<Turn, AiError> {
+        if game.is_game_over() {
+            return Err(AiError::NoMoves);
+        }
+        let started = Instant::now();
+        self.nodes.store(0, Ordering::Relaxed);
+        // No point searching when the only option is to pass
+        let mut turns = game.turns();
+        if let (Some(Turn::Skip), None) = (turns.next(), turns.next()) {
+            self.record_stats(started, 0, false);
+            return Ok(Turn::Skip);
+        }
+
+        let pondering_time = self.pondering_time(game);
+        self.strategy.set_timeout(pondering_time);
+        let turn = match self.strategy.choose_move(game) {
+            Some(turn) => turn,
+            None => {
+                self.strategy
+                    .set_timeout(self.max_pondering_time.saturating_sub(pondering_time));
+                let turn = self
+                    .strategy
+                    .choose_move(game)
+                    // Even if no depth finished, the search may have a best guess in its table
+                    .or_else(|| self.strategy.principal_variation().first().copied());
+                let Some(turn) = turn else {
+                    self.record_stats(started, self.searched_depth(), true);
+                    return Err(AiError::Timeout);
+                };
+                turn
+            }
+        };
+        let depth = self.searched_depth();
+        self.record_stats(started, depth, false);
+        if !game.turn_is_valid(turn) {
+            return Err(AiError::SearchFailed(turn));
+        }
+        if let Some(opening) = self.random_opening
+            && game.hive().piece_count() < opening.plies
+        {
+            let candidates = self.opening_candidates(game, opening.top);
+            return Ok(*candidates.choose(&mut self.opening_rng).unwrap_or(&turn));
+        }
+        // The timed search has used up its share already, so the tie break only gets what's left
+        let deadline = match self.search {
+            SearchKind::Parallel => Some(started + self.max_pondering_time),
+            SearchKind::FixedDepth { .. } => None,
+        };
+        Ok(self.break_tie(game, turn, depth, deadline))
+    }
+
+    /// How many plies deep the last search looked, see `SearchStats::depth`
+    fn searched_depth(&self) -> usize {
+        match self.search {
+            SearchKind::Parallel => self.strategy.principal_variation().len(),
+            SearchKind::FixedDepth { depth, .. } => depth as usize,
         }
     }
+
+    fn record_stats(&mut self, started: Instant, depth: usize, timed_out: bool) {
+        if let Some(search_stats) = &mut self.search_stats {
+            search_stats.push(SearchStats {
+                nodes: self.nodes.load(Ordering::Relaxed),
+                depth,
+                time: started.elapsed(),
+                timed_out,
+            });
+        }
+    }
+
+    /// The `top` best turns one ply deep, leaving out any that lose on the spot or let the
+    /// opponent win with their next turn
+    fn opening_candidates(&self, game: &Game, top: usize) -> Vec<Turn> {
+        let opponent_wins = |after: &Game| {
+            after.game_result()
+                == GameResult::Winner {
+                    color: game.active_player.opposite(),
+                }
+        };
+        let mut scored = vec![];
+        game.for_each_turn(|turn| {
+            let after = game.with_turn_applied(turn);
+            let score = match after.game_result() {
+                GameResult::None => {
+                    let mut gives_away_win = false;
+                    after.for_each_turn(|reply| {
+                        gives_away_win |= opponent_wins(&after.with_turn_applied(reply))
+                    });
+                    if gives_away_win {
+                        return;
+                    }
+                    -(self.evaluator.evaluate(&after) as i32)
+                }
+                GameResult::Draw => 0,
+                GameResult::Winner { color } if color == game.active_player => i32::MAX,
+                GameResult::Winner { .. } => return,
+            };
+            scored.push((score, turn));
+        });
+        scored.sort_by_key(|(score, _)| Reverse(*score));
+        scored.into_iter().take(top).map(|(_, turn)| turn).collect()
+    }
+
+    fn break_tie(
+        &mut self,
+        game: &Game,
+        best: Turn,
+        depth: usize,
+        deadline: Option<Instant>,
+    ) -> Turn {
+        if self.tie_break == TieBreak::First {
+            return best;
+        }
+
+        let tied = self.tied_turns(game, best, depth, deadline);
+        match self.tie_break {
+            TieBreak::First => best,
+            TieBreak::Random { .. } => *tied.choose(&mut self.rng).unwrap_or(&best),
+            TieBreak::Developing => {
+                let center = hive_center(game);
+                tied.into_iter()
+                    .min_by_key(|turn| match turn {
+                        Turn::Placement { hex, .. } => (0, flat_distance(hex, &center)),
+                        Turn::Move { to, .. } => (1, flat_distance(to, &center)),
+                        Turn::Skip => (2, 0),
+                    })
+                    .unwrap_or(best)
+            }
+        }
+    }
+
+    /// Every turn that scores at least as well as `best` when searched `depth` plies deep,
+    /// including `best` itself. Only turns that already tie with `best` one ply deep get searched,
+    /// but without a transposition table each of those can cost as much as a whole iteration of
+    /// the search that picked `best`. Turns that haven't been confirmed by `deadline` are left out.
+    fn tied_turns(
+        &self,
+        game: &Game,
+        best: Turn,
+        depth: usize,
+        deadline: Option<Instant>,
+    ) -> Vec<Turn> {
+        let one_ply = |turn: Turn| {
+            let after = game.with_turn_applied(turn);
+            match <HiveGame as minimax::Game>::get_winner(&after) {
+                Some(winner) => -winner.evaluate(),
+                None => -self.evaluator.evaluate(&after),
+            }
+        };
+        let search = FixedDepthSearch {
+            evaluator: self.evaluator.clone(),
+            depth: depth.clamp(1, u8::MAX as usize) as u8,
+            seed: 0,
+            deadline,
+            timed_out: Cell::default(),
+        };
+        let best_score = one_ply(best);
+        let best_value = search.turn_value(game, best, WORST_EVAL, BEST_EVAL);
+        let alpha = max(best_value.saturating_sub(1), WORST_EVAL);
+        let mut tied = vec![];
+        game.for_each_turn(|turn| {
+            if turn == best
+                || (!search.timed_out.get()
+                    && one_ply(turn) == best_score
+                    && search.turn_value(game, turn, alpha, best_value) >= best_value
+                    && !search.timed_out.get())
+            {
+                tied.push(turn)
+            }
+        });
+        tied
+    }
 }
 
-struct HiveGame;
+/// Alpha-beta search to a fixed depth. The searches in `minimax` shuffle the turns at the root
+/// before looking at them, so they can answer differently each time even single threaded.
+struct FixedDepthSearch<E> {
+    evaluator: E,
+    depth: u8,
+    seed: u64,
+    /// Stop searching once this passes. The scores found after that are meaningless, so
+    /// `timed_out` gets set for the caller to throw them away.
+    deadline: Option<Instant>,
+    timed_out: Cell<bool>,
+}
+
+impl<E: Evaluator<G = HiveGame>> FixedDepthSearch<E> {
+    fn negamax(
+        &self,
+        game: &Game,
+        depth: u8,
+        mut alpha: Evaluation,
+        beta: Evaluation,
+    ) -> Evaluation {
+        if let Some(winner) = <HiveGame as minimax::Game>::get_winner(game) {
+            // Win as soon as possible and lose as late as possible
+            let plies = (self.depth - depth) as Evaluation;
+            return match winner {
+                Winner::PlayerToMove => BEST_EVAL - plies,
+                Winner::PlayerJustMoved => WORST_EVAL + plies,
+                Winner::Draw => 0,
+            };
+        }
+        if depth == 0 {
+            return self.evaluator.evaluate(game);
+        }
+        if self.out_of_time() {
+            return 0;
+        }
+        let mut turns = vec![];
+        <HiveGame as minimax::Game>::generate_moves(game, &mut turns);
+        let mut best = WORST_EVAL;
+        for turn in turns {
+            let after = game.with_turn_applied(turn);
+            let value = -self.negamax(&after, depth - 1, -beta, -alpha);
+            best = max(best, value);
+            alpha = max(alpha, value);
+            if alpha >= beta {
+                break;
+            }
+        }
+        best
+    }
+
+    /// Whether `deadline` has passed, remembering it in `timed_out` once it has
+    fn out_of_time(&self) -> bool {
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            self.timed_out.set(true);
+        }
+        self.timed_out.get()
+    }
+
+    /// The score of taking `turn`, searched the rest of the way down. Only exact when it falls
+    /// between `alpha` and `beta`, otherwise it's a bound on the side it fell out on.
+    fn turn_value(
+        &self,
+        game: &Game,
+        turn: Turn,
+        alpha: Evaluation,
+        beta: Evaluation,
+    ) -> Evaluation {
+        let after = game.with_turn_applied(turn);
+        -self.negamax(&after, self.depth - 1, -beta, -alpha)
+    }
+}
+
+impl<E: Evaluator<G = HiveGame>> Strategy<HiveGame> for FixedDepthSearch<E> {
+    fn choose_move(&mut self, game: &Game) -> Option<Turn> {
+        if self.depth == 0 || <HiveGame as minimax::Game>::get_winner(game).is_some() {
+            return None;
+        }
+        let mut turns = vec![];
+        <HiveGame as minimax::Game>::generate_moves(game, &mut turns);
+
+        // Search each turn with a window just below the best score so far, so every turn that ties
+        // it gets an exact score
+        let mut best = WORST_EVAL;
+        let mut tied = vec![];
+        for turn in turns {
+            let alpha = max(best.saturating_sub(1), WORST_EVAL);
+            let value = self.turn_value(game, turn, alpha, BEST_EVAL);
+            if value > best {
+                best = value;
+                tied.clear();
+            }
+            if value == best {
+                tied.push(turn);
+            }
+        }
+        let mut rng = StdRng::seed_from_u64(self.seed ^ game.zobrist_hash.value());
+        tied.choose(&mut rng).copied()
+    }
+}
+
+/// The average position of the pieces in the hive, rounded to the nearest hex
+fn hive_center(game: &Game) -> Hex {
+    let count = game.hive().map.len().max(1) as f32;
+    let (q, r) = game.hive().map.keys().fold((0.0, 0.0), |(q, r), hex| {
+        (q + hex.q as f32, r + hex.r as f32)
+    });
+    let (q, r) = (q / count, r / count);
+    let s = -q - r;
+    // Round each cube coordinate on its own, then recompute whichever one rounding moved the
+    // furthest so the three still add up to zero
+    let (mut rounded_q, mut rounded_r, rounded_s) = (q.round(), r.round(), s.round());
+    let (q_diff, r_diff, s_diff) = (
+        (rounded_q - q).abs(),
+        (rounded_r - r).abs(),
+        (rounded_s - s).abs(),
+    );
+    if q_diff > r_diff && q_diff > s_diff {
+        rounded_q = -rounded_r - rounded_s;
+    } else if r_diff > s_diff {
+        rounded_r = -rounded_q - rounded_s;
+    }
+    Hex {
+        q: rounded_q as i32,
+        r: rounded_r as i32,
+        h: 0,
+    }
+}
+
+pub struct HiveGame;
 
 impl minimax::Game for HiveGame {
     type S = Game;
     type M = Turn;
 
     fn generate_moves(state: &Self::S, moves: &mut Vec<Self::M>) {
-        moves.extend(state.turns())
+        state.for_each_turn(|turn| moves.push(turn))
     }
 
     fn apply(state: &mut Self::S, m: Self::M) -> Option<Self::S> {
@@ -81,10 +617,20 @@ impl minimax::Game for HiveGame {
     }
 }
 
-#[derive(Clone)]
-struct PiecesAroundQueenAndAvailableMoves {
+#[derive(Debug, Clone)]
+pub struct PiecesAroundQueenAndAvailableMoves {
     pub piece_around_queen_value: i16,
     pub available_move_value: i16,
+    /// Reward for each empty square your own queen could still slide into
+    pub queen_escape_value: i16,
+    /// Penalty for having your queen pinned under a beetle, which is usually close to a loss
+    pub covered_queen_value: i16,
+    /// Reward for each piece more than the opponent you have on the board. Scaled down as the game
+    /// goes on so it only matters in the opening.
+    pub development_value: i16,
+    /// Reward for each empty square around the enemy queen that it can't slide into, and penalty
+    /// for each one around your own. Off by default until it's been tuned with `mirror_match`.
+    pub blocked_escape_value: i16,
 }
 
 impl Default for PiecesAroundQueenAndAvailableMoves {
@@ -92,22 +638,52 @@ impl Default for PiecesAroundQueenAndAvailableMoves {
         Self {
             piece_around_queen_value: 100,
             available_move_value: 1,
+            queen_escape_value: 20,
+            covered_queen_value: 400,
+            development_value: 10,
+            blocked_escape_value: 0,
         }
     }
 }
+
+impl PiecesAroundQueenAndAvailableMoves {
+    fn queen_safety(&self, s: &Game, color: Color) -> i16 {
+        if s.queen_is_covered(color) {
+            return -self.covered_queen_value;
+        }
+        s.queen_escape_squares(color).unwrap_or(0) as i16 * self.queen_escape_value
+    }
+
+    fn blocked_escapes(&self, s: &Game) -> i16 {
+        if self.blocked_escape_value == 0 {
+            return 0;
+        }
+        let own = s
+            .queen_escapes(s.active_player)
+            .map_or(0, |escapes| escapes.blocked.len());
+        (s.enemy_queen_escape_count() as i16 - own as i16) * self.blocked_escape_value
+    }
+
+    fn development(&self, s: &Game) -> i16 {
+        let lead = s.hive().piece_count_for(s.active_player) as f32
+            - s.hive().piece_count_for(s.active_player.opposite()) as f32;
+        (lead * self.development_value as f32 * (1.0 - s.phase())).round() as i16
+    }
+}
+
 impl Evaluator for PiecesAroundQueenAndAvailableMoves {
     type G = HiveGame;
 
     fn evaluate(&self, s: &<Self::G as minimax::Game>::S) -> Evaluation {
         let statuses: FxHashMap<_, _> = s
-            .hive
+            .hive()
             .map
             .iter()
             .filter(|(_, tile)| tile.bug == Bug::Queen)
             .map(|(hex, tile)| {
                 (
                     tile.color,
-                    s.hive.occupied_neighbors_at_same_level(hex).count() as i16,
+                    s.hive().occupied_neighbors_at_same_level(hex).count() as i16,
                 )
             })
             .collect();
@@ -115,9 +691,468 @@ impl Evaluator for PiecesAroundQueenAndAvailableMoves {
         let inactive_player_pieces_around_queen =
             *statuses.get(&s.active_player.opposite()).unwrap_or(&0);
         let active_player_pieces_around_queen = *statuses.get(&s.active_player).unwrap_or(&0);
-        let active_player_available_moves = s.turns().count() as i16;
+        let mut active_player_available_moves = 0;
+        s.for_each_turn(|_| active_player_available_moves += 1);
         (inactive_player_pieces_around_queen - active_player_pieces_around_queen)
             * self.piece_around_queen_value
             + active_player_available_moves * self.available_move_value
+            + self.queen_safety(s, s.active_player)
+            - self.queen_safety(s, s.active_player.opposite())
+            + self.development(s)
+            + self.blocked_escapes(s)
+    }
+}
+
+/// An evaluator that can be picked by name at runtime, for comparing heuristics without
+/// recompiling
+pub struct NamedEvaluator {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub build: fn() -> PiecesAroundQueenAndAvailableMoves,
+}
+
+/// Every evaluator that can be picked by name, the first being the one the AI uses by default
+pub const EVALUATORS: &[NamedEvaluator] = &[
+    NamedEvaluator {
+        name: "default",
+        description: "The standard weights",
+        build: PiecesAroundQueenAndAvailableMoves::default,
+    },
+    NamedEvaluator {
+        name: "surround",
+        description: "Only counts the pieces around each queen",
+        build: || PiecesAroundQueenAndAvailableMoves {
+            available_move_value: 0,
+            queen_escape_value: 0,
+            covered_queen_value: 0,
+            development_value: 0,
+            blocked_escape_value: 0,
+            ..Default::default()
+        },
+    },
+    NamedEvaluator {
+        name: "weighted-mobility",
+        description: "Like default, but values having turns to play and room for the queen more",
+        build: || PiecesAroundQueenAndAvailableMoves {
+            available_move_value: 5,
+            queen_escape_value: 50,
+            ..Default::default()
+        },
+    },
+];
+
+/// Build the evaluator in `EVALUATORS` with the given name
+pub fn evaluator_named(
+    name: &str,
+) -> Result<PiecesAroundQueenAndAvailableMoves, UnknownEvaluatorError> {
+    EVALUATORS
+        .iter()
+        .find(|evaluator| evaluator.name == name)
+        .map(|evaluator| (evaluator.build)())
+        .ok_or_else(|| UnknownEvaluatorError {
+            name: name.to_string(),
+            known: EVALUATORS.iter().map(|evaluator| evaluator.name).join(", "),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::hex::is_adjacent;
+    use crate::engine::hive::{Hive, HiveBuilder};
+    use rustc_hash::FxHashSet;
+
+    #[test]
+    fn covered_queen_scores_worse_than_open_queen() {
+        let covered = Game::from_map_str(
+            r#"
+        Layer 0
+            .  a  .
+             .  Q  q
+        Layer 1
+            .  .  .
+             .  b  .
+        "#,
+        )
+        .unwrap();
+        let open = Game::from_map_str(
+            r#"
+        Layer 0
+            .  a  b
+             .  Q  q
+        "#,
+        )
+        .unwrap();
+
+        let evaluator = PiecesAroundQueenAndAvailableMoves::default();
+        assert!(evaluator.evaluate(&covered) < evaluator.evaluate(&open) - 300);
+    }
+
+    #[test]
+    fn development_prefers_placing_over_shuffling() {
+        let game = Game::from_map_str(
+            r#"
+            .  A  Q  q  a
+            "#,
+        )
+        .unwrap();
+        let evaluator = PiecesAroundQueenAndAvailableMoves {
+            piece_around_queen_value: 0,
+            available_move_value: 0,
+            queen_escape_value: 0,
+            covered_queen_value: 0,
+            ..Default::default()
+        };
+        let placed = game.with_turn_applied(game.placement_turns().next().unwrap());
+        let shuffled = game.with_turn_applied(game.movement_turns().next().unwrap());
+
+        // Evaluations are from the point of view of the player to move, black after either turn
+        assert!(evaluator.evaluate(&placed) < evaluator.evaluate(&shuffled));
+        assert!(evaluator.development(&placed) < 0);
+        assert_eq!(evaluator.development(&shuffled), 0);
+    }
+
+    #[test]
+    fn looks_up_evaluators_by_name() {
+        assert_eq!(
+            EVALUATORS.iter().map(|e| e.name).unique().count(),
+            EVALUATORS.len()
+        );
+        for evaluator in EVALUATORS {
+            assert!(evaluator_named(evaluator.name).is_ok());
+        }
+        assert_eq!(evaluator_named("surround").unwrap().available_move_value, 0);
+
+        let error = evaluator_named("surrond").unwrap_err();
+        assert_eq!(error.name, "surrond");
+        assert!(error.to_string().contains("weighted-mobility"));
+    }
+
+    #[test]
+    fn deterministic_ai_repeats_itself() {
+        let game = Game::from_map_str(
+            r#"
+            .  A  G  .
+             .  Q  q  b
+            "#,
+        )
+        .unwrap();
+        let choose = |seed| Ai::deterministic(seed, 2).choose_turn(&game).unwrap();
+
+        let turn = choose(3);
+        assert!(game.turn_is_valid(turn));
+        assert_eq!(choose(3), turn);
+        let mut ai = Ai::deterministic(3, 2);
+        assert_eq!(ai.choose_turn(&game).unwrap(), turn);
+        assert_eq!(ai.choose_turn(&game).unwrap(), turn);
+    }
+
+    #[test]
+    fn search_stats_are_opt_in() {
+        let game = Game::from_map_str(
+            r#"
+            .  A  G  .
+             .  Q  q  b
+            "#,
+        )
+        .unwrap();
+        let mut ai = Ai::deterministic(3, 2);
+        ai.choose_turn(&game).unwrap();
+        assert!(ai.search_stats().is_empty());
+
+        let mut ai = Ai::deterministic(3, 2).with_search_stats();
+        ai.choose_turn(&game).unwrap();
+        ai.choose_turn(&game).unwrap();
+        let stats = ai.search_stats();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].depth, 2);
+        // At least one reply to every turn gets evaluated
+        assert!(stats[0].nodes >= game.turns().count() as u64);
+        assert_eq!(stats[0].nodes, stats[1].nodes);
+
+        let forced_pass = Game::from_hive_with_reserves(
+            ".  a  Q  a  .".parse().unwrap(),
+            Color::White,
+            vec![],
+            vec![],
+        );
+        ai.choose_turn(&forced_pass).unwrap();
+        assert_eq!(ai.search_stats()[2].nodes, 0);
+        assert!(!ai.search_stats()[2].timed_out);
+
+        // A search with no depth to it never finds a turn, like one that runs out of time straight
+        // away, and still gets recorded
+        let mut ai = Ai::deterministic(3, 0).with_search_stats();
+        assert_eq!(ai.choose_turn(&game), Err(AiError::Timeout));
+        assert_eq!(ai.search_stats().len(), 1);
+        assert!(ai.search_stats()[0].timed_out);
+    }
+
+    #[test]
+    fn ai_wins_with_pillbug_and_mosquito_pushes() {
+        // The only way to fill the hole next to black's queen is to push white's queen into it,
+        // first with the pillbug and then with a mosquito copying a pillbug that isn't next to it
+        for map in [
+            r#"
+             .  a  a  .
+              g  q  g  Q
+             .  a  .  P
+              .  .  a  .
+            "#,
+            r#"
+             .  a  a  .
+              g  q  g  Q
+             .  a  .  M
+              .  .  a  P
+            "#,
+        ] {
+            let game =
+                Game::from_hive_with_reserves(map.parse().unwrap(), Color::White, vec![], vec![]);
+            let mut turns = vec![];
+            <HiveGame as minimax::Game>::generate_moves(&game, &mut turns);
+            assert!(game.ability_turns().iter().all(|push| turns.contains(push)));
+
+            let turn = Ai::deterministic(0, 1).choose_turn(&game).unwrap();
+            assert!(game.ability_turns().contains(&turn));
+            assert_eq!(
+                game.with_turn_applied(turn).game_result(),
+                GameResult::Winner {
+                    color: Color::White
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn deterministic_ai_plays_forced_wins() {
+        let hive: Hive = r#"
+             Q  A  G  .
+              B  q  G  .
+             .  S  .  A
+            "#
+        .parse()
+        .unwrap();
+        let game = Game::from_hive_with_reserves(hive, Color::White, vec![], vec![]);
+        let line = game.forced_win_in(Color::White, 3).unwrap();
+
+        for seed in 0..3 {
+            let turn = Ai::deterministic(seed, 3).choose_turn(&game).unwrap();
+            assert_eq!(turn, line[0]);
+        }
+    }
+
+    #[test]
+    fn random_tie_break_is_repeatable() {
+        let game = Game::from_map_str(
+            r#"
+            .  A  .
+             .  Q  q
+            "#,
+        )
+        .unwrap();
+        let ai = || {
+            Ai::new(Duration::from_millis(10), Duration::from_millis(20))
+                .with_tie_break(TieBreak::Random { seed: 7 })
+        };
+        let best = game.turns().next().unwrap();
+        let tied = ai().tied_turns(&game, best, 1, None);
+
+        let choice = ai().break_tie(&game, best, 1, None);
+        assert!(tied.contains(&best));
+        assert!(tied.contains(&choice));
+        assert_eq!(choice, ai().break_tie(&game, best, 1, None));
+    }
+
+    #[test]
+    fn developing_tie_break_prefers_placements() {
+        let game = Game::from_map_str(
+            r#"
+            .  A  .
+             .  Q  q
+            "#,
+        )
+        .unwrap();
+        let mut ai = Ai::new(Duration::from_millis(10), Duration::from_millis(20))
+            .with_tie_break(TieBreak::Developing);
+        let best = game.placement_turns().next().unwrap();
+
+        let choice = ai.break_tie(&game, best, 1, None);
+        assert!(matches!(choice, Turn::Placement { .. }));
+        assert!(ai.tied_turns(&game, best, 1, None).contains(&choice));
+    }
+
+    #[test]
+    fn tie_break_stays_within_the_pondering_time() {
+        let game = Game::from_uhp("Base+MLP", &["wS1", "bG1 wS1-", "wQ -wS1", "bQ bG1-"]).unwrap();
+        let max_pondering_time = Duration::from_millis(200);
+        for tie_break in [TieBreak::Random { seed: 3 }, TieBreak::Developing] {
+            let mut ai =
+                Ai::new(Duration::from_millis(180), max_pondering_time).with_tie_break(tie_break);
+            let started = Instant::now();
+            ai.choose_turn(&game).unwrap();
+            // Leave a little room for the search to notice its time is up
+            assert!(
+                started.elapsed() < max_pondering_time + Duration::from_millis(50),
+                "{tie_break:?} took {:?}",
+                started.elapsed()
+            );
+        }
+    }
+
+    #[test]
+    fn tied_turns_give_up_at_the_deadline() {
+        let game = Game::from_uhp("Base+MLP", &["wS1", "bG1 wS1-", "wQ -wS1", "bQ bG1-"]).unwrap();
+        let ai = Ai::new(Duration::from_millis(10), Duration::from_millis(20));
+        let best = game.turns().next().unwrap();
+        // Searching every turn 6 plies deep would take minutes
+        let started = Instant::now();
+        let tied = ai.tied_turns(&game, best, 6, Some(started + Duration::from_millis(50)));
+        assert!(started.elapsed() < Duration::from_millis(150));
+        assert!(tied.contains(&best));
+    }
+
+    #[test]
+    fn tied_turns_hold_up_as_deep_as_the_search() {
+        // Most of black's placements look the same one ply deep, but not once white gets to reply
+        let hive: Hive = r#"
+              a  .  .  .
+             .  q  .  .
+              b  m  .  .
+             .  .  M  .
+              .  .  S  A
+             .  .  Q  .
+              .  P  .  .
+            "#
+        .parse()
+        .unwrap();
+        let game = Game::from_hive(hive, Color::Black);
+        let best = Ai::deterministic(0, 2).choose_turn(&game).unwrap();
+        let ai = Ai::new(Duration::from_millis(10), Duration::from_millis(20));
+        let one_ply = ai.tied_turns(&game, best, 1, None);
+        let searched = ai.tied_turns(&game, best, 2, None);
+        assert!(searched.contains(&best));
+        assert!(searched.len() < one_ply.len());
+
+        let search = FixedDepthSearch {
+            evaluator: PiecesAroundQueenAndAvailableMoves::default(),
+            depth: 2,
+            seed: 0,
+            deadline: None,
+            timed_out: Cell::default(),
+        };
+        let value = |turn| search.turn_value(&game, turn, WORST_EVAL, BEST_EVAL);
+        for turn in one_ply {
+            assert_eq!(searched.contains(&turn), value(turn) >= value(best));
+        }
+    }
+
+    #[test]
+    fn hive_center_rounds_to_the_nearest_hex() {
+        let hive = HiveBuilder::new()
+            .place(0, 0, 0, Bug::Queen, Color::White)
+            .place(-1, 0, 0, Bug::Queen, Color::Black)
+            .place(-1, 1, 0, Bug::Ant, Color::White)
+            .build()
+            .unwrap();
+        let game = Game::from_hive(hive, Color::Black);
+        // The average is (-2/3, 1/3), which truncating would put at (0, 0)
+        assert_eq!(hive_center(&game), Hex { q: -1, r: 0, h: 0 });
+    }
+
+    #[test]
+    fn random_opening_varies_between_seeds() {
+        let game = Game::default();
+        let choices: FxHashSet<Turn> = (0..8)
+            .map(|seed| {
+                let mut ai = Ai::new(Duration::from_millis(10), Duration::from_millis(20))
+                    .with_random_opening(RandomOpening {
+                        plies: 4,
+                        top: 3,
+                        seed,
+                    });
+                ai.choose_turn(&game).unwrap()
+            })
+            .collect();
+        let ai = Ai::new(Duration::from_millis(10), Duration::from_millis(20));
+        let top = ai.opening_candidates(&game, 3);
+
+        assert_eq!(top.len(), 3);
+        assert!(choices.len() > 1);
+        assert!(choices.iter().all(|turn| top.contains(turn)));
+    }
+
+    #[test]
+    fn random_opening_avoids_losing_turns() {
+        // The ant can fill the last hex around its own queen
+        let game = Game::from_map_str(
+            r#"
+            .  a  a  .
+             g  Q  g
+            .  a  .  A
+            "#,
+        )
+        .unwrap();
+        let ant = game.hive().pieces_of(Bug::Ant, Color::White)[0];
+        let queen = game.hive().pieces_of(Bug::Queen, Color::White)[0];
+        let ai = Ai::new(Duration::from_millis(10), Duration::from_millis(20));
+
+        assert!(
+            game.moves_for_piece(&ant)
+                .any(|turn| matches!(turn, Turn::Move { to, .. } if is_adjacent(&to, &queen)))
+        );
+        let candidates = ai.opening_candidates(&game, usize::MAX);
+        assert!(!candidates.is_empty());
+        assert!(candidates.iter().all(|turn| match turn {
+            Turn::Move { from, to, .. } => *from != ant || !is_adjacent(to, &queen),
+            _ => true,
+        }));
+    }
+
+    #[test]
+    fn no_moves_once_game_is_over() {
+        let game = Game::from_map_str(
+            r#"
+            .  a  a  .
+             g  Q  g
+            .  a  g  .
+            "#,
+        )
+        .unwrap();
+        assert_ne!(game.game_result(), GameResult::None);
+
+        let mut ai = Ai::new(Duration::from_millis(10), Duration::from_millis(20));
+        assert_eq!(ai.choose_turn(&game), Err(AiError::NoMoves));
+    }
+
+    #[test]
+    fn scaled_time_stays_in_bounds() {
+        let min = Duration::from_millis(100);
+        let max = Duration::from_secs(2);
+        let ai = Ai::new(Duration::from_secs(1), Duration::from_secs(3))
+            .with_time_management(TimeManagement::Scaled { min, max });
+
+        let opening = Game::default();
+        let opening_time = ai.pondering_time(&opening);
+        assert!(opening_time >= min && opening_time < Duration::from_secs(1));
+
+        let forced_pass = Game::from_hive_with_reserves(
+            r#"
+            .  a  Q  a  .
+            "#
+            .parse()
+            .unwrap(),
+            Color::White,
+            vec![],
+            vec![],
+        );
+        assert_eq!(forced_pass.branching_factor(), 1);
+        assert_eq!(ai.pondering_time(&forced_pass), min);
+
+        let mut timed = Game::default();
+        timed.white_clock = Some(Duration::from_secs(5));
+        assert_eq!(
+            ai.pondering_time(&timed),
+            Duration::from_millis(500).min(opening_time)
+        );
     }
 }
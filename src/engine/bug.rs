@@ -16,6 +16,29 @@ pub enum Bug {
     Pillbug,
 }
 
+impl Bug {
+    /// Whether the bug can end a move on top of the hive. A mosquito can when it copies a beetle.
+    pub fn can_climb(&self) -> bool {
+        matches!(self, Bug::Beetle | Bug::Mosquito)
+    }
+
+    /// Whether the bug can do more than move itself. The pillbug can move its neighbors, and the
+    /// mosquito copies the movement of whatever it's next to.
+    pub fn has_special_ability(&self) -> bool {
+        matches!(self, Bug::Pillbug | Bug::Mosquito)
+    }
+
+    /// How many spaces the bug moves in a turn, or `None` if it isn't fixed. Ants and grasshoppers
+    /// go as far as they like, and a mosquito moves like whatever it copies.
+    pub fn movement_range(&self) -> Option<u8> {
+        match self {
+            Bug::Queen | Bug::Beetle | Bug::Pillbug => Some(1),
+            Bug::Spider | Bug::Ladybug => Some(3),
+            Bug::Ant | Bug::Grasshopper | Bug::Mosquito => None,
+        }
+    }
+}
+
 impl Display for Bug {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -1,4 +1,5 @@
 use crate::engine::hex::{Hex, RotationDegrees};
+use itertools::Itertools;
 use rustc_hash::FxHashMap;
 use std::cmp::{Ordering, min};
 use strum::IntoEnumIterator;
@@ -20,12 +21,30 @@ fn canonicalize_translation<T>(hexes: &mut Vec<(Hex, T)>) {
 }
 
 pub fn canonicalize<T: Clone + Ord>(map: &FxHashMap<Hex, T>) -> FxHashMap<Hex, T> {
+    canonicalize_orientations(map, &[false])
+}
+
+/// Like `canonicalize`, but a map and its mirror image also share a canonical form
+pub fn canonicalize_with_reflection<T: Clone + Ord>(map: &FxHashMap<Hex, T>) -> FxHashMap<Hex, T> {
+    canonicalize_orientations(map, &[false, true])
+}
+
+fn canonicalize_orientations<T: Clone + Ord>(
+    map: &FxHashMap<Hex, T>,
+    reflections: &[bool],
+) -> FxHashMap<Hex, T> {
     let mut best: Option<Vec<(Hex, &T)>> = None;
 
-    for rotation in RotationDegrees::iter() {
+    for (&reflect, rotation) in reflections
+        .iter()
+        .cartesian_product(RotationDegrees::iter())
+    {
         let mut rotated: Vec<(Hex, &T)> = map
             .iter()
-            .map(|(hex, val)| (hex.rotated_by(rotation), val))
+            .map(|(hex, val)| {
+                let hex = if reflect { hex.reflected() } else { *hex };
+                (hex.rotated_by(rotation), val)
+            })
             .collect();
 
         canonicalize_translation(&mut rotated);
@@ -99,5 +118,21 @@ mod test {
 
             assert_eq!(canonicalize(&original_map), canonicalize(&translated_map))
         }
+
+        #[test]
+        fn reflections_do_not_affect_canonical_form_with_reflection(
+            original_map in hex_map_strategy(),
+            rotation in rotation_strategy(),
+        ) {
+            let reflected_map: FxHashMap<Hex, String> = original_map
+                .iter()
+                .map(|(hex, val)| (hex.reflected().rotated_by(rotation), val.clone()))
+                .collect();
+
+            assert_eq!(
+                canonicalize_with_reflection(&original_map),
+                canonicalize_with_reflection(&reflected_map)
+            )
+        }
     }
 }
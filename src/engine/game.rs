@@ -1,20 +1,30 @@
+use crate::engine::ai::Ai;
 use crate::engine::bug::Bug;
+use crate::engine::canonicalizer::{canonicalize, canonicalize_with_reflection};
 use crate::engine::game::Turn::{Move, Placement};
-use crate::engine::hex::{Hex, is_adjacent, neighbors};
+use crate::engine::hex::{Hex, RotationDegrees, flat_distance, is_adjacent, neighbors};
 use crate::engine::hive::{Color, Hive, HiveParseError, Tile};
-use crate::engine::parse::{HexMapParseError, parse_hex_map_string};
+use crate::engine::parse::{HexMapParseError, hex_map_to_highlighted_string, parse_hex_map_string};
 use crate::engine::pathfinding::move_would_break_hive;
+use crate::engine::row_col::{RowColDimensions, bounding_box};
+use crate::engine::uhp::{GameType, UhpError, candidate_turns, format_turn};
 use crate::engine::zobrist::{ZobristHash, ZobristTable};
 use Turn::Skip;
 use itertools::{Either, Itertools};
-use rustc_hash::{FxHashMap, FxHashSet};
+use rustc_hash::{FxHashMap, FxHashSet, FxHasher};
 use std::cmp::max;
+use std::fmt::{self, Debug, Formatter};
+use std::hash::{Hash, Hasher};
 use std::iter;
+use std::sync::OnceLock;
+use std::time::Duration;
+use strum::IntoEnumIterator;
 use thiserror::Error;
 
 #[derive(Clone)]
 pub struct Game {
-    pub hive: Hive,
+    /// Private so nothing can change the board behind the caches below, see `hive()`
+    hive: Hive,
     pub zobrist_table: &'static ZobristTable,
     pub zobrist_hash: ZobristHash,
     pub white_reserve: Vec<Bug>,
@@ -22,6 +32,26 @@ pub struct Game {
     pub active_player: Color,
     pub immobilized_piece: Option<Hex>,
     pub last_turn: Option<Turn>,
+    /// Remaining thinking time for each player, `None` if the game is untimed
+    pub white_clock: Option<Duration>,
+    pub black_clock: Option<Duration>,
+    /// Which copy of its bug each piece on the board is, i.e. the 2 in `wA2`. Numbers are handed
+    /// out in placement order and follow the piece when it moves.
+    pub piece_numbers: FxHashMap<Hex, u8>,
+    /// Generate moves as if pieces could never break the hive. Only set through
+    /// `ignoring_hive_rule`, so no game can drop the rule by accident.
+    ignore_hive_rule: bool,
+    /// Rules variant where a player with no legal turn loses instead of having to pass. With this
+    /// set `turns()` is empty rather than a lone skip, and `game_result()` reports the loss.
+    pub stuck_player_loses: bool,
+    /// The player who conceded the game, if anyone has. The game is over once this is set.
+    pub resigned: Option<Color>,
+    /// Where the queens are and how surrounded they are, kept up to date as turns are applied so
+    /// `game_result()` doesn't have to scan the board
+    pub(crate) queens: QueenSurrounds,
+    /// Where each color could place a piece, worked out the first time it's needed. Only depends
+    /// on the hive, so anything that builds a game with a different hive has to start this over.
+    pub(crate) placement_hexes: PlacementHexes,
 }
 
 #[derive(Debug, Eq, PartialEq, Clone, Copy, Ord, PartialOrd, Hash)]
@@ -38,7 +68,130 @@ pub enum Turn {
     Skip,
 }
 
-#[derive(Debug)]
+impl Turn {
+    /// The same turn on a board rotated about the origin
+    pub fn rotated_by(&self, degrees: RotationDegrees) -> Turn {
+        match *self {
+            Placement { hex, tile } => Placement {
+                hex: hex.rotated_by(degrees),
+                tile,
+            },
+            Move {
+                from,
+                to,
+                freezes_piece,
+            } => Move {
+                from: from.rotated_by(degrees),
+                to: to.rotated_by(degrees),
+                freezes_piece,
+            },
+            Skip => Skip,
+        }
+    }
+
+    /// Pack the turn into 32 bits, for storing or streaming lots of turns. From the top bit down:
+    ///
+    /// - Skip: all zero
+    /// - Placement: `0`, `1`, 11 unused zero bits, the color (1 for black), the bug's index in
+    ///   the order `Bug` declares them (3 bits), then the hex (15 bits)
+    /// - Move: `1`, whether it freezes the piece, the from hex (15 bits), then the to hex (15 bits)
+    ///
+    /// A hex is q and r offset by 32 (6 bits each) followed by h (3 bits), which covers the whole
+    /// board the Zobrist table does. None if a hex is outside that, i.e. q or r outside -32..=31
+    /// or h above 7.
+    pub fn to_u32(&self) -> Option<u32> {
+        let packed = match *self {
+            Skip => 0,
+            Placement { hex, tile } => {
+                let bug = Bug::iter().position(|bug| bug == tile.bug).unwrap() as u32;
+                let color = (tile.color == Color::Black) as u32;
+                1 << 30 | color << 18 | bug << 15 | pack_hex(&hex)?
+            }
+            Move {
+                from,
+                to,
+                freezes_piece,
+            } => 1 << 31 | (freezes_piece as u32) << 30 | pack_hex(&from)? << 15 | pack_hex(&to)?,
+        };
+        Some(packed)
+    }
+
+    /// The inverse of `to_u32`. None if the value isn't one `to_u32` could have produced.
+    pub fn from_u32(value: u32) -> Option<Turn> {
+        if value == 0 {
+            return Some(Skip);
+        }
+        if value >> 31 == 1 {
+            return Some(Move {
+                from: unpack_hex(value >> 15),
+                to: unpack_hex(value),
+                freezes_piece: value >> 30 & 1 == 1,
+            });
+        }
+        if value >> 19 != 1 << 11 {
+            return None;
+        }
+        let tile = Tile {
+            bug: Bug::iter().nth((value >> 15 & 0b111) as usize)?,
+            color: if value >> 18 & 1 == 1 {
+                Color::Black
+            } else {
+                Color::White
+            },
+        };
+        Some(Placement {
+            hex: unpack_hex(value),
+            tile,
+        })
+    }
+}
+
+const HEX_AXIS_OFFSET: i32 = 32;
+
+/// Pack the hex into 15 bits, or None if it's too far out or too high to fit
+fn pack_hex(hex: &Hex) -> Option<u32> {
+    let axis = |value: i32| {
+        let packed = value + HEX_AXIS_OFFSET;
+        (0..64).contains(&packed).then_some(packed as u32)
+    };
+    if !(0..8).contains(&hex.h) {
+        return None;
+    }
+    Some(axis(hex.q)? << 9 | axis(hex.r)? << 3 | hex.h as u32)
+}
+
+/// Unpack the hex in the low 15 bits of `value`
+fn unpack_hex(value: u32) -> Hex {
+    Hex {
+        q: (value >> 9 & 0b11_1111) as i32 - HEX_AXIS_OFFSET,
+        r: (value >> 3 & 0b11_1111) as i32 - HEX_AXIS_OFFSET,
+        h: (value & 0b111) as i32,
+    }
+}
+
+/// The empty hexes around a queen, split by whether the queen could slide into them
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct QueenEscapes {
+    pub open: Vec<Hex>,
+    /// Empty but out of reach, behind a gate, across a move that would break the hive, or under a
+    /// beetle sitting on the queen
+    pub blocked: Vec<Hex>,
+}
+
+/// How a piece gets to one of its destinations, see `Game::annotated_destinations`
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Hash)]
+pub enum MoveKind {
+    /// Sliding along the ground around the hive
+    Slide,
+    /// Moving onto, across or down off the top of the hive, like a beetle or a ladybug
+    Climb,
+    /// Jumping in a straight line over a row of pieces, like a grasshopper
+    Jump,
+    /// Being picked up and put down by an adjacent pillbug, or a mosquito copying one
+    PillbugPush,
+}
+
+#[derive(Debug, Eq, PartialEq)]
 pub enum GameResult {
     None,
     Draw,
@@ -66,6 +219,220 @@ fn default_reserve() -> Vec<Bug> {
     Vec::from(DEFAULT_RESERVE)
 }
 
+/// Each queen on the board along with how many of the hexes around it are occupied
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub(crate) struct QueenSurrounds {
+    white: Option<(Hex, usize)>,
+    black: Option<(Hex, usize)>,
+}
+
+impl QueenSurrounds {
+    fn scan(hive: &Hive) -> QueenSurrounds {
+        let mut queens = QueenSurrounds::default();
+        for (hex, tile) in hive.map.iter().filter(|(_, tile)| tile.bug == Bug::Queen) {
+            *queens.queen_mut(tile.color) =
+                Some((*hex, hive.occupied_neighbors_at_same_level(hex).count()));
+        }
+        queens
+    }
+
+    /// The surrounds after pieces were added to or removed from the `changed` hexes of `hive`.
+    /// Only queens that moved or are next to a changed hex are recounted.
+    fn after_change(&self, hive: &Hive, changed: &[Hex]) -> QueenSurrounds {
+        let mut queens = *self;
+        for color in [Color::White, Color::Black] {
+            let queen = Tile {
+                bug: Bug::Queen,
+                color,
+            };
+            let entry = queens.queen_mut(color);
+            let moved_to = changed.iter().find(|hex| hive.tile_at(hex) == Some(queen));
+            let hex = match (moved_to, *entry) {
+                (Some(hex), _) => *hex,
+                (None, Some((hex, _))) if changed.iter().any(|c| is_adjacent(c, &hex)) => hex,
+                _ => continue,
+            };
+            *entry = Some((hex, hive.occupied_neighbors_at_same_level(&hex).count()));
+        }
+        queens
+    }
+
+    fn queen_mut(&mut self, color: Color) -> &mut Option<(Hex, usize)> {
+        match color {
+            Color::White => &mut self.white,
+            Color::Black => &mut self.black,
+        }
+    }
+
+    fn hex(&self, color: Color) -> Option<Hex> {
+        let queen = match color {
+            Color::White => self.white,
+            Color::Black => self.black,
+        };
+        queen.map(|(hex, _)| hex)
+    }
+
+    fn occupied_around(&self, color: Color) -> usize {
+        let queen = match color {
+            Color::White => self.white,
+            Color::Black => self.black,
+        };
+        queen.map_or(0, |(_, occupied)| occupied)
+    }
+
+    fn surrounded_colors(&self) -> Vec<Color> {
+        [(Color::White, self.white), (Color::Black, self.black)]
+            .into_iter()
+            .filter(|(_, queen)| queen.is_some_and(|(_, occupied)| occupied == 6))
+            .map(|(color, _)| color)
+            .collect()
+    }
+}
+
+/// The empty hexes each color is allowed to place a piece on, once the board has at least two
+/// pieces. Which bugs can go there depends on the reserves, so that's left to the caller.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PlacementHexes {
+    white: OnceLock<Vec<Hex>>,
+    black: OnceLock<Vec<Hex>>,
+}
+
+impl PlacementHexes {
+    fn get_or_init(&self, color: Color, init: impl FnOnce() -> Vec<Hex>) -> &[Hex] {
+        match color {
+            Color::White => self.white.get_or_init(init),
+            Color::Black => self.black.get_or_init(init),
+        }
+    }
+
+    /// Whatever has been worked out for `color` so far, without filling it in
+    #[cfg(test)]
+    fn get(&self, color: Color) -> Option<&[Hex]> {
+        match color {
+            Color::White => self.white.get(),
+            Color::Black => self.black.get(),
+        }
+        .map(Vec::as_slice)
+    }
+}
+
+/// The result of the game given the colors whose queens are surrounded
+fn surrounded_queens_result(losing_colors: &[Color]) -> GameResult {
+    if losing_colors.is_empty() {
+        return GameResult::None;
+    }
+    if losing_colors.len() == 2 {
+        return GameResult::Draw;
+    }
+
+    GameResult::Winner {
+        color: losing_colors.first().unwrap().opposite(),
+    }
+}
+
+/// Fails with `TooManyPieces` if `color` has more of a bug between the board and `reserve` than a
+/// player starts with, otherwise returns the pieces that have fewer than that
+fn check_piece_counts(
+    hive: &Hive,
+    color: Color,
+    reserve: &[Bug],
+) -> Result<Vec<Tile>, PositionError> {
+    let mut short = vec![];
+    for bug in DEFAULT_RESERVE.iter().unique() {
+        let tile = Tile { bug: *bug, color };
+        let on_board = hive.map.values().filter(|t| **t == tile).count();
+        let in_reserve = reserve.iter().filter(|b| *b == bug).count();
+        let starting = DEFAULT_RESERVE.iter().filter(|b| *b == bug).count();
+        if on_board + in_reserve > starting {
+            return Err(PositionError::TooManyPieces { tile });
+        } else if on_board + in_reserve < starting {
+            short.push(tile);
+        }
+    }
+    Ok(short)
+}
+
+/// Number the pieces of a board without any history, using the order from `Hive::pieces_of`
+fn scan_order_piece_numbers(hive: &Hive) -> FxHashMap<Hex, u8> {
+    let tiles: FxHashSet<Tile> = hive.map.values().copied().collect();
+    tiles
+        .into_iter()
+        .flat_map(|tile| hive.pieces_of(tile.bug, tile.color).into_iter().zip(1..))
+        .collect()
+}
+
+/// Prints the board the way `Hive`'s `Display` does rather than dumping the raw map, along with
+/// everything else needed to tell what's going on in the game
+impl Debug for Game {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Game {{")?;
+        writeln!(f, "    active player: {}", self.active_player)?;
+        writeln!(
+            f,
+            "    white reserve: {}",
+            self.white_reserve.iter().join(" ")
+        )?;
+        writeln!(
+            f,
+            "    black reserve: {}",
+            self.black_reserve.iter().join(" ")
+        )?;
+        writeln!(f, "    immobilized piece: {:?}", self.immobilized_piece)?;
+        writeln!(f, "    last turn: {:?}", self.last_turn)?;
+        writeln!(f, "    board:")?;
+        for line in self.hive.to_string().lines() {
+            writeln!(f, "    {line}")?;
+        }
+        write!(f, "}}")
+    }
+}
+
+/// Two games are equal if they have the same board, reserves, player to move, immobilized piece
+/// and last turn, which is everything `Debug` prints. Boards that only differ by where they sit
+/// are the same, since saving and loading a game doesn't keep absolute coordinates. Reserves are
+/// compared ignoring order. The clocks, resignation, piece numbers, rule flags and cached values
+/// aren't compared.
+impl PartialEq for Game {
+    fn eq(&self, other: &Self) -> bool {
+        let corner = |game: &Game| game.hive.map.keys().copied().min().unwrap_or_default();
+        let offset = corner(self) - corner(other);
+        let shift = |hex: Hex| hex + Hex { h: 0, ..offset };
+        let shift_turn = |turn: Turn| match turn {
+            Placement { hex, tile } => Placement {
+                hex: shift(hex),
+                tile,
+            },
+            Move {
+                from,
+                to,
+                freezes_piece,
+            } => Move {
+                from: shift(from),
+                to: shift(to),
+                freezes_piece,
+            },
+            Skip => Skip,
+        };
+        let same_bugs = |left: &[Bug], right: &[Bug]| {
+            left.len() == right.len() && left.iter().sorted().eq(right.iter().sorted())
+        };
+
+        self.hive.map.len() == other.hive.map.len()
+            && other
+                .hive
+                .map
+                .iter()
+                .all(|(hex, tile)| self.hive.map.get(&shift(*hex)) == Some(tile))
+            && same_bugs(&self.white_reserve, &other.white_reserve)
+            && same_bugs(&self.black_reserve, &other.black_reserve)
+            && self.active_player == other.active_player
+            && self.immobilized_piece == other.immobilized_piece.map(shift)
+            && self.last_turn == other.last_turn.map(shift_turn)
+    }
+}
+
+impl Eq for Game {}
+
 impl Default for Game {
     fn default() -> Self {
         Game {
@@ -79,6 +446,14 @@ impl Default for Game {
             immobilized_piece: None,
             zobrist_table: ZobristTable::get(),
             zobrist_hash: Default::default(),
+            white_clock: None,
+            black_clock: None,
+            piece_numbers: Default::default(),
+            ignore_hive_rule: false,
+            stuck_player_loses: false,
+            resigned: None,
+            queens: QueenSurrounds::default(),
+            placement_hexes: Default::default(),
         }
     }
 }
@@ -91,6 +466,47 @@ pub enum GameParseError {
     InvalidHive(#[from] HiveParseError),
 }
 
+#[derive(Error, Debug)]
+pub enum PositionError {
+    #[error("Invalid hive")]
+    InvalidHive(#[from] HiveParseError),
+    #[error("The hive is split into {components} separate groups")]
+    Disconnected { components: usize },
+    #[error("There are more {tile} pieces on the board and in reserve than a player starts with")]
+    TooManyPieces { tile: Tile },
+    #[error("The {tile} pieces on the board and in reserve don't make up a full set")]
+    ReserveMismatch { tile: Tile },
+    #[error("The immobilized piece at {hex:?} isn't on the board")]
+    ImmobilizedPieceMissing { hex: Hex },
+    #[error("The last turn {turn:?} didn't leave a piece on the board")]
+    LastTurnMissing { turn: Turn },
+}
+
+/// Why `preview` refused a turn. `IllegalTurnError` is the same for a whole line of turns.
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum ApplyError {
+    #[error("The game is already over")]
+    GameOver,
+    #[error("Turn {turn:?} is not legal")]
+    IllegalTurn { turn: Turn },
+}
+
+#[derive(Error, Debug)]
+#[error("Turn {turn_number} ({turn:?}) is not legal")]
+pub struct IllegalTurnError {
+    pub turn_number: usize,
+    pub turn: Turn,
+}
+
+/// The board, who's to move and both reserves, as `Game::canonical_position` lays them out. Each
+/// piece on the board also says whether it's immobilized and whether it moved last turn.
+type CanonicalPosition = (Vec<(Hex, (Tile, bool, bool))>, Color, Vec<Bug>, Vec<Bug>);
+
+/// Positions `forced_win_in` has already searched, keyed by hash, immobilized piece, the piece that
+/// moved last turn, whether the last turn was a skip and plies left. The last turn matters since a
+/// pillbug can't move the piece that just moved, and only a skip can end the game in a draw.
+type ProvenLines = FxHashMap<(u64, Option<Hex>, Option<Hex>, bool, u32), Option<Vec<Turn>>>;
+
 impl Game {
     pub fn turn_is_valid(&self, turn: Turn) -> bool {
         //TODO: This is a really slow way to implement this
@@ -103,22 +519,219 @@ impl Game {
         Ok(Self::from_hive(hive, Color::White))
     }
 
+    /// Every distinct opening line up to `depth` plies long, along with the game it leads to, in
+    /// order of length. Lines that reach the same position as an earlier line, up to rotating and
+    /// sliding the board, are dropped so symmetric openings only show up once. Stops after
+    /// `max_lines` lines to bound how long this takes.
+    pub fn all_openings(depth: usize, max_lines: usize) -> Vec<(Vec<Turn>, Game)> {
+        let mut seen: FxHashSet<(Vec<(Hex, Tile)>, Color)> = FxHashSet::default();
+        let mut openings: Vec<(Vec<Turn>, Game)> = vec![];
+        let mut frontier: Vec<(Vec<Turn>, Game)> = vec![(vec![], Game::default())];
+
+        for _ in 0..depth {
+            let mut next_frontier = vec![];
+            for (line, game) in frontier {
+                for turn in game.turns() {
+                    if openings.len() >= max_lines {
+                        return openings;
+                    }
+                    let next = game.with_turn_applied(turn);
+                    let board: FxHashMap<Hex, Tile> = next
+                        .hive
+                        .map
+                        .iter()
+                        .map(|(hex, tile)| (*hex, *tile))
+                        .collect();
+                    let position = canonicalize(&board).into_iter().sorted().collect();
+                    if !seen.insert((position, next.active_player)) {
+                        continue;
+                    }
+                    let mut next_line = line.clone();
+                    next_line.push(turn);
+                    openings.push((next_line.clone(), next.clone()));
+                    next_frontier.push((next_line, next));
+                }
+            }
+            frontier = next_frontier;
+        }
+        openings
+    }
+
+    /// Play the given turns from the start of a game, failing on the first one that isn't legal
+    pub fn from_turns(turns: impl IntoIterator<Item = Turn>) -> Result<Game, IllegalTurnError> {
+        let mut game = Game::default();
+        for (i, turn) in turns.into_iter().enumerate() {
+            if !game.turn_is_valid(turn) {
+                return Err(IllegalTurnError {
+                    turn_number: i + 1,
+                    turn,
+                });
+            }
+            game = game.with_turn_applied(turn);
+        }
+        Ok(game)
+    }
+
+    /// Play the given turns from the start of a game like `from_turns`, but say why a turn can't be
+    /// played along with its index in `turns`. Each turn is checked against the game the turns
+    /// before it led to, including which piece was last moved, so pillbug pushes are judged the
+    /// same as in play.
+    pub fn replay_checked(turns: &[Turn]) -> Result<Game, (usize, ApplyError)> {
+        turns
+            .iter()
+            .enumerate()
+            .try_fold(Game::default(), |game, (i, turn)| {
+                game.preview(*turn).map_err(|error| (i, error))
+            })
+    }
+
+    /// Build a game from a UHP game type like `Base+MLP` and a list of UHP move strings like
+    /// `wS1` or `bG1 wS1-`
+    pub fn from_uhp(game_type: &str, moves: &[&str]) -> Result<Game, UhpError> {
+        let game_type: GameType = game_type
+            .parse()
+            .map_err(|_| UhpError::InvalidGameType(game_type.to_string()))?;
+        let mut game = Game::from_hive_with_reserves(
+            Hive {
+                map: Default::default(),
+            },
+            Color::White,
+            game_type.reserve(),
+            game_type.reserve(),
+        );
+
+        for (i, move_string) in moves.iter().enumerate() {
+            game = game
+                .apply_move_string(move_string)
+                .map_err(|e| e.on_turn(i + 1))?;
+        }
+        Ok(game)
+    }
+
+    /// Parse a UHP move string like `wS1`, `bG1 wS1-` or `pass` and play it, for driving the
+    /// engine from text. Fails if the string can't be parsed or the move isn't legal, including
+    /// once the game is over.
+    ///
+    /// ```
+    /// use chive::engine::game::Game;
+    ///
+    /// let mut game = Game::from_uhp("Base+MLP", &[]).unwrap();
+    /// for move_string in ["wS1", "bG1 wS1-", "wQ -wS1", "bQ bG1-"] {
+    ///     game = game.apply_move_string(move_string).unwrap();
+    /// }
+    /// assert_eq!(game.hive().piece_count(), 4);
+    /// assert!(game.apply_move_string("wQ bQ-").is_err());
+    /// ```
+    pub fn apply_move_string(&self, move_string: &str) -> Result<Game, UhpError> {
+        candidate_turns(self, move_string)?
+            .into_iter()
+            .find_map(|turn| self.preview(turn).ok())
+            .ok_or_else(|| UhpError::IllegalMove {
+                turn_number: None,
+                move_string: move_string.trim().to_string(),
+            })
+    }
+
+    /// Format a turn in this game as a UHP move string like `wS1`, `bG1 wS1-` or `pass`
+    pub fn to_move_string(&self, turn: Turn) -> String {
+        format_turn(self, turn)
+    }
+
+    /// Every legal turn as a UHP move string, in the form the UHP `validmoves` command returns
+    /// them once joined with `;`. A friendly piece that could either move or be pushed to the same
+    /// spot only shows up once, and a forced skip is `pass`.
+    pub fn legal_move_strings(&self) -> Vec<String> {
+        self.turns()
+            .map(|turn| self.to_move_string(turn))
+            .unique()
+            .collect()
+    }
+
+    /// The board after each of `turns`, played in order from this game, with the turn's number and
+    /// move string above it and the hexes it touched in square brackets. A `Game` doesn't remember
+    /// how it got here, so the turns have to come from whoever played them.
+    pub fn render_history(&self, turns: &[Turn]) -> Vec<String> {
+        let mut game = self.clone();
+        let mut boards = vec![];
+        for (i, turn) in turns.iter().enumerate() {
+            let caption = format!("{}. {}", i + 1, game.to_move_string(*turn));
+            game = game.with_turn_applied(*turn);
+            let highlighted: Vec<Hex> = match game.last_move_hexes() {
+                Some((from, to)) => from.into_iter().chain([to]).collect(),
+                None => vec![],
+            };
+            let board = hex_map_to_highlighted_string(&game.hive.to_hex_map(), &highlighted);
+            boards.push(format!("{caption}\n{board}"));
+        }
+        boards
+    }
+
+    /// `render_history` as one block of text, the boards separated by lines, for pasting somewhere
+    /// to go through a game move by move
+    pub fn history_transcript(&self, turns: &[Turn]) -> String {
+        self.render_history(turns).join("\n----------------\n\n")
+    }
+
+    /// The board. Read only, since the queen surrounds and placement hexes are worked out from it
+    /// and would go stale if it changed underneath them. Build a new game from a changed hive
+    /// with `from_hive` or `from_hive_with_reserves` instead.
+    pub fn hive(&self) -> &Hive {
+        &self.hive
+    }
+
     pub fn from_hive(hive: Hive, active_player: Color) -> Game {
         let mut white_reserve = default_reserve();
         let mut black_reserve = default_reserve();
         for (_, tile) in hive.map.iter() {
-            if tile.color == Color::White {
-                let index = white_reserve.iter().position(|b| *b == tile.bug).unwrap();
-                white_reserve.remove(index);
-            } else {
-                let index = black_reserve.iter().position(|b| *b == tile.bug).unwrap();
-                black_reserve.remove(index);
+            let reserve = match tile.color {
+                Color::White => &mut white_reserve,
+                Color::Black => &mut black_reserve,
+            };
+            // Extra pieces are caught by validate() rather than panicking here
+            if let Some(index) = reserve.iter().position(|b| *b == tile.bug) {
+                reserve.remove(index);
             }
         }
 
         Self::from_hive_with_reserves(hive, active_player, white_reserve, black_reserve)
     }
 
+    /// Build a game in the middle of play, for puzzles and reproducing specific positions. Fails
+    /// if the board has floating stacks, if a player has more of a bug between the board and their
+    /// reserve than they start with, or if the immobilized piece or the piece the last turn moved
+    /// isn't on the board.
+    pub fn from_parts(
+        hive: Hive,
+        active_player: Color,
+        white_reserve: Vec<Bug>,
+        black_reserve: Vec<Bug>,
+        immobilized_piece: Option<Hex>,
+        last_turn: Option<Turn>,
+    ) -> Result<Game, PositionError> {
+        hive.validate()?;
+        check_piece_counts(&hive, Color::White, &white_reserve)?;
+        check_piece_counts(&hive, Color::Black, &black_reserve)?;
+        if let Some(hex) = immobilized_piece
+            && !hive.is_occupied(&hex)
+        {
+            return Err(PositionError::ImmobilizedPieceMissing { hex });
+        }
+        match last_turn {
+            Some(turn @ (Placement { hex: to, .. } | Move { to, .. }))
+                if !hive.is_occupied(&to) =>
+            {
+                return Err(PositionError::LastTurnMissing { turn });
+            }
+            _ => {}
+        }
+
+        Ok(Game {
+            immobilized_piece,
+            last_turn,
+            ..Self::from_hive_with_reserves(hive, active_player, white_reserve, black_reserve)
+        })
+    }
+
     pub fn from_hive_with_reserves(
         hive: Hive,
         active_player: Color,
@@ -127,6 +740,8 @@ impl Game {
     ) -> Game {
         let zobrist_table = ZobristTable::get();
         let zobrist_hash = zobrist_table.hash(&hive, active_player);
+        let piece_numbers = scan_order_piece_numbers(&hive);
+        let queens = QueenSurrounds::scan(&hive);
         Game {
             hive,
             white_reserve,
@@ -136,7 +751,62 @@ impl Game {
             zobrist_table,
             zobrist_hash,
             active_player,
+            white_clock: None,
+            black_clock: None,
+            piece_numbers,
+            ignore_hive_rule: false,
+            stuck_player_loses: false,
+            resigned: None,
+            queens,
+            placement_hexes: Default::default(),
+        }
+    }
+
+    /// The same game with `color`'s reserve replaced, for setting up positions like having only the
+    /// queen left to place. Fails if the new reserve and the pieces already on the board add up to
+    /// more of a bug than a player starts with. The hash doesn't cover reserves, so it carries over.
+    pub fn with_reserve(&self, color: Color, reserve: Vec<Bug>) -> Result<Game, PositionError> {
+        check_piece_counts(&self.hive, color, &reserve)?;
+        let mut game = self.clone();
+        match color {
+            Color::White => game.white_reserve = reserve,
+            Color::Black => game.black_reserve = reserve,
+        }
+        Ok(game)
+    }
+
+    /// Check that the position could have come from a real game, for games loaded from files or
+    /// the network. The hive has to be connected with no floating stacks, each player's pieces on
+    /// the board and in reserve have to make up a full set of each bug (expansion bugs can be left
+    /// out entirely), and the immobilized piece has to be on the board. Returns the first problem
+    /// found. Debug builds also check that the cached queen surrounds match the board.
+    pub fn validate(&self) -> Result<(), PositionError> {
+        self.hive.validate()?;
+        let components = self.hive.components().len();
+        if components > 1 {
+            return Err(PositionError::Disconnected { components });
+        }
+        for (color, reserve) in [
+            (Color::White, &self.white_reserve),
+            (Color::Black, &self.black_reserve),
+        ] {
+            for tile in check_piece_counts(&self.hive, color, reserve)? {
+                let left_out = !self.hive.map.values().contains(&tile)
+                    && !reserve.contains(&tile.bug)
+                    && GameType::BaseMLP.expansion_bugs().contains(&tile.bug);
+                if !left_out {
+                    return Err(PositionError::ReserveMismatch { tile });
+                }
+            }
+        }
+        // Every constructor scans the board for the queens, so this would be a bug in Game itself
+        debug_assert_eq!(self.queens, QueenSurrounds::scan(&self.hive));
+        if let Some(hex) = self.immobilized_piece
+            && !self.hive.is_occupied(&hex)
+        {
+            return Err(PositionError::ImmobilizedPieceMissing { hex });
         }
+        Ok(())
     }
 
     pub fn with_turn_applied(&self, turn: Turn) -> Game {
@@ -179,14 +849,20 @@ impl Game {
                     black_reserve = new_reserve;
                 }
 
+                let mut piece_numbers = self.piece_numbers.clone();
+                let placed = self.hive.map.values().filter(|t| **t == tile).count();
+                piece_numbers.insert(hex, placed as u8 + 1);
+
                 new_map.insert(hex, tile);
                 let new_zobrist_hash = self
                     .zobrist_hash
                     .with_added_tile(self.zobrist_table, &hex, &tile)
                     .with_turn_change(self.zobrist_table);
+                let hive = Hive { map: new_map };
+                let queens = self.queens.after_change(&hive, &[hex]);
 
                 Game {
-                    hive: Hive { map: new_map },
+                    hive,
                     white_reserve,
                     black_reserve,
                     immobilized_piece: None,
@@ -194,6 +870,14 @@ impl Game {
                     active_player: self.active_player.opposite(),
                     zobrist_table: self.zobrist_table,
                     zobrist_hash: new_zobrist_hash,
+                    white_clock: self.white_clock,
+                    black_clock: self.black_clock,
+                    piece_numbers,
+                    ignore_hive_rule: self.ignore_hive_rule,
+                    stuck_player_loses: self.stuck_player_loses,
+                    resigned: self.resigned,
+                    queens,
+                    placement_hexes: Default::default(),
                 }
             }
             Move {
@@ -214,15 +898,22 @@ impl Game {
                     "Only the pillbug can move a piece of the opposing player, and that should freeze the piece"
                 );
 
+                let mut piece_numbers = self.piece_numbers.clone();
+                if let Some(number) = piece_numbers.remove(&from) {
+                    piece_numbers.insert(to, number);
+                }
+
                 new_map.insert(to, tile);
                 let new_zobrist_hash = self
                     .zobrist_hash
                     .with_removed_tile(self.zobrist_table, &from, &tile)
                     .with_added_tile(self.zobrist_table, &to, &tile)
                     .with_turn_change(self.zobrist_table);
+                let hive = Hive { map: new_map };
+                let queens = self.queens.after_change(&hive, &[from, to]);
 
                 Game {
-                    hive: Hive { map: new_map },
+                    hive,
                     white_reserve: self.white_reserve.clone(),
                     black_reserve: self.black_reserve.clone(),
                     last_turn: Some(turn),
@@ -230,6 +921,14 @@ impl Game {
                     active_player: self.active_player.opposite(),
                     zobrist_table: self.zobrist_table,
                     zobrist_hash: new_zobrist_hash,
+                    white_clock: self.white_clock,
+                    black_clock: self.black_clock,
+                    piece_numbers,
+                    ignore_hive_rule: self.ignore_hive_rule,
+                    stuck_player_loses: self.stuck_player_loses,
+                    resigned: self.resigned,
+                    queens,
+                    placement_hexes: Default::default(),
                 }
             }
             Skip => {
@@ -243,369 +942,1239 @@ impl Game {
                     active_player: self.active_player.opposite(),
                     zobrist_table: self.zobrist_table,
                     zobrist_hash: new_zobrist_hash,
+                    white_clock: self.white_clock,
+                    black_clock: self.black_clock,
+                    piece_numbers: self.piece_numbers.clone(),
+                    ignore_hive_rule: self.ignore_hive_rule,
+                    stuck_player_loses: self.stuck_player_loses,
+                    resigned: self.resigned,
+                    queens: self.queens,
+                    placement_hexes: self.placement_hexes.clone(),
                 }
             }
         }
     }
 
-    pub fn game_result(&self) -> GameResult {
-        let losing_colors: Vec<Color> = self
-            .hive
-            .map
+    /// Apply a turn that took `elapsed` to decide on, deducting the time from the active player's
+    /// clock. Untimed games are unaffected.
+    pub fn with_timed_turn_applied(&self, turn: Turn, elapsed: Duration) -> Game {
+        let mut game = self.with_turn_applied(turn);
+        let clock = match self.active_player {
+            Color::White => &mut game.white_clock,
+            Color::Black => &mut game.black_clock,
+        };
+        if let Some(remaining) = clock {
+            *remaining = remaining.saturating_sub(elapsed);
+        }
+        game
+    }
+
+    /// Where the given copy of a bug is, e.g. the second white ant
+    pub fn find_piece(&self, tile: Tile, number: u8) -> Option<Hex> {
+        self.piece_numbers
             .iter()
-            .filter(|(hex, t)| {
-                t.bug == Bug::Queen && self.hive.occupied_neighbors_at_same_level(hex).count() == 6
-            })
-            .map(|(_, t)| t.color)
-            .collect();
+            .find(|(hex, n)| **n == number && self.hive.tile_at(hex) == Some(tile))
+            .map(|(hex, _)| *hex)
+    }
 
-        if losing_colors.is_empty() {
-            return GameResult::None;
-        }
-        if losing_colors.len() == 2 {
-            return GameResult::Draw;
+    /// The same game with the board rotated about the origin, see `Hive::rotated_by`. Every hex
+    /// the game tracks, like the immobilized piece and the last turn, is rotated with it.
+    pub fn rotated_by(&self, degrees: RotationDegrees) -> Game {
+        let hive = self.hive.rotated_by(degrees);
+        Game {
+            zobrist_hash: self.zobrist_table.hash(&hive, self.active_player),
+            queens: QueenSurrounds::scan(&hive),
+            placement_hexes: Default::default(),
+            hive,
+            immobilized_piece: self.immobilized_piece.map(|hex| hex.rotated_by(degrees)),
+            last_turn: self.last_turn.map(|turn| turn.rotated_by(degrees)),
+            piece_numbers: self
+                .piece_numbers
+                .iter()
+                .map(|(hex, number)| (hex.rotated_by(degrees), *number))
+                .collect(),
+            ..self.clone()
         }
+    }
 
-        GameResult::Winner {
-            color: losing_colors.first().unwrap().opposite(),
+    /// Where the last turn took a piece from and where it put it, for highlighting it in a UI.
+    /// Placements have no `from`, and pillbug pushes report the pushed piece, not the pillbug.
+    /// Returns `None` if the last turn was a skip or nothing has been played yet.
+    pub fn last_move_hexes(&self) -> Option<(Option<Hex>, Hex)> {
+        match self.last_turn? {
+            Placement { hex, .. } => Some((None, hex)),
+            Move { from, to, .. } => Some((Some(from), to)),
+            Skip => None,
         }
     }
 
-    fn active_reserve(&self) -> &Vec<Bug> {
+    /// The Zobrist hash of just the board, without the side to move mixed in. Positions with the
+    /// same pieces in the same places share a board hash no matter whose turn it is.
+    pub fn board_hash(&self) -> u64 {
         match self.active_player {
-            Color::Black => &self.black_reserve,
-            Color::White => &self.white_reserve,
+            Color::White => self.zobrist_hash.value(),
+            Color::Black => self
+                .zobrist_hash
+                .with_turn_change(self.zobrist_table)
+                .value(),
         }
     }
 
-    pub fn valid_destinations_for_piece(&self, hex: &Hex) -> impl Iterator<Item = Hex> {
-        //TODO: This is a slow way to do this
-        self.moves().into_iter().filter_map(|turn| match turn {
-            Move {
-                from,
-                to,
-                freezes_piece: false,
-            } if from == *hex => Some(to),
-            _ => None,
-        })
+    /// A key for storing positions that's the same for any two games that play out identically
+    /// up to rotating, mirroring or sliding the board. Exactly these affect the key:
+    /// - the pieces on the board, where they are relative to each other and how they're stacked
+    /// - whose turn it is
+    /// - the pieces left in each player's reserve
+    /// - which piece, if any, was immobilized by a pillbug
+    /// - which piece, if any, moved last turn, since a pillbug can't move it
+    ///
+    /// Nothing else does, including the turn number, piece numbers, clocks, resignations and
+    /// `ignore_hive_rule`.
+    pub fn canonical_key(&self) -> u64 {
+        let mut hasher = FxHasher::default();
+        self.canonical_position().hash(&mut hasher);
+        hasher.finish()
     }
 
-    pub fn turns(&self) -> impl Iterator<Item = Turn> {
-        let active_player_reserve = if self.active_player == Color::Black {
-            &self.black_reserve
-        } else {
-            &self.white_reserve
+    /// Everything `canonical_key` hashes, for telling positions apart without trusting the hash
+    fn canonical_position(&self) -> CanonicalPosition {
+        let moved_last_turn = match self.last_turn {
+            Some(Move { to, .. }) => Some(to),
+            _ => None,
         };
+        let pieces: FxHashMap<Hex, (Tile, bool, bool)> = self
+            .hive
+            .map
+            .iter()
+            .map(|(hex, tile)| {
+                let immobilized = self.immobilized_piece == Some(*hex);
+                let moved = moved_last_turn == Some(*hex);
+                (*hex, (*tile, immobilized, moved))
+            })
+            .collect();
 
-        let mut turns = self
-            .placements(active_player_reserve)
-            .into_iter()
-            .chain(self.moves())
-            .peekable();
-
-        // If there are no valid turns, you must skip
-        if turns.peek().is_none() {
-            Either::Left(iter::once(Skip))
-        } else {
-            Either::Right(turns)
-        }
+        (
+            canonicalize_with_reflection(&pieces)
+                .into_iter()
+                .sorted()
+                .collect(),
+            self.active_player,
+            self.white_reserve.iter().copied().sorted().collect(),
+            self.black_reserve.iter().copied().sorted().collect(),
+        )
     }
 
-    fn placements<'a>(
-        &'a self,
-        active_player_reserve: &'a Vec<Bug>,
-    ) -> Box<dyn Iterator<Item = Turn> + 'a> {
-        if active_player_reserve.is_empty() {
-            return Box::new(iter::empty());
+    /// Who won, if anyone. A queen is surrounded once all six hexes around it at its own level are
+    /// occupied, no matter whose pieces they are, so a player can help surround their own queen.
+    /// A beetle sitting on a queen isn't one of the six, and a queen buried under one still loses
+    /// once its sides are covered.
+    ///
+    /// Besides surrounded queens, resignations and clocks, the game is a draw once a player has
+    /// passed and neither side has anything but a pass left, since otherwise they would pass back
+    /// and forth forever. That's only checked right after a pass, so it costs nothing in ordinary
+    /// positions.
+    pub fn game_result(&self) -> GameResult {
+        if let Some(color) = self.resigned {
+            return GameResult::Winner {
+                color: color.opposite(),
+            };
         }
-
-        if self.hive.map.is_empty() {
-            return Box::new(
-                active_player_reserve
-                    .iter()
-                    .filter(|bug| **bug != Bug::Queen)
-                    .unique()
-                    .map(|bug| Placement {
-                        hex: Hex { q: 0, r: 0, h: 0 },
-                        tile: Tile {
-                            bug: *bug,
-                            color: self.active_player,
-                        },
-                    }),
-            );
+        if let Some(result) = self.out_of_time_result() {
+            return result;
         }
-
-        if self.hive.map.len() == 1 {
-            let only_occupied_hex = self.hive.map.iter().next().unwrap().0;
-
-            return Box::new(
-                active_player_reserve
-                    .iter()
-                    .filter(|bug| **bug != Bug::Queen)
-                    .flat_map(|bug| {
-                        neighbors(only_occupied_hex).map(|hex| Placement {
-                            hex,
-                            tile: Tile {
-                                bug: *bug,
-                                color: self.active_player,
-                            },
-                        })
-                    }),
-            );
+        let result = surrounded_queens_result(&self.queens.surrounded_colors());
+        if result == GameResult::None && self.stuck_player_loses && self.has_no_legal_turns() {
+            return GameResult::Winner {
+                color: self.active_player.opposite(),
+            };
         }
+        if result == GameResult::None && self.last_turn == Some(Skip) && self.both_players_stuck() {
+            return GameResult::Draw;
+        }
+        result
+    }
 
-        let mut placement_allowed: FxHashMap<Hex, bool> = FxHashMap::default();
-        let mut valid_turns: Vec<Turn> = Vec::new();
-        // If you haven't played your queen by turn 4, you must play your queen
-        let is_turn_four = active_player_reserve.len() <= DEFAULT_RESERVE.len() - 3;
-        let reserve = if is_turn_four && active_player_reserve.contains(&Bug::Queen) {
-            &vec![Bug::Queen]
-        } else {
-            active_player_reserve
-        };
+    /// Whether the active player can only pass, and so can their opponent after that pass
+    fn both_players_stuck(&self) -> bool {
+        self.has_no_legal_turns() && self.with_turn_applied(Skip).has_no_legal_turns()
+    }
 
-        for (hex, tile) in self.hive.map.iter() {
-            if tile.color == self.active_player {
-                for neighbor in self.hive.unoccupied_neighbors(&Hex { h: 0, ..*hex }) {
-                    let allowed = *placement_allowed.entry(neighbor).or_insert_with(|| {
-                        !self.is_adjacent_to_color(&neighbor, &self.active_player.opposite())
-                    });
-                    if allowed {
-                        let turns = reserve.iter().map(|bug| Placement {
-                            hex: neighbor,
-                            tile: Tile {
-                                bug: *bug,
-                                color: self.active_player,
-                            },
-                        });
-
-                        valid_turns.extend(turns);
-                    }
-                }
-            }
-        }
+    /// Whether someone has won or the game is drawn, so there are no more turns to play
+    pub fn is_game_over(&self) -> bool {
+        self.game_result() != GameResult::None
+    }
 
-        Box::new(valid_turns.into_iter())
+    /// Apply a turn and work out the result of the game after it in one go
+    pub fn with_turn_applied_checked(&self, turn: Turn) -> (Game, GameResult) {
+        let game = self.with_turn_applied(turn);
+        let result = game.game_result();
+        (game, result)
     }
 
-    fn moves(&self) -> impl Iterator<Item = Turn> {
-        if self.active_reserve().contains(&Bug::Queen) {
-            return Either::Left(iter::empty());
+    /// The opponent's replies to `my_turn` that win them the game on the spot, for warning a player
+    /// before they commit to a blunder. Empty if `my_turn` ends the game by itself, since then
+    /// there's nothing to reply to.
+    pub fn opponent_winning_replies(&self, my_turn: Turn) -> Vec<Turn> {
+        let after = self.with_turn_applied(my_turn);
+        if after.game_result() != GameResult::None {
+            return vec![];
         }
-
-        Either::Right(
+        // One turn fills at most one more hex around a queen, so unless the opponent can win by
+        // leaving us stuck or by pushing our queen somewhere more crowded there's no need to try
+        // their replies
+        if !self.stuck_player_loses
+            && after.queens.occupied_around(self.active_player) < 5
+            && !after.could_push_queen(self.active_player.opposite())
+        {
+            return vec![];
+        }
+        let opponent_wins = GameResult::Winner {
+            color: self.active_player.opposite(),
+        };
+        after
+            .turns()
+            .filter(|reply| after.with_turn_applied(*reply).game_result() == opponent_wins)
+            .collect()
+    }
+
+    /// Whether `color` has a pillbug next to the other color's queen, or a mosquito on the ground
+    /// next to both that queen and a pillbug, and so might push the queen to another hex
+    fn could_push_queen(&self, color: Color) -> bool {
+        let Some(queen) = self.queens.hex(color.opposite()) else {
+            return false;
+        };
+        let is_pillbug = |hex: Hex| {
             self.hive
-                .toplevel_pieces()
-                .filter(|(_, tile)| tile.color == self.active_player)
-                .flat_map(|(hex, tile)| self.moves_for_tile(tile.bug, hex)),
-        )
+                .tile_at(&hex)
+                .is_some_and(|tile| tile.bug == Bug::Pillbug)
+        };
+        self.hive.topmost_occupied_neighbors(&queen).any(|hex| {
+            self.hive.tile_at(&hex).is_some_and(|tile| {
+                tile.color == color
+                    && match tile.bug {
+                        Bug::Pillbug => true,
+                        Bug::Mosquito => {
+                            hex.h == 0 && self.hive.topmost_occupied_neighbors(&hex).any(is_pillbug)
+                        }
+                        _ => false,
+                    }
+            })
+        })
     }
 
-    pub fn moves_for_piece<'a>(&'a self, hex: &'a Hex) -> impl Iterator<Item = Turn> {
-        // If you haven't placed your queen yet you're not allowed to move.
-        // Only the top piece in a stack is allowed to move
-        if self.active_reserve().contains(&Bug::Queen) || self.hive.stack_height(hex) != hex.h + 1 {
-            return Either::Left(iter::empty());
+    /// The game as it would be after `turn`, without touching this one. Meant for speculative UI
+    /// like showing what a move would lead to on hover: query the preview with `turns()` or
+    /// `game_result()` and drop it. Unlike `with_turn_applied` this checks the turn first and
+    /// returns an error instead of producing a broken game. Only the pieces that could make the
+    /// turn are asked for their moves, so it's much cheaper than `turn_is_valid`.
+    pub fn preview(&self, turn: Turn) -> Result<Game, ApplyError> {
+        if self.game_result() != GameResult::None {
+            return Err(ApplyError::GameOver);
+        }
+        let legal = match turn {
+            Skip => self.has_no_legal_turns(),
+            _ if self.must_pass_due_to_queen_rule() => false,
+            Placement { .. } => self.placement_turns().contains(&turn),
+            // Either the piece moves itself or an adjacent pillbug (or mosquito) pushes it
+            Move { from, .. } => iter::once(from)
+                .chain(self.hive.topmost_occupied_neighbors(&from))
+                .filter(|hex| {
+                    self.hive
+                        .tile_at(hex)
+                        .is_some_and(|tile| tile.color == self.active_player)
+                })
+                .any(|hex| self.moves_for_piece(&hex).contains(&turn)),
+        };
+        if !legal {
+            return Err(ApplyError::IllegalTurn { turn });
         }
+        Ok(self.with_turn_applied(turn))
+    }
 
-        let tile = self.hive.tile_at(hex).unwrap();
-        Either::Right(self.moves_for_tile(tile.bug, hex))
+    /// Concede the game for `color`, giving the win to their opponent no matter what the board
+    /// looks like. Returns the result of the game, which stays over from then on.
+    pub fn resign(&mut self, color: Color) -> GameResult {
+        self.resigned = Some(color);
+        self.game_result()
     }
 
-    fn moves_for_tile<'a>(&'a self, bug: Bug, hex: &'a Hex) -> Box<dyn Iterator<Item = Turn> + 'a> {
-        match bug {
-            Bug::Beetle => Box::new(self.beetle_moves(&hex)),
-            Bug::Queen => Box::new(self.queen_moves(&hex)),
-            Bug::Grasshopper => Box::new(self.grasshopper_moves(&hex)),
-            Bug::Ant => Box::new(self.ant_moves(&hex)),
-            Bug::Spider => Box::new(self.spider_moves(&hex)),
-            Bug::Ladybug => Box::new(self.ladybug_moves(&hex)),
-            Bug::Mosquito => Box::new(self.mosquito_moves(&hex)),
-            Bug::Pillbug => Box::new(self.pillbug_moves(&hex)),
+    /// Running out of time loses regardless of the board
+    fn out_of_time_result(&self) -> Option<GameResult> {
+        if self.white_clock.is_some_and(|clock| clock.is_zero()) {
+            return Some(GameResult::Winner {
+                color: Color::Black,
+            });
+        }
+        if self.black_clock.is_some_and(|clock| clock.is_zero()) {
+            return Some(GameResult::Winner {
+                color: Color::White,
+            });
         }
+        None
     }
 
-    fn pillbug_moves(&self, pillbug_hex: &Hex) -> impl Iterator<Item = Turn> {
-        // The Pillbug moves one space at a time, but it also has a special ability it may use
-        // instead of moving.
-        // The special ability allows the Pillbug to move an adjacent piece (friend or enemy) up to
-        // two spaces; up onto itself and then down into another empty space adjacent to itself.
-        //
-        // Exceptions:
-        //  * The Pillbug may not move the piece which was just moved by the other player
-        //  * The Pillbug may not move any piece in a stack of pieces
-        //  * The Pillbug may not move a piece if it splits the hive (violating the One Hive Rule)
-        //  * The Pillbug may not move a piece through a narrow gap of stacked pieces (violating the
-        //    Freedom to Move Rule)
-        //
-        //  Furthermore, any piece moved by the Pillbug may not be moved at all (directly or via
-        //  Pillbug action) on the next player's turn.
-        //  The Mosquito can mimic either the movement or special ability of the Pillbug, even when
-        //  the Pillbug is immobile.
+    /// How many empty neighbors the queen of the given color could slide into right now, ignoring
+    /// whose turn it is, i.e. the open hexes from `queen_escapes`. A queen pinned under a beetle
+    /// has no escape squares. Returns `None` if the queen hasn't been placed yet.
+    pub fn queen_escape_squares(&self, color: Color) -> Option<usize> {
+        self.queen_escapes(color).map(|escapes| escapes.open.len())
+    }
 
-        let direct_moves = if self.immobilized_piece == Some(*pillbug_hex) {
-            Either::Left(iter::empty())
+    /// Which of the empty hexes around the queen of the given color it could slide into right now,
+    /// ignoring whose turn it is. Each list is sorted. Returns `None` if the queen hasn't been
+    /// placed yet.
+    pub fn queen_escapes(&self, color: Color) -> Option<QueenEscapes> {
+        let queen = Tile {
+            bug: Bug::Queen,
+            color,
+        };
+        let hex = self.find_piece(queen, 1)?;
+        let reachable: Vec<Hex> = if self.hive.stack_height(&hex) != hex.h + 1 {
+            vec![]
         } else {
-            Either::Right(self.queen_moves(pillbug_hex))
+            self.queen_moves(&hex)
+                .filter_map(|turn| match turn {
+                    Move { to, .. } => Some(to),
+                    _ => None,
+                })
+                .collect()
         };
+        let (open, blocked) = self
+            .hive
+            .unoccupied_neighbors(&hex.base_level())
+            .sorted()
+            .partition(|neighbor| reachable.contains(neighbor));
+        Some(QueenEscapes { open, blocked })
+    }
 
-        let mut special_ability_moves: Vec<Turn> = vec![];
-        let free_spaces: Vec<_> = self.hive.unoccupied_neighbors(&pillbug_hex).collect();
-        let above_pillbug = Hex {
-            h: 1,
-            ..*pillbug_hex
+    /// How many of the empty hexes around the opponent's queen it can't slide into. Those still
+    /// have to be filled to win, but unlike the open ones the queen can't use them to run away.
+    pub fn enemy_queen_escape_count(&self) -> usize {
+        self.queen_escapes(self.active_player.opposite())
+            .map_or(0, |escapes| escapes.blocked.len())
+    }
+
+    /// How many hexes apart the two queens are, measured along the ground. Returns `None` if either
+    /// queen hasn't been placed yet.
+    pub fn queen_distance(&self) -> Option<i32> {
+        let [white, black] = [Color::White, Color::Black].map(|color| {
+            self.find_piece(
+                Tile {
+                    bug: Bug::Queen,
+                    color,
+                },
+                1,
+            )
+        });
+        Some(flat_distance(&white?, &black?))
+    }
+
+    /// Whether the queen of the given color has a beetle on top of it
+    pub fn queen_is_covered(&self, color: Color) -> bool {
+        let queen = Tile {
+            bug: Bug::Queen,
+            color,
         };
-        let piece_moved_last_turn = match self.last_turn {
+        self.find_piece(queen, 1)
+            .is_some_and(|hex| self.hive.stack_height(&hex) != hex.h + 1)
+    }
+
+    /// A rough estimate of who is winning the race to surround the enemy queen: how many of the
+    /// empty squares around the enemy queen the active player could fill with a single turn, minus
+    /// how many around their own queen the opponent could fill if it were their turn. Positive
+    /// means the active player is ahead.
+    ///
+    /// This is a one-ply estimate, it ignores multi-turn sequences and any squares that only
+    /// become reachable after another piece moves.
+    pub fn surround_race(&self) -> i32 {
+        let opponent_to_move = Game {
+            active_player: self.active_player.opposite(),
+            immobilized_piece: None,
+            ..self.clone()
+        };
+        self.reachable_queen_neighbors(self.active_player.opposite()) as i32
+            - opponent_to_move.reachable_queen_neighbors(self.active_player) as i32
+    }
+
+    /// The enemy pieces on the ground that have one of `color`'s pieces stacked somewhere above
+    /// them, so can't move at all until it leaves. The covering piece is usually a beetle, but a
+    /// mosquito that climbed on top of the hive covers pieces just the same. Enemy beetles in the
+    /// middle of a stack aren't included. Sorted by `Hex`'s ordering.
+    pub fn covered_enemy_pieces(&self, color: Color) -> Vec<Hex> {
+        let mut covered: Vec<Hex> = self
+            .hive
+            .map
+            .iter()
+            .filter(|(hex, tile)| {
+                hex.h == 0
+                    && tile.color != color
+                    && self
+                        .hive
+                        .stack_at(hex)
+                        .skip(hex.h as usize + 1)
+                        .any(|above| above.color == color)
+            })
+            .map(|(hex, _)| *hex)
+            .collect();
+        covered.sort();
+        covered
+    }
+
+    /// How many different empty hexes `color` could move one of its pieces into, as if it were
+    /// their turn. Unlike `branching_factor()`, a hex that several pieces can reach only counts
+    /// once, so this measures how much of the board a player controls rather than how many
+    /// options they have. Climbs onto the hive don't count.
+    pub fn space_control(&self, color: Color) -> usize {
+        let color_to_move;
+        let game = if self.active_player == color {
+            self
+        } else {
+            color_to_move = Game {
+                active_player: color,
+                immobilized_piece: None,
+                ..self.clone()
+            };
+            &color_to_move
+        };
+        game.movement_turns()
+            .filter_map(|turn| match turn {
+                Move { from, to, .. }
+                    if to.h == 0 && game.hive.tile_at(&from).is_some_and(|t| t.color == color) =>
+                {
+                    Some(to)
+                }
+                _ => None,
+            })
+            .collect::<FxHashSet<Hex>>()
+            .len()
+    }
+
+    /// How far into the game this is, from 0.0 when every piece is in reserve to 1.0 once they've
+    /// all been placed
+    pub fn phase(&self) -> f32 {
+        let placed = self.hive.piece_count();
+        let total = placed + self.white_reserve.len() + self.black_reserve.len();
+        if total == 0 {
+            return 0.0;
+        }
+        placed as f32 / total as f32
+    }
+
+    /// How many legal turns the active player has. A forced skip counts as one turn.
+    pub fn branching_factor(&self) -> usize {
+        let mut turns = 0;
+        self.for_each_turn(|_| turns += 1);
+        turns
+    }
+
+    /// How many different positions the active player can reach this turn, judged by Zobrist hash.
+    /// Different turns can lead to the same position, so this can be lower than
+    /// `branching_factor()`.
+    pub fn distinct_next_positions(&self) -> usize {
+        let mut positions: FxHashSet<u64> = FxHashSet::default();
+        self.for_each_turn(|turn| {
+            positions.insert(self.with_turn_applied(turn).zobrist_hash.value());
+        });
+        positions.len()
+    }
+
+    /// How many turns the opponent would have after the given turn is played, or 0 if the turn
+    /// ends the game. There's no in-place apply and undo yet, so this clones the game.
+    pub fn reply_count_after(&self, turn: Turn) -> usize {
+        let after = self.with_turn_applied(turn);
+        if !matches!(after.game_result(), GameResult::None) {
+            return 0;
+        }
+        let mut replies = 0;
+        after.for_each_turn(|_| replies += 1);
+        replies
+    }
+
+    /// How many lines of play `depth` plies long start from this position, the standard count for
+    /// checking a move generator against known numbers. Lines stop early when the game ends.
+    pub fn perft(&self, depth: usize) -> u64 {
+        if depth == 0 || self.game_result() != GameResult::None {
+            return 1;
+        }
+        let mut nodes = 0;
+        self.for_each_turn(|turn| nodes += self.with_turn_applied(turn).perft(depth - 1));
+        nodes
+    }
+
+    /// The shortest line of turns that forces a win for `color` within `plies` plies, counting both
+    /// players' turns, or None if the opponent can hold out that long. Where the opponent has a
+    /// choice, the line follows whichever reply holds out the longest.
+    ///
+    /// Unlike the `Ai` this searches every turn, so it's only feasible to around 5 plies. It's
+    /// meant for checking that hand-made puzzles have the solution they claim. Each of `color`'s
+    /// turns that `Ai::deterministic` would pick one ply deep is tried first, since it's usually
+    /// the one that wins. Picking it evaluates every position one ply on, so positions where it's
+    /// `color`'s turn cost about twice what they would otherwise.
+    pub fn forced_win_in(&self, color: Color, plies: u32) -> Option<Vec<Turn>> {
+        let mut ai = Ai::deterministic(0, 1);
+        let mut proven = FxHashMap::default();
+        (0..=plies).find_map(|depth| self.forced_win_line(color, depth, &mut ai, &mut proven))
+    }
+
+    fn forced_win_line(
+        &self,
+        color: Color,
+        plies: u32,
+        ai: &mut Ai,
+        proven: &mut ProvenLines,
+    ) -> Option<Vec<Turn>> {
+        match self.game_result() {
+            GameResult::Winner { color: winner } if winner == color => return Some(vec![]),
+            GameResult::None if plies > 0 => {}
+            _ => return None,
+        }
+        // Different move orders often reach the same position, only search each one once
+        let moved_last_turn = match self.last_turn {
             Some(Move { to, .. }) => Some(to),
             _ => None,
         };
+        let key = (
+            self.zobrist_hash.value(),
+            self.immobilized_piece,
+            moved_last_turn,
+            self.last_turn == Some(Skip),
+            plies,
+        );
+        if let Some(line) = proven.get(&key) {
+            return line.clone();
+        }
 
-        for neighbor in self.hive.topmost_occupied_neighbors(pillbug_hex) {
-            // Cannot move a piece that is in a stack
-            if neighbor.h != 0 {
-                continue;
-            }
-            // Cannot move a piece that just moved
-            if Some(neighbor) == piece_moved_last_turn {
-                continue;
+        let line = if self.active_player == color {
+            let pick = ai.choose_turn(self).ok();
+            pick.into_iter()
+                .chain(self.turns().filter(|turn| Some(*turn) != pick))
+                .find_map(|turn| {
+                    let after = self.with_turn_applied(turn);
+                    let mut line = after.forced_win_line(color, plies - 1, ai, proven)?;
+                    line.insert(0, turn);
+                    Some(line)
+                })
+        } else {
+            let mut longest: Option<Vec<Turn>> = None;
+            for turn in self.turns() {
+                let after = self.with_turn_applied(turn);
+                let Some(mut line) = after.forced_win_line(color, plies - 1, ai, proven) else {
+                    proven.insert(key, None);
+                    return None;
+                };
+                line.insert(0, turn);
+                if longest
+                    .as_ref()
+                    .is_none_or(|longest| line.len() > longest.len())
+                {
+                    longest = Some(line);
+                }
             }
+            longest
+        };
+        proven.insert(key, line.clone());
+        line
+    }
 
-            // Verify that the move onto the pillbug is not blocked
-            if !self.slide_is_allowed(&Hex { h: 1, ..neighbor }, &above_pillbug) {
-                continue;
-            }
+    /// How many of the empty squares around the queen of the given color the active player could
+    /// put a piece into this turn
+    fn reachable_queen_neighbors(&self, color: Color) -> usize {
+        let queen = Tile {
+            bug: Bug::Queen,
+            color,
+        };
+        let Some(queen_hex) = self.find_piece(queen, 1) else {
+            return 0;
+        };
+        let empty_neighbors: FxHashSet<Hex> = neighbors(&queen_hex.base_level())
+            .filter(|hex| !self.hive.map.contains_key(hex))
+            .collect();
 
-            // The only move that could break the hive is the move up onto the pillbug, so we
-            // only check that one
-            if move_would_break_hive(&self.hive, &neighbor, &above_pillbug) {
-                continue;
+        let mut reached = FxHashSet::default();
+        self.for_each_turn(|turn| match turn {
+            Placement { hex, .. } | Move { to: hex, .. } if empty_neighbors.contains(&hex) => {
+                reached.insert(hex);
             }
+            _ => {}
+        });
+        reached.len()
+    }
 
-            // Can move every neighbor to every unoccupied space
-            for free_space in free_spaces.iter() {
-                // Verify that the move down from the pillbug is not blocked
-                let above_free_space = Hex {
-                    h: 1,
-                    ..*free_space
-                };
-                if !self.slide_is_allowed(&above_pillbug, &above_free_space) {
-                    continue;
-                }
-                special_ability_moves.push(Move {
-                    from: neighbor,
-                    to: *free_space,
-                    freezes_piece: true,
-                })
-            }
+    /// How many pieces the given player still has to place
+    pub fn pieces_in_reserve(&self, color: Color) -> usize {
+        match color {
+            Color::White => self.white_reserve.len(),
+            Color::Black => self.black_reserve.len(),
         }
+    }
 
-        direct_moves.chain(special_ability_moves)
+    fn active_reserve(&self) -> &Vec<Bug> {
+        match self.active_player {
+            Color::Black => &self.black_reserve,
+            Color::White => &self.white_reserve,
+        }
     }
 
-    fn grasshopper_moves(&self, from: &Hex) -> impl Iterator<Item = Turn> {
-        // Grasshopper either cannot move at all or can make all moves, so just check for hive
-        // breakage once at the start
-        if move_would_break_hive(&self.hive, from, &Hex{h: 100, ..*from}) {
-            return Either::Left(iter::empty())
+    /// A copy of the game that generates moves as if pieces could never break the hive. Only meant
+    /// for hints like `destinations_ignoring_hive`, games played this way aren't legal Hive.
+    pub fn ignoring_hive_rule(&self) -> Game {
+        Game {
+            ignore_hive_rule: true,
+            ..self.clone()
         }
+    }
 
-        let mut allowed_jumps = vec![];
-        let occupied_neighbors = self.hive.occupied_neighbors_at_same_level(from);
-        for neighbor in occupied_neighbors {
-            let direction = neighbor - *from;
-            let unoccupied_spot = self
+    /// Where the piece at `hex` could move if moving it could never break the hive. Comparing this
+    /// to `valid_destinations_for_piece` shows which moves are only blocked by the one hive rule.
+    pub fn destinations_ignoring_hive(&self, hex: &Hex) -> Vec<Hex> {
+        self.ignoring_hive_rule()
+            .moves_for_piece(hex)
+            .filter_map(|turn| match turn {
+                Move {
+                    from,
+                    to,
+                    freezes_piece: false,
+                } if from == *hex => Some(to),
+                _ => None,
+            })
+            .sorted()
+            .dedup()
+            .collect()
+    }
+
+    /// Every legal destination of the piece at `hex` along with how it gets there, sorted by hex,
+    /// for explaining moves to new players. Includes being pushed by one of the active player's
+    /// pillbugs. A mosquito that could reach a hex by copying more than one of its neighbors is
+    /// explained by the first one found.
+    pub fn annotated_destinations(&self, hex: &Hex) -> Vec<(Hex, MoveKind)> {
+        let Some(tile) = self.hive.tile_at(hex) else {
+            return vec![];
+        };
+        let reaches = |turn: Turn, destination: Hex, pushed: bool| {
+            matches!(turn, Move { from, to, freezes_piece } if from == *hex
+                && to == destination
+                && freezes_piece == pushed)
+        };
+        let kind = |bug: Bug, to: Hex| {
+            if hex.h > 0 || to.h > 0 || bug == Bug::Ladybug {
+                MoveKind::Climb
+            } else if bug == Bug::Grasshopper {
+                MoveKind::Jump
+            } else {
+                MoveKind::Slide
+            }
+        };
+        // A mosquito on the ground moves like its neighbors, anywhere else it's just itself
+        let bugs: Vec<Bug> = if tile.bug == Bug::Mosquito && hex.h == 0 {
+            self.hive
+                .topmost_occupied_neighbors(hex)
+                .filter_map(|neighbor| self.hive.tile_at(&neighbor))
+                .map(|neighbor| neighbor.bug)
+                .filter(|bug| *bug != Bug::Mosquito)
+                .unique()
+                .collect()
+        } else {
+            vec![tile.bug]
+        };
+
+        let mut destinations: Vec<(Hex, MoveKind)> = vec![];
+        if tile.color == self.active_player {
+            let own_moves: Vec<Hex> = self
+                .moves_for_piece(hex)
+                .filter_map(|turn| match turn {
+                    Move {
+                        from,
+                        to,
+                        freezes_piece: false,
+                    } if from == *hex => Some(to),
+                    _ => None,
+                })
+                .unique()
+                .collect();
+            for to in own_moves {
+                let bug = bugs
+                    .iter()
+                    .find(|bug| {
+                        self.moves_for_tile(**bug, hex)
+                            .any(|turn| reaches(turn, to, false))
+                    })
+                    .copied()
+                    .unwrap_or(tile.bug);
+                destinations.push((to, kind(bug, to)));
+            }
+        }
+        for pusher in self.hive.topmost_occupied_neighbors(hex) {
+            if self
                 .hive
-                .next_unoccupied_spot_in_direction(from, &direction);
-            allowed_jumps.push(unoccupied_spot);
+                .tile_at(&pusher)
+                .is_none_or(|pusher| pusher.color != self.active_player)
+            {
+                continue;
+            }
+            for turn in self.moves_for_piece(&pusher) {
+                if let Move { to, .. } = turn
+                    && reaches(turn, to, true)
+                    && !destinations.iter().any(|(hex, _)| *hex == to)
+                {
+                    destinations.push((to, MoveKind::PillbugPush));
+                }
+            }
         }
-        Either::Right(allowed_jumps
-            .into_iter()
-            .map(|to| Move {
-                from: *from,
+        destinations.sort();
+        destinations
+    }
+
+    /// Where a `bug` placed at `hex` by the active player could move on their next turn, if the
+    /// opponent passed in between, for previewing placements. Sorted, and empty if the placement
+    /// isn't legal. Nothing can move while the queen is in reserve, so placing anything but the
+    /// queen before the queen is down previews no moves.
+    pub fn hypothetical_moves(&self, hex: Hex, bug: Bug) -> Vec<Hex> {
+        let placement = Placement {
+            hex,
+            tile: Tile {
+                bug,
+                color: self.active_player,
+            },
+        };
+        let Ok(placed) = self.preview(placement) else {
+            return vec![];
+        };
+        let next_turn = placed.with_turn_applied(Skip);
+        next_turn
+            .moves_for_piece(&hex)
+            .filter_map(|turn| match turn {
+                Move {
+                    from,
+                    to,
+                    freezes_piece: false,
+                } if from == hex => Some(to),
+                _ => None,
+            })
+            .sorted()
+            .dedup()
+            .collect()
+    }
+
+    pub fn valid_destinations_for_piece(&self, hex: &Hex) -> impl Iterator<Item = Hex> {
+        //TODO: This is a slow way to do this
+        self.movement_turns().filter_map(|turn| match turn {
+            Move {
+                from,
                 to,
                 freezes_piece: false,
-            }))
+            } if from == *hex => Some(to),
+            _ => None,
+        })
     }
 
-    fn queen_moves(&self, from: &Hex) -> impl Iterator<Item = Turn> {
-        if self.immobilized_piece == Some(*from) {
-            return Either::Left(iter::empty());
+    /// Every legal turn, or a skip if there are none. The order depends on how the board and
+    /// reserves happen to be stored and isn't worth paying to fix here, so use `turns_sorted`
+    /// wherever the order shows.
+    pub fn turns(&self) -> impl Iterator<Item = Turn> {
+        // If there are no valid turns, you must skip
+        if !self.has_any_turn() {
+            return Either::Left(self.forced_pass());
         }
-
-        Either::Right(
-            self.allowed_slides(from, Some(from))
-                .filter(|possible_move| !move_would_break_hive(&self.hive, from, possible_move))
-                .map(|to| Move {
-                    from: *from,
-                    to,
-                    freezes_piece: false,
-                }),
-        )
+        Either::Right(self.placement_turns().chain(self.movement_turns()))
     }
 
-    fn beetle_moves(&self, from: &Hex) -> impl Iterator<Item = Turn> {
-        if self.immobilized_piece == Some(*from) {
-            return Either::Left(iter::empty());
+    /// Whether the active player has anything to do besides pass. Stops at the first placement or
+    /// move it finds instead of generating them all, so it's the cheap way to tell whether a skip
+    /// is forced.
+    pub fn has_any_turn(&self) -> bool {
+        if self.queen_placement_is_forced() {
+            return self.can_place();
         }
+        self.can_place() || self.movement_turns().next().is_some()
+    }
 
-        Either::Right(
-            neighbors(from)
-                .filter_map(|neighbor| {
-                    let to_height = self.hive.stack_height(&neighbor);
-                    // If we're moving up, we need to check if we're blocked from the level above
-                    // If we're moving down, we need to check if we're blocked at our level
-                    let slide_check_height = max(from.h, to_height);
-                    if self.slide_is_allowed(
-                        &Hex {
-                            h: slide_check_height,
-                            ..*from
-                        },
-                        &Hex {
-                            h: slide_check_height,
-                            ..neighbor
-                        },
-                    ) {
-                        Some(Hex {
-                            h: to_height,
-                            ..neighbor
-                        })
-                    } else {
-                        None
-                    }
+    /// Whether the active player has a piece to place and somewhere they're allowed to put it
+    fn can_place(&self) -> bool {
+        let reserve = self.active_reserve();
+        if self.hive.map.len() <= 1 {
+            // Anything but the queen can go down on either player's first turn
+            return reserve.iter().any(|bug| *bug != Bug::Queen);
+        }
+        !reserve.is_empty()
+            && !self
+                .placement_hexes
+                .get_or_init(self.active_player, || {
+                    self.find_placement_hexes(self.active_player)
                 })
-                .filter(|possible_move| !move_would_break_hive(&self.hive, from, possible_move))
-                .map(|to| Move {
-                    from: *from,
-                    to,
-                    freezes_piece: false,
-                }),
-        )
+                .is_empty()
     }
 
-    fn ladybug_moves(&self, from: &Hex) -> impl Iterator<Item = Turn> {
-        if self.immobilized_piece == Some(*from) {
-            return Either::Left(iter::empty());
+    /// Call `f` with every legal turn. Generates the same turns as `turns()`, but without boxing
+    /// an iterator per piece, which makes it the cheaper choice in hot loops.
+    pub fn for_each_turn(&self, mut f: impl FnMut(Turn)) {
+        if self.must_pass_due_to_queen_rule() {
+            return self.forced_pass().for_each(f);
         }
 
-        let mut paths: Vec<Vec<Hex>> = vec![vec![*from]];
-        let mut new_paths: Vec<Vec<Hex>> = vec![];
+        let mut found_turn = false;
+        let mut emit = |turn| {
+            found_turn = true;
+            f(turn)
+        };
+        self.for_each_placement(&mut emit);
+        self.for_each_move(&mut emit);
 
-        for i in 1..=3 {
-            let last_move = i == 3;
-            for path in paths.iter() {
+        // If there are no valid turns, you must skip
+        if !found_turn {
+            self.forced_pass().for_each(f)
+        }
+    }
+
+    /// The legal turns with one representative kept for each group that lead to the same position
+    /// up to rotating, mirroring or sliding the board, the first in `turns()` order. In the
+    /// opening most placements are mirror images of each other, so this cuts the branching factor
+    /// for perft or a search a long way.
+    ///
+    /// This only says which of this position's turns are redundant with each other. A turn left
+    /// out isn't illegal, and since the symmetry breaks as pieces go down, the turns kept here
+    /// mean nothing for any other position. Positions are compared by everything `canonical_key`
+    /// hashes rather than the key itself, so two different positions can't collide.
+    pub fn symmetry_reduced_turns(&self) -> Vec<Turn> {
+        let mut seen = FxHashSet::default();
+        let mut turns = vec![];
+        self.for_each_turn(|turn| {
+            if seen.insert(self.with_turn_applied(turn).canonical_position()) {
+                turns.push(turn);
+            }
+        });
+        turns
+    }
+
+    /// The same turns as `turns()`, sorted by `Turn`'s ordering so the same position always lists
+    /// them the same way
+    pub fn turns_sorted(&self) -> Vec<Turn> {
+        self.turns().sorted().collect()
+    }
+
+    /// A hash of the set of legal turns, for reusing anything worked out from them, like move
+    /// highlights, between positions that allow exactly the same turns. Boards can differ and
+    /// still match. Doesn't depend on the order turns are generated in.
+    pub fn turns_signature(&self) -> u64 {
+        let mut hasher = FxHasher::default();
+        self.turns()
+            .sorted()
+            .dedup()
+            .collect_vec()
+            .hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// The legal turns that don't leave more of the active player's pieces pinned than there are
+    /// now, see `Hive::pinned_pieces`, for steering new players away from tying up their own
+    /// pieces. This plays out every turn and counts the pins after it, so it's far too slow for
+    /// the search and only meant for hints. Game has no undo, so turns are applied to a copy.
+    pub fn non_pinning_turns(&self) -> Vec<Turn> {
+        let pinned = self.hive.pinned_pieces(self.active_player).len();
+        let mut turns = vec![];
+        self.for_each_turn(|turn| {
+            let after = self.with_turn_applied(turn);
+            if after.hive.pinned_pieces(self.active_player).len() <= pinned {
+                turns.push(turn);
+            }
+        });
+        turns
+    }
+
+    /// The legal turns that use a pillbug's special ability, including a mosquito copying it, so
+    /// they can be shown apart from ordinary moves. These are the moves that freeze the piece
+    /// they move.
+    pub fn ability_turns(&self) -> Vec<Turn> {
+        let mut turns = vec![];
+        self.for_each_turn(|turn| {
+            if let Move {
+                freezes_piece: true,
+                ..
+            } = turn
+            {
+                turns.push(turn);
+            }
+        });
+        turns
+    }
+
+    /// The legal turns that put a piece into `region`, whether by placing it, moving it, or pushing
+    /// it with a pillbug. Heights are ignored, so climbing onto a stack counts as going to the
+    /// stack's hex on the ground, and `region` should only hold ground level hexes.
+    pub fn turns_into(&self, region: &FxHashSet<Hex>) -> Vec<Turn> {
+        let mut turns = vec![];
+        self.for_each_turn(|turn| {
+            let destination = match turn {
+                Placement { hex, .. } => hex,
+                Move { to, .. } => to,
+                Skip => return,
+            };
+            if region.contains(&destination.base_level()) {
+                turns.push(turn);
+            }
+        });
+        turns
+    }
+
+    /// The turns of a player who can't place or move anything: a skip, unless the rules variant
+    /// where they lose instead is on
+    fn forced_pass(&self) -> impl Iterator<Item = Turn> + use<> {
+        (!self.stuck_player_loses).then_some(Skip).into_iter()
+    }
+
+    /// Whether the active player can't place or move anything, so their only turn is a skip
+    fn has_no_legal_turns(&self) -> bool {
+        !self.has_any_turn()
+    }
+
+    /// Whether the active player has to place their queen this turn. If you haven't played your
+    /// queen by your fourth turn, you must play your queen.
+    fn queen_placement_is_forced(&self) -> bool {
+        self.active_reserve().contains(&Bug::Queen) && self.placements_made(self.active_player) >= 3
+    }
+
+    /// How many pieces the given player has placed so far. Pieces never leave the board, so this
+    /// is the same for a game loaded mid-play and doesn't depend on how big the reserves started.
+    pub fn placements_made(&self, color: Color) -> usize {
+        self.hive.piece_count_for(color)
+    }
+
+    /// Whether the active player has to place their queen this turn but there's nowhere legal to
+    /// put it. Pieces can't move until the queen is placed either, so the official rule that a
+    /// player who can't place or move a piece must pass applies, and the only turn is a skip.
+    pub fn must_pass_due_to_queen_rule(&self) -> bool {
+        self.queen_placement_is_forced() && !self.can_place()
+    }
+
+    /// Every legal placement for the active player. Unlike `turns()` this doesn't include a skip
+    /// when there's nothing to place.
+    ///
+    /// The first piece of the game (ply 1) goes at the origin. The second (ply 2, the other
+    /// player's first) can go next to it, which is the only time a piece may be placed touching
+    /// an opponent's. From ply 3 on a piece has to touch one of its own color and none of the
+    /// opponent's. The queen can't be placed on either player's first turn. Which case applies is
+    /// decided by how many pieces are on the board, so it holds for games set up mid-play too.
+    pub fn placement_turns(&self) -> impl Iterator<Item = Turn> + use<> {
+        let mut placements = vec![];
+        self.for_each_placement(&mut |turn| placements.push(turn));
+        placements.into_iter()
+    }
+
+    /// The box around every hex the active player could place a piece on, for fitting a view that
+    /// highlights them. Returns `None` when there's nothing they can place.
+    pub fn placement_bounds(&self) -> Option<RowColDimensions> {
+        let hexes: FxHashSet<Hex> = self
+            .placement_turns()
+            .filter_map(|turn| match turn {
+                Placement { hex, .. } => Some(hex),
+                _ => None,
+            })
+            .collect();
+        bounding_box(hexes.iter())
+    }
+
+    fn for_each_placement(&self, f: &mut impl FnMut(Turn)) {
+        let active_player_reserve = self.active_reserve();
+        if active_player_reserve.is_empty() {
+            return;
+        }
+
+        let placement = |hex: Hex, bug: Bug| Placement {
+            hex,
+            tile: Tile {
+                bug,
+                color: self.active_player,
+            },
+        };
+
+        if self.hive.map.is_empty() {
+            for bug in active_player_reserve
+                .iter()
+                .filter(|bug| **bug != Bug::Queen)
+                .unique()
+            {
+                f(placement(Hex { q: 0, r: 0, h: 0 }, *bug));
+            }
+            return;
+        }
+
+        if self.hive.map.len() == 1 {
+            let only_occupied_hex = self.hive.map.iter().next().unwrap().0;
+            for bug in active_player_reserve
+                .iter()
+                .filter(|bug| **bug != Bug::Queen)
+                .unique()
+            {
+                for hex in neighbors(only_occupied_hex) {
+                    f(placement(hex, *bug));
+                }
+            }
+            return;
+        }
+
+        let bugs: Vec<Bug> = if self.queen_placement_is_forced() {
+            vec![Bug::Queen]
+        } else {
+            active_player_reserve.iter().copied().unique().collect()
+        };
+
+        let hexes = self.placement_hexes.get_or_init(self.active_player, || {
+            self.find_placement_hexes(self.active_player)
+        });
+        for hex in hexes {
+            for bug in bugs.iter() {
+                f(placement(*hex, *bug));
+            }
+        }
+    }
+
+    /// The empty hexes next to one of `color`'s pieces and not next to any of the opponent's, in
+    /// the order they're found
+    fn find_placement_hexes(&self, color: Color) -> Vec<Hex> {
+        let mut placement_checked: FxHashSet<Hex> = FxHashSet::default();
+        let mut hexes = vec![];
+        for (hex, tile) in self.hive.map.iter() {
+            if tile.color == color {
+                for neighbor in self.hive.unoccupied_neighbors(&Hex { h: 0, ..*hex }) {
+                    if placement_checked.insert(neighbor)
+                        && !self.is_adjacent_to_color(&neighbor, &color.opposite())
+                    {
+                        hexes.push(neighbor);
+                    }
+                }
+            }
+        }
+        hexes
+    }
+
+    /// Every legal move of a piece already on the board for the active player, including pillbug
+    /// pushes. Unlike `turns()` this doesn't include a skip when nothing can move.
+    pub fn movement_turns(&self) -> impl Iterator<Item = Turn> {
+        if self.active_reserve().contains(&Bug::Queen) {
+            return Either::Left(iter::empty());
+        }
+
+        Either::Right(
+            self.hive
+                .toplevel_pieces()
+                .filter(|(_, tile)| tile.color == self.active_player)
+                .flat_map(|(hex, tile)| self.moves_for_tile(tile.bug, hex)),
+        )
+    }
+
+    fn for_each_move(&self, f: &mut impl FnMut(Turn)) {
+        if self.active_reserve().contains(&Bug::Queen) {
+            return;
+        }
+
+        for (hex, tile) in self.hive.toplevel_pieces() {
+            if tile.color != self.active_player {
+                continue;
+            }
+            match tile.bug {
+                Bug::Beetle => self.beetle_moves(hex).for_each(&mut *f),
+                Bug::Queen => self.queen_moves(hex).for_each(&mut *f),
+                Bug::Grasshopper => self.grasshopper_moves(hex).for_each(&mut *f),
+                Bug::Ant => self.ant_moves(hex).for_each(&mut *f),
+                Bug::Spider => self.spider_moves(hex).for_each(&mut *f),
+                Bug::Ladybug => self.ladybug_moves(hex).for_each(&mut *f),
+                Bug::Mosquito => self.mosquito_moves(hex).for_each(&mut *f),
+                Bug::Pillbug => self.pillbug_moves(hex).for_each(&mut *f),
+            }
+        }
+    }
+
+    /// The destinations of the piece at `hex` that end on top of the hive, for UIs that want to
+    /// show climbing differently from sliding. Covers beetles and mosquitoes moving as beetles.
+    /// Climbs squeezing between two taller stacks aren't allowed, same as for any other move.
+    pub fn beetle_climb_destinations(&self, hex: &Hex) -> Vec<Hex> {
+        self.moves_for_piece(hex)
+            .filter_map(|turn| match turn {
+                Move {
+                    from,
+                    to,
+                    freezes_piece: false,
+                } if from == *hex && to.h > 0 => Some(to),
+                _ => None,
+            })
+            .unique()
+            .collect()
+    }
+
+    pub fn moves_for_piece<'a>(&'a self, hex: &'a Hex) -> impl Iterator<Item = Turn> {
+        // If you haven't placed your queen yet you're not allowed to move.
+        // Only the top piece in a stack is allowed to move
+        if self.active_reserve().contains(&Bug::Queen) || self.hive.stack_height(hex) != hex.h + 1 {
+            return Either::Left(iter::empty());
+        }
+
+        let tile = self.hive.tile_at(hex).unwrap();
+        Either::Right(self.moves_for_tile(tile.bug, hex))
+    }
+
+    fn moves_for_tile<'a>(&'a self, bug: Bug, hex: &'a Hex) -> Box<dyn Iterator<Item = Turn> + 'a> {
+        match bug {
+            Bug::Beetle => Box::new(self.beetle_moves(hex)),
+            Bug::Queen => Box::new(self.queen_moves(hex)),
+            Bug::Grasshopper => Box::new(self.grasshopper_moves(hex)),
+            Bug::Ant => Box::new(self.ant_moves(hex)),
+            Bug::Spider => Box::new(self.spider_moves(hex)),
+            Bug::Ladybug => Box::new(self.ladybug_moves(hex)),
+            Bug::Mosquito => Box::new(self.mosquito_moves(hex)),
+            Bug::Pillbug => Box::new(self.pillbug_moves(hex)),
+        }
+    }
+
+    fn pillbug_moves(&self, pillbug_hex: &Hex) -> impl Iterator<Item = Turn> {
+        // The Pillbug moves one space at a time, but it also has a special ability it may use
+        // instead of moving.
+        // The special ability allows the Pillbug to move an adjacent piece (friend or enemy) up to
+        // two spaces; up onto itself and then down into another empty space adjacent to itself.
+        //
+        // Exceptions:
+        //  * The Pillbug may not move the piece which was just moved by the other player
+        //  * The Pillbug may not move any piece in a stack of pieces
+        //  * The Pillbug may not move a piece if it splits the hive (violating the One Hive Rule)
+        //  * The Pillbug may not move a piece through a narrow gap of stacked pieces (violating the
+        //    Freedom to Move Rule)
+        //
+        //  Furthermore, any piece moved by the Pillbug may not be moved at all (directly or via
+        //  Pillbug action) on the next player's turn.
+        //  The Mosquito can mimic either the movement or special ability of the Pillbug, even when
+        //  the Pillbug is immobile.
+
+        let direct_moves = if self.immobilized_piece == Some(*pillbug_hex) {
+            Either::Left(iter::empty())
+        } else {
+            Either::Right(self.queen_moves(pillbug_hex))
+        };
+
+        let mut special_ability_moves: Vec<Turn> = vec![];
+        let free_spaces: Vec<_> = self.hive.unoccupied_neighbors(pillbug_hex).collect();
+        let above_pillbug = Hex {
+            h: 1,
+            ..*pillbug_hex
+        };
+        let piece_moved_last_turn = match self.last_turn {
+            Some(Move { to, .. }) => Some(to),
+            _ => None,
+        };
+
+        for neighbor in self.hive.topmost_occupied_neighbors(pillbug_hex) {
+            // Cannot move a piece that is in a stack
+            if neighbor.h != 0 {
+                continue;
+            }
+            // Cannot move a piece that just moved
+            if Some(neighbor) == piece_moved_last_turn {
+                continue;
+            }
+
+            // Verify that the move onto the pillbug is not blocked
+            if !self.can_move_between(&neighbor, &above_pillbug) {
+                continue;
+            }
+
+            // The only move that could break the hive is the move up onto the pillbug, so we
+            // only check that one
+            if self.breaks_hive(&neighbor, &above_pillbug) {
+                continue;
+            }
+
+            // Can move every neighbor to every unoccupied space
+            for free_space in free_spaces.iter() {
+                // Verify that the move down from the pillbug is not blocked
+                if !self.can_move_between(&above_pillbug, free_space) {
+                    continue;
+                }
+                special_ability_moves.push(Move {
+                    from: neighbor,
+                    to: *free_space,
+                    freezes_piece: true,
+                })
+            }
+        }
+
+        direct_moves.chain(special_ability_moves)
+    }
+
+    fn grasshopper_moves(&self, from: &Hex) -> impl Iterator<Item = Turn> {
+        // Grasshopper either cannot move at all or can make all moves, so just check for hive
+        // breakage once at the start
+        if self.breaks_hive(from, &Hex { h: 100, ..*from }) {
+            return Either::Left(iter::empty());
+        }
+
+        let mut allowed_jumps = vec![];
+        let occupied_neighbors = self.hive.occupied_neighbors_at_same_level(from);
+        for neighbor in occupied_neighbors {
+            let direction = neighbor - *from;
+            let unoccupied_spot = self
+                .hive
+                .next_unoccupied_spot_in_direction(from, &direction);
+            allowed_jumps.push(unoccupied_spot);
+        }
+        Either::Right(allowed_jumps
+            .into_iter()
+            .map(|to| Move {
+                from: *from,
+                to,
+                freezes_piece: false,
+            }))
+    }
+
+    fn queen_moves(&self, from: &Hex) -> impl Iterator<Item = Turn> {
+        if self.immobilized_piece == Some(*from) {
+            return Either::Left(iter::empty());
+        }
+
+        Either::Right(
+            self.allowed_slides(from, Some(from))
+                .filter(|possible_move| !self.breaks_hive(from, possible_move))
+                .map(|to| Move {
+                    from: *from,
+                    to,
+                    freezes_piece: false,
+                }),
+        )
+    }
+
+    fn beetle_moves(&self, from: &Hex) -> impl Iterator<Item = Turn> {
+        if self.immobilized_piece == Some(*from) {
+            return Either::Left(iter::empty());
+        }
+
+        Either::Right(
+            neighbors(from)
+                .map(|neighbor| Hex {
+                    h: self.hive.stack_height(&neighbor),
+                    ..neighbor
+                })
+                .filter(|to| self.can_move_between(from, to))
+                .filter(|possible_move| !self.breaks_hive(from, possible_move))
+                .map(|to| Move {
+                    from: *from,
+                    to,
+                    freezes_piece: false,
+                }),
+        )
+    }
+
+    fn ladybug_moves(&self, from: &Hex) -> impl Iterator<Item = Turn> {
+        if self.immobilized_piece == Some(*from) {
+            return Either::Left(iter::empty());
+        }
+
+        let mut paths: Vec<Vec<Hex>> = vec![vec![*from]];
+        let mut new_paths: Vec<Vec<Hex>> = vec![];
+
+        for i in 1..=3 {
+            let last_move = i == 3;
+            for path in paths.iter() {
                 let current = path.last().unwrap();
                 let dests: Vec<Hex> = if last_move {
                     self.hive
                         .unoccupied_neighbors(&Hex { h: 0, ..*current })
-                        .filter(|dest| {
-                            self.slide_is_allowed(
-                                current,
-                                &Hex {
-                                    h: current.h,
-                                    ..*dest
-                                },
-                            )
-                        })
+                        .filter(|dest| self.can_move_between(current, dest))
                         .collect()
                 } else {
                     self.hive
@@ -615,16 +2184,8 @@ impl Game {
                             ..dest
                         })
                         .filter(|dest| dest.base_level() != *from)
-                        .filter(|dest| {
-                            self.slide_is_allowed(
-                                &Hex {
-                                    h: dest.h,
-                                    ..*current
-                                },
-                                dest,
-                            )
-                        })
-                        .filter(|dest| !(i == 1 && move_would_break_hive(&self.hive, from, dest)))
+                        .filter(|dest| self.can_move_between(current, dest))
+                        .filter(|dest| !(i == 1 && self.breaks_hive(from, dest)))
                         .collect()
                 };
 
@@ -655,8 +2216,28 @@ impl Game {
     }
 
     fn spider_moves(&self, from: &Hex) -> impl Iterator<Item = Turn> {
-        if self.immobilized_piece == Some(*from) {
-            return Either::Left(iter::empty());
+        let mut unique_destinations: FxHashSet<Hex> = FxHashSet::default();
+        unique_destinations.extend(self.spider_paths(from).map(|path| *path.last().unwrap()));
+        unique_destinations.into_iter().map(|to| Move {
+            from: *from,
+            to,
+            freezes_piece: false,
+        })
+    }
+
+    /// For each destination of the spider at `from`, how many distinct three step slides reach it
+    pub fn spider_path_count(&self, from: &Hex) -> FxHashMap<Hex, usize> {
+        let mut counts: FxHashMap<Hex, usize> = FxHashMap::default();
+        for path in self.spider_paths(from) {
+            *counts.entry(*path.last().unwrap()).or_default() += 1;
+        }
+        counts
+    }
+
+    /// Every legal three step path of the spider at `from`, starting with `from` itself
+    fn spider_paths(&self, from: &Hex) -> impl Iterator<Item = Vec<Hex>> {
+        if self.immobilized_piece == Some(*from) {
+            return Either::Left(iter::empty());
         }
         let mut paths: Vec<Vec<Hex>> = vec![vec![*from]];
         let mut new_paths: Vec<Vec<Hex>> = vec![];
@@ -671,7 +2252,7 @@ impl Game {
                     }
                     // The spider can only break the hive on its first move as long as it is adjacent to
                     // something at each step. I think?!?!?!
-                    if first_move && move_would_break_hive(&self.hive, current, &dest)
+                    if first_move && self.breaks_hive(current, &dest)
                         || !first_move
                             && self.slide_would_separate_self_from_hive(current, &dest, from)
                     {
@@ -690,18 +2271,7 @@ impl Game {
             new_paths.clear();
         }
 
-        let mut unique_destinations: FxHashSet<Hex> = FxHashSet::default();
-        unique_destinations.extend(
-            paths
-                .into_iter()
-                .filter(|path| path.len() == 4)
-                .map(|path| *path.last().unwrap()),
-        );
-        Either::Right(unique_destinations.into_iter().map(|to| Move {
-            from: *from,
-            to,
-            freezes_piece: false,
-        }))
+        Either::Right(paths.into_iter().filter(|path| path.len() == 4))
     }
 
     fn ant_moves(&self, from: &Hex) -> impl Iterator<Item = Turn> {
@@ -723,7 +2293,7 @@ impl Game {
                 }
                 // The ant can only break the hive on its first move as long as it is adjacent to
                 // something at each step. I think?!?!?!
-                if first_move && move_would_break_hive(&self.hive, &current, &dest)
+                if first_move && self.breaks_hive(&current, &dest)
                     || !first_move
                         && self.slide_would_separate_self_from_hive(&current, &dest, from)
                 {
@@ -743,6 +2313,11 @@ impl Game {
     }
 
     fn mosquito_moves(&self, start: &Hex) -> impl Iterator<Item = Turn> {
+        // On top of the hive the mosquito can only move like a beetle
+        if start.h > 0 {
+            return Either::Left(self.beetle_moves(start));
+        }
+
         let immobilized = self.immobilized_piece == Some(*start);
 
         let adjacent_bugs: Vec<_> = self
@@ -755,898 +2330,2934 @@ impl Game {
             .filter(|bug| !immobilized || *bug == Bug::Pillbug)
             .collect();
 
-        let mut turns: FxHashSet<Turn> = FxHashSet::default();
-        for bug in adjacent_bugs {
-            turns.extend(self.moves_for_tile(bug, start))
-        }
-
-        turns.into_iter()
+        let mut turns: FxHashSet<Turn> = FxHashSet::default();
+        for bug in adjacent_bugs {
+            turns.extend(self.moves_for_tile(bug, start))
+        }
+
+        Either::Right(turns.into_iter())
+    }
+
+    fn breaks_hive(&self, from: &Hex, to: &Hex) -> bool {
+        !self.ignore_hive_rule && move_would_break_hive(&self.hive, from, to)
+    }
+
+    fn slide_would_separate_self_from_hive(&self, from: &Hex, to: &Hex, ignore_hex: &Hex) -> bool {
+        !self
+            .hive
+            .occupied_neighbors_at_same_level(from)
+            .any(|neighbor| neighbor != *ignore_hex && is_adjacent(&neighbor, to))
+    }
+
+    /// Checks the freedom to move rule for a single step between two adjacent hexes at the same
+    /// height. Returns false if the hexes aren't adjacent or aren't at the same height.
+    pub fn can_slide(&self, from: &Hex, to: &Hex) -> bool {
+        from.h == to.h && is_adjacent(from, to) && self.slide_is_allowed(from, to)
+    }
+
+    /// Checks the freedom to move rule for a single step between two adjacent stacks, whether it
+    /// slides along the ground, climbs onto a stack or drops off one. `from` is where the piece
+    /// is and `to` is where it ends up, heights included. The step happens at the higher of the
+    /// two heights, so only pieces at least that high on both sides block it. Returns false if
+    /// the hexes aren't next to each other.
+    pub fn can_move_between(&self, from: &Hex, to: &Hex) -> bool {
+        let h = max(from.h, to.h);
+        is_adjacent(&from.base_level(), &to.base_level())
+            && self.slide_is_allowed(&Hex { h, ..*from }, &Hex { h, ..*to })
+    }
+
+    fn slide_is_allowed(&self, from: &Hex, to: &Hex) -> bool {
+        assert_eq!(from.h, to.h, "Slides must stay at the same height");
+
+        // To test if a slide is allowed, we need to check if the two adjacent tiles to the slide
+        // are blocking the slide. For example in this board:
+        // .  .  1
+        //  .  Q  d
+        // .  .  2
+        // To check if Q can move to position d, we need to check spaces 1 and 2. If both are
+        // filled, Q cannot move there.
+        let mov = to - from;
+        let counter_clockwise_neighbor = from
+            + &Hex {
+                q: -mov.s(),
+                r: -mov.q,
+                h: 0,
+            };
+        let clockwise_neighbor = from
+            + &Hex {
+                q: -mov.r,
+                r: -mov.s(),
+                h: 0,
+            };
+
+        !self.hive.is_occupied(&clockwise_neighbor)
+            || !self.hive.is_occupied(&counter_clockwise_neighbor)
+    }
+
+    fn allowed_slides(
+        &self,
+        hex: &Hex,
+        ignore_hex: Option<&Hex>,
+    ) -> impl Iterator<Item = Hex> + use<> {
+        let neighbors: Vec<Hex> = self.hive.neighbors_at_same_level(hex).collect();
+        let is_empty = |hex: &Hex| !self.hive.is_occupied(hex) || Some(hex) == ignore_hex;
+
+        // Neighbors are in clockwise order, so a piece can slide into an empty neighbor as long as
+        // one of the neighbors on either side of it, wrapping around, is empty too
+        let mut allowed_slides: Vec<Hex> = vec![];
+        for (i, neighbor) in neighbors.iter().enumerate() {
+            let previous = &neighbors[(i + neighbors.len() - 1) % neighbors.len()];
+            let next = &neighbors[(i + 1) % neighbors.len()];
+            if is_empty(neighbor) && (is_empty(previous) || is_empty(next)) {
+                allowed_slides.push(*neighbor);
+            }
+        }
+
+        allowed_slides.into_iter()
+    }
+
+    fn is_adjacent_to_color(&self, hex: &Hex, color: &Color) -> bool {
+        self.neighbors_of_color(hex, *color).next().is_some()
+    }
+
+    /// How many of the stacks around `hex` have one of `color`'s pieces on top
+    pub fn adjacent_friendly(&self, hex: &Hex, color: Color) -> usize {
+        self.neighbors_of_color(hex, color).count()
+    }
+
+    /// How many of the stacks around `hex` have one of the opponent of `color`'s pieces on top, so
+    /// a beetle sitting on a friendly piece counts as the opponent's
+    pub fn adjacent_enemy(&self, hex: &Hex, color: Color) -> usize {
+        self.neighbors_of_color(hex, color.opposite()).count()
+    }
+
+    /// The neighbors of `hex` whose topmost piece is `color`'s
+    fn neighbors_of_color(&self, hex: &Hex, color: Color) -> impl Iterator<Item = Hex> {
+        self.hive
+            .topmost_occupied_neighbors(hex)
+            .filter(move |adjacent_hex| {
+                self.hive
+                    .map
+                    .get(adjacent_hex)
+                    .is_some_and(|tile| tile.color == color)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::hex::{Direction, neighbor};
+    use crate::engine::hive::{HiveBuilder, TileMap};
+    use crate::engine::parse::{hex_map_to_string, parse_hex_map_string};
+    use crate::engine::row_col::RowCol;
+    use Turn::Move;
+    use Turn::Placement;
+    use proptest::prelude::*;
+    use rand::SeedableRng;
+    use rand::prelude::IndexedRandom;
+    use rand::rngs::StdRng;
+    use rustc_hash::FxHashMap;
+    use std::collections::BTreeSet;
+    use strum::IntoEnumIterator;
+
+    fn turns_to_string(hex_map: &FxHashMap<Hex, String>, turns: Vec<Turn>) -> String {
+        let mut turns_map = hex_map.clone();
+        for turn in turns {
+            match turn {
+                Placement { hex, tile: _ } => {
+                    turns_map.insert(hex, "*".to_owned());
+                }
+                Move { to, .. } => {
+                    turns_map.insert(to, "*".to_owned());
+                }
+                Skip => {}
+            }
+        }
+        hex_map_to_string(&turns_map)
+    }
+
+    #[allow(clippy::useless_conversion)]
+    fn assert_placements(placements: &str) {
+        let placements_map = parse_hex_map_string(placements).unwrap();
+        let mut expected_placements: Vec<Turn> = placements_map
+            .iter()
+            .filter(|(_, token)| *token == "*")
+            .map(|(hex, _)| Placement {
+                hex: *hex,
+                tile: Tile {
+                    bug: Bug::Queen,
+                    color: Color::White,
+                },
+            })
+            .collect();
+
+        let hex_map: FxHashMap<Hex, String> = placements_map
+            .into_iter()
+            .filter(|(_, token)| *token != "*")
+            .collect();
+        let hive = Hive::from_hex_map(&hex_map).unwrap();
+        let game = Game::from_hive(hive, Color::White);
+
+        let mut actual_placements: Vec<Turn> = game
+            .turns()
+            .into_iter()
+            .filter(|turn| matches!(turn, Placement { .. }))
+            .collect();
+
+        expected_placements.sort();
+        actual_placements.sort();
+
+        if expected_placements != actual_placements {
+            let actual_placements_map = turns_to_string(&hex_map, actual_placements);
+            let expected_placements_map = turns_to_string(&hex_map, expected_placements);
+
+            pretty_assertions::assert_eq!(expected_placements_map, actual_placements_map);
+        }
+    }
+
+    fn assert_pillbug_pushes(moves: &str) {
+        let mut moves_map = parse_hex_map_string(moves).unwrap();
+        let push_target_hex = moves_map
+            .iter()
+            .find(|(_, char)| *char == "&")
+            .map(|(hex, _)| *hex)
+            .unwrap();
+
+        moves_map.insert(push_target_hex, "a".into());
+
+        _assert_moves(&moves_map, push_target_hex, true)
+    }
+
+    fn assert_moves(moves: &str) {
+        let moves_map = parse_hex_map_string(moves).unwrap();
+        let (from, _) = moves_map
+            .iter()
+            .find(|(_, token)| token.chars().next().unwrap().is_uppercase())
+            .unwrap();
+
+        _assert_moves(&moves_map, *from, false)
+    }
+
+    #[allow(clippy::into_iter_on_ref, clippy::clone_on_copy)]
+    fn _assert_moves(moves_map: &FxHashMap<Hex, String>, for_hex: Hex, freezes_piece: bool) {
+        let mut expected_moves: Vec<Turn> = moves_map
+            .iter()
+            .filter(|(_, token)| *token == "*")
+            .map(|(hex, _)| Move {
+                from: for_hex,
+                to: *hex,
+                freezes_piece,
+            })
+            .collect();
+
+        let hex_map: FxHashMap<Hex, String> = moves_map
+            .into_iter()
+            .filter(|(_, token)| *token != "*")
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        let hive = Hive::from_hex_map(&hex_map).unwrap();
+        let game = Game::from_hive_with_reserves(hive, Color::White, vec![], vec![]);
+
+        let mut actual_moves: Vec<Turn> = game.turns().collect();
+
+        actual_moves.retain(|turn| match turn {
+            Move { from, .. } => *from == for_hex,
+            _ => false,
+        });
+
+        expected_moves.sort();
+        actual_moves.sort();
+
+        if expected_moves != actual_moves {
+            let expected_moves_map = turns_to_string(&hex_map, expected_moves);
+            let actual_moves_map = turns_to_string(&hex_map, actual_moves);
+            pretty_assertions::assert_str_eq!(expected_moves_map, actual_moves_map)
+        }
+    }
+
+    #[test]
+    fn test_placement() {
+        assert_placements(
+            r#"
+            .  a  .
+             .  B  *
+            .  *  *
+        "#,
+        )
+    }
+
+    #[test]
+    fn only_the_second_placement_may_touch_the_opponent() {
+        let placement_hexes = |game: &Game| -> FxHashSet<Hex> {
+            game.placement_turns()
+                .map(|turn| match turn {
+                    Placement { hex, .. } => hex,
+                    _ => unreachable!(),
+                })
+                .collect()
+        };
+        let touches = |hex: &Hex, color: Color, game: &Game| {
+            game.hive
+                .map
+                .iter()
+                .any(|(other, tile)| tile.color == color && is_adjacent(hex, other))
+        };
+        let placed_queen = |game: &Game| {
+            game.placement_turns()
+                .any(|turn| matches!(turn, Placement { tile, .. } if tile.bug == Bug::Queen))
+        };
+
+        // Ply 1: only the origin, and not the queen
+        let game = Game::default();
+        assert_eq!(
+            placement_hexes(&game),
+            FxHashSet::from_iter([Hex::default()])
+        );
+        assert!(!placed_queen(&game));
+
+        // Ply 2: anywhere around white's piece, still not the queen
+        let game = Game::from_uhp("Base", &["wS1"]).unwrap();
+        let hexes = placement_hexes(&game);
+        assert_eq!(hexes.len(), 6);
+        assert!(hexes.iter().all(|hex| touches(hex, Color::White, &game)));
+        assert!(!placed_queen(&game));
+
+        // Ply 3 and 4: next to your own piece and away from the opponent's, queen allowed
+        for moves in [&["wS1", "bG1 wS1-"][..], &["wS1", "bG1 wS1-", "wQ -wS1"]] {
+            let game = Game::from_uhp("Base", moves).unwrap();
+            let color = game.active_player;
+            let hexes = placement_hexes(&game);
+            assert_eq!(hexes.len(), 3);
+            assert!(hexes.iter().all(|hex| touches(hex, color, &game)));
+            assert!(
+                !hexes
+                    .iter()
+                    .any(|hex| touches(hex, color.opposite(), &game))
+            );
+            assert!(placed_queen(&game));
+        }
+    }
+
+    #[test]
+    fn placement_bounds_cover_every_placement() {
+        let bounds = Game::default().placement_bounds().unwrap();
+        assert_eq!((bounds.width(), bounds.height()), (1, 1));
+
+        // White's pieces are all on the right, so the box doesn't reach back to the origin
+        let game = Game::from_hive_with_reserves(
+            ".  q  Q  A".parse().unwrap(),
+            Color::White,
+            vec![Bug::Ant],
+            vec![],
+        );
+        let bounds = game.placement_bounds().unwrap();
+        let (rows, cols) = (
+            bounds.row_min..=bounds.row_max,
+            bounds.col_min..=bounds.col_max,
+        );
+        for turn in game.placement_turns() {
+            let Placement { hex, .. } = turn else {
+                unreachable!()
+            };
+            let oddr = RowCol::from_hex(&hex);
+            assert!(rows.contains(&oddr.row) && cols.contains(&oddr.col));
+        }
+        assert_eq!((bounds.row_min, bounds.row_max), (-1, 1));
+        assert_eq!((bounds.col_min, bounds.col_max), (2, 4));
+
+        let game = Game::from_hive_with_reserves(game.hive, Color::White, vec![], vec![]);
+        assert!(game.placement_bounds().is_none());
+    }
+
+    #[test]
+    fn test_placement_with_multiple_layers() {
+        assert_placements(
+            r#"
+        Layer 0
+            .  a  .
+             .  B  .
+            .  .  .
+        Layer 1
+            .  a  .
+             .  b  .
+            .  .  .
+        "#,
+        )
+    }
+
+    #[test]
+    fn test_placement_not_allowed_above_layer_0() {
+        assert_placements(
+            r#"
+        Layer 0
+            .  a  .
+             .  b  .
+            .  .  a
+        Layer 1
+            .  .  .
+             .  B  .
+            .  .  .
+        "#,
+        )
+    }
+
+    #[test]
+    fn test_placement_uses_top_layer_for_hex_color() {
+        assert_placements(
+            r#"
+        Layer 0
+            .  a  .
+             .  b  *
+            .  *  *
+        Layer 1
+            .  .  .
+             .  B  .
+            .  .  .
+        "#,
+        )
+    }
+
+    #[test]
+    #[allow(clippy::len_zero)]
+    fn test_must_place_queen_by_turn_four() {
+        let hex_map = parse_hex_map_string(
+            r#"
+            .  A  .
+             A  A  .
+            .  .  .
+        "#,
+        )
+        .unwrap();
+
+        let hive = Hive::from_hex_map(&hex_map).unwrap();
+        let game = Game::from_hive(hive, Color::White);
+
+        let moves = game.turns();
+        let (queen_placements, non_queen_placements): (Vec<Turn>, Vec<Turn>) =
+            moves.partition(|mv| {
+                matches!(
+                    mv,
+                    Placement {
+                        tile: Tile {
+                            bug: Bug::Queen,
+                            ..
+                        },
+                        ..
+                    }
+                )
+            });
+
+        assert!(queen_placements.len() > 0);
+        assert_eq!(non_queen_placements.len(), 0);
+    }
+
+    #[test]
+    fn test_queen_not_forced_early_with_smaller_reserve() {
+        // Without the expansion pieces the reserve starts smaller, but on white's second turn
+        // there's still no need to place the queen
+        let hex_map = parse_hex_map_string(
+            r#"
+            S  s
+        "#,
+        )
+        .unwrap();
+        let hive = Hive::from_hex_map(&hex_map).unwrap();
+        let mut reserve = Vec::from(&DEFAULT_RESERVE[..11]);
+        reserve.retain(|bug| *bug != Bug::Spider);
+        let game = Game::from_hive_with_reserves(hive, Color::White, reserve.clone(), reserve);
+
+        assert!(game.turns().any(|turn| matches!(
+            turn,
+            Placement {
+                tile: Tile { bug: Bug::Ant, .. },
+                ..
+            }
+        )));
+    }
+
+    #[test]
+    fn draws_when_neither_player_can_do_anything_but_pass() {
+        // Queens can't go down on the first turn, and nothing can move before its queen is placed,
+        // so with nothing but queens in reserve both players are stuck from the start
+        let game = Game::from_hive_with_reserves(
+            Game::default().hive,
+            Color::White,
+            vec![Bug::Queen],
+            vec![Bug::Queen],
+        );
+        assert_eq!(game.turns().collect_vec(), vec![Skip]);
+        assert_eq!(game.game_result(), GameResult::None);
+
+        let passed = game.with_turn_applied(Skip);
+        assert_eq!(passed.turns().collect_vec(), vec![Skip]);
+        assert_eq!(passed.game_result(), GameResult::Draw);
+
+        // A pass when the other player still has turns isn't a draw
+        assert_eq!(
+            Game::default().with_turn_applied(Skip).game_result(),
+            GameResult::None
+        );
+    }
+
+    #[test]
+    fn queen_deadline_holds_with_a_variant_reserve() {
+        let reserve = vec![Bug::Queen, Bug::Ant, Bug::Ant, Bug::Ant, Bug::Ant];
+        let mut game = Game::from_hive_with_reserves(
+            Game::default().hive,
+            Color::White,
+            reserve.clone(),
+            reserve,
+        );
+        for _ in 0..6 {
+            let turn = game
+                .turns()
+                .find(|turn| matches!(turn, Placement { tile, .. } if tile.bug == Bug::Ant))
+                .unwrap();
+            game = game.with_turn_applied(turn);
+        }
+        assert_eq!(game.placements_made(Color::White), 3);
+        assert_eq!(game.placements_made(Color::Black), 3);
+
+        let is_queen =
+            |turn: &Turn| matches!(turn, Placement { tile, .. } if tile.bug == Bug::Queen);
+        assert!(game.turns().all(|turn| is_queen(&turn)));
+        let game = game.with_turn_applied(game.turns().next().unwrap());
+        assert_eq!(game.placements_made(Color::White), 4);
+        assert!(game.turns().all(|turn| is_queen(&turn)));
+    }
+
+    #[test]
+    fn test_queen_cannot_move_out_from_under_beetle() {
+        assert_moves(
+            r#"
+        Layer 0
+            .  a  .
+             .  Q  .
+            .  .  .
+        Layer 1
+            .  .  .
+             .  b  .
+            .  .  .
+        "#,
+        )
+    }
+
+    #[test]
+    fn test_queen_move_slide() {
+        assert_moves(
+            r#"
+            .  a  *
+             *  Q  .
+            .  .  .
+        "#,
+        );
+    }
+
+    #[test]
+    fn test_queen_move_does_not_break_hive() {
+        assert_moves(
+            r#"
+            .  a  .
+             .  Q  a
+            .  .  .
+        "#,
+        );
+    }
+
+    #[test]
+    fn test_queen_move_escapes_semicircle() {
+        assert_moves(
+            r#"
+            .  a  *
+             b  Q  *
+            .  b  a
+        "#,
+        );
+    }
+
+    #[test]
+    fn test_queen_move_does_not_temporarily_break_hive() {
+        assert_moves(
+            r#"
+            .  b  b  .
+             q  *  a  .
+            .  .  Q  *
+        "#,
+        );
+    }
+
+    #[test]
+    fn test_queen_move_can_escape_semicircle_in_top_left() {
+        assert_moves(
+            r#"
+            .  *  a
+             *  Q  a
+            .  b  b
+        "#,
+        );
+    }
+
+    #[test]
+    fn test_queen_move_can_escape_semicircle_in_top() {
+        assert_moves(
+            r#"
+            .  *  *
+             a  Q  a
+            .  b  b
+        "#,
+        );
+    }
+
+    #[test]
+    fn test_beetle_move_slide() {
+        assert_moves(
+            r#"
+        Layer 0
+            .  a  *
+             *  B  .
+            .  .  .
+        Layer 1
+            .  *  .
+             .  .  .
+            .  .  .
+        "#,
+        );
+    }
+
+    #[test]
+    fn test_beetle_can_slide_down() {
+        assert_moves(
+            r#"
+            Layer 0
+            .  *  a
+             a  q  *
+            .  *  *
+            Layer 1
+            .  .  *
+             *  B  .
+            .  .  .
+            "#,
+        );
+    }
+
+    #[test]
+    fn test_beetle_move_from_higher_layer() {
+        assert_moves(
+            r#"
+        Layer 0
+            .  a  *
+             *  a  *
+            .  *  *
+        Layer 1
+            .  *  .
+             .  B  .
+            .  .  .
+        "#,
+        );
+    }
+
+    #[test]
+    fn test_beetle_move_does_not_break_hive() {
+        assert_moves(
+            r#"
+        Layer 0
+            .  a  .
+             .  B  a
+            .  b  .
+        "#,
+        );
+    }
+
+    #[test]
+    fn test_beetle_move_can_slide_or_mount() {
+        assert_moves(
+            r#"
+        Layer 0
+            *  a  .
+             B  b  .
+            *  a  .
+        Layer 1
+            .  *  .
+           .  *  .
+            .  *  .
+        "#,
+        );
+    }
+
+    #[test]
+    fn test_grasshopper_move_jumps() {
+        assert_moves(
+            r#"
+            .  *  .
+             .  a  .
+            *  a  G
+        "#,
+        );
+    }
+
+    #[test]
+    fn test_grasshopper_jumps_over_stacks() {
+        assert_moves(
+            r#"
+        Layer 0
+            .  .  .  .  .
+             G  a  q  *  .
+            .  .  .  .  .
+        Layer 1
+            .  .  .  .  .
+             .  .  b  .  .
+            .  .  .  .  .
+        "#,
+        );
+    }
+
+    #[test]
+    fn test_ant_move_unlimited_slides() {
+        assert_moves(
+            r#"
+            .  A  *
+             *  q  *
+            .  *  *
+        "#,
+        );
+    }
+
+    #[test]
+    fn test_valid_spider_moves() {
+        assert_moves(
+            r#"
+            .  S  .
+             .  b  .
+            .  .  *
+        "#,
+        );
+    }
+
+    #[test]
+    fn test_spider_moves_finds_multiple_paths_to_same_location() {
+        assert_moves(
+            r#"
+            *  *  *  *
+             a  .  a  .
+            b  .  S  g
+             g  g  a  .
+        "#,
+        );
+    }
+
+    #[test]
+    fn test_grasshopper_does_not_break_hive() {
+        assert_moves(
+            r#"
+            .  a  .
+             .  G  .
+            .  .  a
+        "#,
+        );
+    }
+
+    #[test]
+    fn test_spider_cannot_make_illegal_slides() {
+        assert_moves(
+            r#"
+            q  .
+             a  .
+            .  q
+             S  .
+            .  q
+             a  .
+        "#,
+        );
+    }
+
+    #[test]
+    fn test_spider_cannot_temporarily_break_hive() {
+        assert_moves(
+            r#"
+            .  a  q
+             .  .  a
+            s  S  a
+        "#,
+        );
+    }
+
+    #[test]
+    fn test_ant_cannot_temporarily_break_hive() {
+        assert_moves(
+            r#"
+            .  a  q
+             .  .  a
+            s  A  a
+        "#,
+        );
+    }
+
+    #[test]
+    fn test_beetle_cannot_temporarily_break_hive() {
+        assert_moves(
+            r#"
+            .  .  .
+             .  a  a
+            .  .  Q
+             .  .  a
+        "#,
+        );
+    }
+
+    #[test]
+    fn test_beetle_cannot_break_slide_rules_when_mounting() {
+        assert_moves(
+            r#"
+            Layer 0
+            .  a  a
+             a  B  *
+            .  *  .
+            Layer 1
+            .  .  b
+             b  .  .
+            .  .  .
+            Layer 2
+            .  .  *
+             *  .  .
+            .  .  .
+            "#,
+        )
+    }
+
+    #[test]
+    fn test_ladybug_movement() {
+        assert_moves(
+            r#"
+            .  *  *
+             *  a  *
+            .  a  *
+             .  L  .
+            "#,
+        );
+    }
+
+    #[test]
+    fn test_ladybug_can_traverse_any_height() {
+        assert_moves(
+            r#"
+            Layer 0
+            .  *  *
+             *  a  *
+            .  a  *
+             .  L  .
+            Layer 1
+            .  .  .
+             .  B  .
+            .  .  .
+             .  .  .
+            Layer 2
+            .  .  .
+             .  b  .
+            .  .  .
+             .  .  .
+            "#,
+        );
+    }
+
+    #[test]
+    fn test_ladybug_cant_make_illegal_slides() {
+        assert_moves(
+            r#"
+            Layer 0
+            .  .  a  .
+             q  a  *  .
+            .  *  a  .
+             .  .  L  .
+            Layer 1
+            .  .  b  .
+             b  .  .  .
+            .  .  .  .
+             .  .  .  .
+            "#,
+        );
+    }
+
+    #[test]
+    fn ladybug_on_a_tall_stack_crosses_a_height_two_gate() {
+        // The ladybug climbs onto the two high stack next to it, then steps down onto the ant
+        // between two more stacks of two. Up at the third level it passes over them, the same
+        // as a beetle would.
+        let ladybug = Hex { q: -1, r: 0, h: 0 };
+        let high = Hex { q: 0, r: 0, h: 2 };
+        let ant = Hex { q: 1, r: 0, h: 1 };
+        let hive = HiveBuilder::new()
+            .place(-1, 0, 0, Bug::Ladybug, Color::White)
+            .place(0, 0, 0, Bug::Queen, Color::White)
+            .place(0, 0, 1, Bug::Beetle, Color::Black)
+            .place(1, 0, 0, Bug::Ant, Color::Black)
+            .place(1, -1, 0, Bug::Queen, Color::Black)
+            .place(1, -1, 1, Bug::Beetle, Color::White)
+            .place(0, 1, 0, Bug::Ant, Color::White)
+            .place(0, 1, 1, Bug::Beetle, Color::White)
+            .build()
+            .unwrap();
+        let game = Game::from_hive_with_reserves(hive, Color::White, vec![], vec![]);
+
+        assert!(game.can_move_between(&ladybug, &high));
+        assert!(game.can_move_between(&high, &ant));
+        assert!(game.can_move_between(&ant, &Hex { q: 2, r: 0, h: 0 }));
+        // One level lower the two stacks close the gate
+        assert!(!game.can_move_between(&Hex { h: 1, ..high }, &ant));
+        assert!(!game.can_move_between(&high, &Hex { q: 2, r: 0, h: 1 }));
+
+        // The only way to reach the far side of the ant is across the gate
+        let destinations: Vec<Hex> = game.valid_destinations_for_piece(&ladybug).collect();
+        assert!(destinations.contains(&Hex { q: 2, r: 0, h: 0 }));
+    }
+
+    #[test]
+    fn test_ladybug_cant_break_hive() {
+        assert_moves(
+            r#"
+            .  .  .  .
+             .  a  .  .
+            .  .  a  .
+             .  .  L  .
+            .  .  .  a
+            "#,
+        );
+    }
+
+    #[test]
+    fn test_ant_can_go_in_and_out_of_pocket() {
+        assert_moves(
+            r#"
+            .  *  *  *
+             *  a  a  A
+            .  *  *  a  *
+             .  *  a  *
+            .  .  *  *
+        "#,
+        );
+    }
+
+    #[test]
+    fn test_mosquito_can_copy_queen() {
+        assert_moves(
+            r#"
+        .  .  .
+         .  q  *
+        .  *  M
+        "#,
+        )
+    }
+
+    #[test]
+    fn test_mosquito_can_copy_multiple_abilities() {
+        assert_moves(
+            r#"
+        .  *  .  *
+         .  q  g  .
+        .  *  M  *
+        "#,
+        )
+    }
+
+    #[test]
+    fn test_mosquito_cannot_copy_another_mosquito() {
+        assert_moves(
+            r#"
+        .  q  .
+         .  m  .
+        .  .  M
+        "#,
+        );
+    }
+
+    #[test]
+    fn test_mosquitoes_next_to_only_mosquitoes_cannot_move() {
+        let hive: Hive = "M  m".parse().unwrap();
+        for color in [Color::White, Color::Black] {
+            let game = Game::from_hive_with_reserves(hive.clone(), color, vec![], vec![]);
+            let mosquito = game.hive.pieces_of(Bug::Mosquito, color)[0];
+            assert_eq!(game.moves_for_piece(&mosquito).count(), 0);
+            assert_eq!(game.turns().collect::<Vec<_>>(), vec![Skip]);
+        }
+    }
+
+    #[test]
+    fn test_mosquito_on_top_of_hive_moves_like_beetle() {
+        let hive: Hive = r#"
+        Layer 0
+        .  g  a  .
+         .  q  .
+        Layer 1
+        .  .  M  .
+         .  .  .
+        "#
+        .parse()
+        .unwrap();
+        let game = Game::from_hive_with_reserves(hive, Color::White, vec![], vec![]);
+        let mosquito = game.hive.pieces_of(Bug::Mosquito, Color::White)[0];
+        assert_eq!(mosquito.h, 1);
+
+        let moves: FxHashSet<Turn> = game.moves_for_piece(&mosquito).collect();
+        let beetle_moves: FxHashSet<Turn> = game.beetle_moves(&mosquito).collect();
+        assert!(!moves.is_empty());
+        assert_eq!(moves, beetle_moves);
+    }
+
+    #[test]
+    fn test_pillbug_can_use_special_ability() {
+        assert_pillbug_pushes(
+            r#"
+        .  *  *
+         *  P  *
+        .  &  *
+        "#,
+        );
+    }
+
+    #[test]
+    fn annotated_destinations_explain_each_move() {
+        let kinds = |map: &str, bug: Bug, color: Color| -> BTreeSet<MoveKind> {
+            let game =
+                Game::from_hive_with_reserves(map.parse().unwrap(), Color::White, vec![], vec![]);
+            let hex = game.hive.pieces_of(bug, color)[0];
+            let destinations = game.annotated_destinations(&hex);
+            if color == game.active_player {
+                let legal: FxHashSet<Hex> = game.valid_destinations_for_piece(&hex).collect();
+                let annotated: FxHashSet<Hex> = destinations.iter().map(|(to, _)| *to).collect();
+                assert_eq!(annotated, legal);
+            }
+            destinations.into_iter().map(|(_, kind)| kind).collect()
+        };
+
+        let slides = BTreeSet::from([MoveKind::Slide]);
+        assert_eq!(kinds("Q  q", Bug::Queen, Color::White), slides);
+        let jumps = BTreeSet::from([MoveKind::Jump]);
+        assert_eq!(kinds("G  Q  q", Bug::Grasshopper, Color::White), jumps);
+        let climbs = BTreeSet::from([MoveKind::Climb, MoveKind::Slide]);
+        assert_eq!(kinds("B  Q  q", Bug::Beetle, Color::White), climbs);
+        let pushes = BTreeSet::from([MoveKind::PillbugPush]);
+        assert_eq!(kinds("q  Q  P  a", Bug::Ant, Color::Black), pushes);
+        // Black's queen isn't next to white's pillbug
+        assert_eq!(
+            kinds("q  Q  P  a", Bug::Queen, Color::Black),
+            BTreeSet::new()
+        );
+    }
+
+    #[test]
+    fn test_pillbug_can_slide() {
+        assert_moves(
+            r#"
+            .  .  .
+             .  q  *
+            .  *  P
+        "#,
+        )
+    }
+
+    #[test]
+    fn test_pillbug_cannot_pull_through_blocked_gap() {
+        assert_pillbug_pushes(
+            r#"
+        Layer 0
+        .  .  .
+         .  P  a
+        .  a  &
+        Layer 1
+        .  .  .
+         .  .  b
+        .  b  .
+        "#,
+        )
+    }
+
+    #[test]
+    fn test_pillbug_cannot_push_through_blocked_gap() {
+        assert_pillbug_pushes(
+            r#"
+        Layer 0
+        .  .  a
+         a  P  *
+        .  *  &
+        Layer 1
+        .  .  b
+         b  .  .
+        .  .  .
+        "#,
+        )
+    }
+
+    #[test]
+    #[allow(clippy::match_like_matches_macro)]
+    fn test_pillbug_cannot_move_piece_that_just_moved() {
+        let hex_map = parse_hex_map_string(
+            r#"
+        .  .  .
+         q  Q  .
+        .  .  P
+        "#,
+        )
+        .unwrap();
+        let hive = Hive::from_hex_map(&hex_map).unwrap();
+        let mut game = Game::from_hive(hive, Color::Black);
+
+        // Do this move
+        // .  .  .
+        //  _  Q  .
+        // .  q  P
+        game = game.with_turn_applied(Move {
+            from: Hex { q: 0, r: 1, h: 0 },
+            to: Hex { q: 0, r: 2, h: 0 },
+            freezes_piece: false,
+        });
+
+        // Find all the moves that move the black queen (at q: 0, r: 2)
+        let moves = game
+            .pillbug_moves(&Hex { q: 1, r: 2, h: 0 })
+            .filter(|turn| match turn {
+                Move {
+                    from: Hex { q: 0, r: 2, h: 0 },
+                    ..
+                } => true,
+                _ => false,
+            });
+
+        // There shouldn't be any
+        assert_eq!(moves.count(), 0);
+    }
+
+    #[test]
+    fn test_cannot_move_piece_just_moved_by_pillbug() {
+        let hex_map = parse_hex_map_string(
+            r#"
+        .  .  .
+         .  P  .
+        .  q  Q
+        "#,
+        )
+        .unwrap();
+
+        let hive = Hive::from_hex_map(&hex_map).unwrap();
+        let mut game = Game::from_hive(hive, Color::White);
+
+        // Move black queen from bottom left of pillbug to top right:
+        // .  .  q
+        //  .  P  .
+        // .  _  Q
+        game = game.with_turn_applied(Move {
+            from: Hex { q: 0, r: 2, h: 0 },
+            to: Hex { q: 2, r: 0, h: 0 },
+            freezes_piece: true,
+        });
+
+        // Black should not be able to move anything
+        assert_eq!(game.movement_turns().count(), 0);
+    }
+
+    #[test]
+    fn test_pillbug_can_use_ability_when_frozen() {
+        let hex_map = parse_hex_map_string(
+            r#"
+            .  Q  .
+             p  q  .
+            .  P  .
+            "#,
+        )
+        .unwrap();
+
+        let hive = Hive::from_hex_map(&hex_map).unwrap();
+        let mut game = Game::from_hive(hive, Color::White);
+
+        // Use white pillbug to move black pillbug, freezing it
+        // .  Q  .
+        //  _  q  .
+        // .  P  p
+        game = game.with_turn_applied(Move {
+            from: Hex { q: 0, r: 1, h: 0 },
+            to: Hex { q: 1, r: 2, h: 0 },
+            freezes_piece: true,
+        });
+
+        // Use black pillbug to move white pillbug, even though the black pillbug is frozen
+        // .  Q  .
+        //  .  q  P
+        // .  _  p
+        assert!(game.turn_is_valid(Move {
+            from: Hex { q: 0, r: 2, h: 0 },
+            to: Hex { q: 2, r: 1, h: 0 },
+            freezes_piece: true
+        }))
+    }
+
+    #[test]
+    fn test_mosquito_can_use_pillbug_ability_even_if_pillbug_is_frozen() {
+        let hex_map = parse_hex_map_string(
+            r#"
+        .  P  .
+         p  q  Q
+        .  .  M
+        "#,
+        )
+        .unwrap();
+
+        let hive = Hive::from_hex_map(&hex_map).unwrap();
+        let mut game = Game::from_hive(hive, Color::Black);
+
+        // Use black pillbug to move white pillbug to the hex next to the white mosquito
+        // .  _  .
+        //  p  q  Q
+        // .  P  M
+        game = game.with_turn_applied(Move {
+            from: Hex { q: 1, r: 0, h: 0 },
+            to: Hex { q: 0, r: 2, h: 0 },
+            freezes_piece: true,
+        });
+
+        // Make sure we can move the white pillbug to the other side of the white mosquito by having
+        // the white mosquito copy the white pillbug's ability
+        // .  .  .  .
+        //  p  q  _  .
+        // .  P  M  Q
+        assert!(game.turn_is_valid(Move {
+            from: Hex { q: 2, r: 1, h: 0 },
+            to: Hex { q: 2, r: 2, h: 0 },
+            freezes_piece: true,
+        }));
+    }
+
+    #[test]
+    fn ability_turns_include_mosquito_pushes() {
+        let game = Game::from_map_str(
+            r#"
+        .  .  .
+         p  q  Q
+        .  P  M
+        "#,
+        )
+        .unwrap();
+        let pushes = game.ability_turns();
+        let expected: Vec<Turn> = game
+            .turns()
+            .filter(|turn| {
+                matches!(
+                    turn,
+                    Move {
+                        freezes_piece: true,
+                        ..
+                    }
+                )
+            })
+            .collect();
+        assert_eq!(pushes, expected);
+
+        // The white mosquito copies the pillbug next to it to push the white queen
+        assert!(pushes.contains(&Move {
+            from: Hex { q: 2, r: 1, h: 0 },
+            to: Hex { q: 2, r: 2, h: 0 },
+            freezes_piece: true,
+        }));
+        assert!(Game::default().ability_turns().is_empty());
     }
 
-    fn slide_would_separate_self_from_hive(&self, from: &Hex, to: &Hex, ignore_hex: &Hex) -> bool {
-        !self
-            .hive
-            .occupied_neighbors_at_same_level(from)
-            .any(|neighbor| neighbor != *ignore_hex && is_adjacent(&neighbor, to))
+    fn slide_test_game(from: &Hex, filled: &[Hex]) -> Game {
+        let tile = Tile {
+            bug: Bug::Ant,
+            color: Color::White,
+        };
+        let mut map = TileMap::default();
+        for h in 0..=from.h {
+            map.insert(Hex { h, ..*from }, tile);
+        }
+        for hex in filled {
+            for h in 0..=hex.h {
+                map.insert(Hex { h, ..*hex }, tile);
+            }
+        }
+        Game::from_hive_with_reserves(Hive { map }, Color::White, vec![], vec![])
     }
 
-    fn slide_is_allowed(&self, from: &Hex, to: &Hex) -> bool {
-        assert_eq!(from.h, to.h, "Slides must stay at the same height");
-
-        // To test if a slide is allowed, we need to check if the two adjacent tiles to the slide
-        // are blocking the slide. For example in this board:
-        // .  .  1
-        //  .  Q  d
-        // .  .  2
-        // To check if Q can move to position d, we need to check spaces 1 and 2. If both are
-        // filled, Q cannot move there.
-        let mov = to - from;
-        let counter_clockwise_neighbor = from
-            + &Hex {
-                q: -mov.s(),
-                r: -mov.q,
-                h: 0,
-            };
-        let clockwise_neighbor = from
-            + &Hex {
-                q: -mov.r,
-                r: -mov.s(),
-                h: 0,
-            };
-
-        !self.hive.is_occupied(&clockwise_neighbor)
-            || !self.hive.is_occupied(&counter_clockwise_neighbor)
+    fn slide_flanks(from: &Hex, direction_index: usize) -> (Hex, Hex, Hex) {
+        let directions: Vec<Direction> = Direction::iter().collect();
+        let to = neighbor(from, &directions[direction_index]);
+        let counter_clockwise = neighbor(from, &directions[(direction_index + 5) % 6]);
+        let clockwise = neighbor(from, &directions[(direction_index + 1) % 6]);
+        (to, counter_clockwise, clockwise)
     }
 
-    fn allowed_slides(
-        &self,
-        hex: &Hex,
-        ignore_hex: Option<&Hex>,
-    ) -> impl Iterator<Item = Hex> + use<> {
-        let neighbors: Vec<Hex> = self.hive.neighbors_at_same_level(hex).collect();
+    #[test]
+    fn test_can_slide_in_every_direction() {
+        for h in 0..=1 {
+            let from = Hex { q: 0, r: 0, h };
+            for i in 0..6 {
+                let (to, counter_clockwise, clockwise) = slide_flanks(&from, i);
+
+                let game = slide_test_game(&from, &[]);
+                assert!(
+                    game.can_slide(&from, &to),
+                    "no flanks, direction {i}, height {h}"
+                );
 
-        let mut empty_seen = 0;
-        let mut allowed_slides: Vec<Hex> = vec![];
-        let mut previous_added = false;
+                let game = slide_test_game(&from, &[counter_clockwise]);
+                assert!(
+                    game.can_slide(&from, &to),
+                    "one flank, direction {i}, height {h}"
+                );
 
-        for (i, hex) in neighbors.iter().enumerate() {
-            if self.hive.is_occupied(hex) && Some(hex) != ignore_hex {
-                empty_seen = 0;
-            } else {
-                if empty_seen > 0 {
-                    allowed_slides.push(*hex);
-                    if !previous_added {
-                        allowed_slides.push(neighbors[i - 1]);
-                    }
-                    previous_added = true;
-                } else {
-                    previous_added = false;
-                }
-                empty_seen += 1;
-            }
-        }
+                let game = slide_test_game(&from, &[clockwise]);
+                assert!(
+                    game.can_slide(&from, &to),
+                    "one flank, direction {i}, height {h}"
+                );
 
-        let first = &neighbors[0];
-        let second = &neighbors[1];
-        let last = &neighbors[5];
-        if !self.hive.is_occupied(first) && !self.hive.is_occupied(&last) {
-            if !previous_added {
-                allowed_slides.push(*last);
-            }
-            if self.hive.is_occupied(second) {
-                allowed_slides.push(*first);
+                let game = slide_test_game(&from, &[counter_clockwise, clockwise]);
+                assert!(
+                    !game.can_slide(&from, &to),
+                    "both flanks, direction {i}, height {h}"
+                );
             }
         }
-
-        allowed_slides.into_iter()
     }
 
-    fn is_adjacent_to_color(&self, hex: &Hex, color: &Color) -> bool {
-        self.hive
-            .topmost_occupied_neighbors(hex)
-            .any(|adjacent_hex| {
-                self.hive
-                    .map
-                    .get(&adjacent_hex)
-                    .is_some_and(|tile| tile.color == *color)
-            })
+    #[test]
+    fn test_can_slide_over_gate_that_is_only_stacked_on_one_side() {
+        let from = Hex { q: 0, r: 0, h: 1 };
+        for i in 0..6 {
+            let (to, counter_clockwise, clockwise) = slide_flanks(&from, i);
+
+            // Both flanks are filled on the ground, but only one reaches the sliding height
+            let game = slide_test_game(&from, &[counter_clockwise, Hex { h: 0, ..clockwise }]);
+            assert!(game.can_slide(&from, &to), "direction {i}");
+
+            let ground_from = from.base_level();
+            let ground_to = to.base_level();
+            assert!(!game.can_slide(&ground_from, &ground_to), "direction {i}");
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::engine::parse::{hex_map_to_string, parse_hex_map_string};
-    use Turn::Move;
-    use Turn::Placement;
+    #[test]
+    fn test_can_slide_rejects_non_slides() {
+        let game = slide_test_game(&Hex::default(), &[]);
+        assert!(!game.can_slide(&Hex::default(), &Hex { q: 2, r: 0, h: 0 }));
+        assert!(!game.can_slide(&Hex::default(), &Hex { q: 1, r: 0, h: 1 }));
+    }
 
-    fn turns_to_string(hex_map: &FxHashMap<Hex, String>, turns: Vec<Turn>) -> String {
-        let mut turns_map = hex_map.clone();
-        for turn in turns {
-            match turn {
-                Placement { hex, tile: _ } => {
-                    turns_map.insert(hex, "*".to_owned());
-                }
-                Move { to, .. } => {
-                    turns_map.insert(to, "*".to_owned());
-                }
-                Skip => {}
+    #[test]
+    fn test_running_out_of_time_loses() {
+        let mut game = Game {
+            white_clock: Some(Duration::from_secs(10)),
+            black_clock: Some(Duration::from_secs(10)),
+            ..Default::default()
+        };
+        let turn = game.turns().next().unwrap();
+        game = game.with_timed_turn_applied(turn, Duration::from_secs(4));
+        assert_eq!(game.white_clock, Some(Duration::from_secs(6)));
+        assert_eq!(game.black_clock, Some(Duration::from_secs(10)));
+        assert!(matches!(game.game_result(), GameResult::None));
+
+        let turn = game.turns().next().unwrap();
+        game = game.with_timed_turn_applied(turn, Duration::from_secs(11));
+        assert_eq!(game.black_clock, Some(Duration::ZERO));
+        assert!(matches!(
+            game.game_result(),
+            GameResult::Winner {
+                color: Color::White
             }
-        }
-        hex_map_to_string(&turns_map)
+        ));
     }
 
-    fn assert_placements(placements: &str) {
-        let placements_map = parse_hex_map_string(placements).unwrap();
-        let mut expected_placements: Vec<Turn> = placements_map
-            .iter()
-            .filter(|(_, token)| *token == "*")
-            .map(|(hex, _)| Placement {
-                hex: *hex,
-                tile: Tile {
-                    bug: Bug::Queen,
-                    color: Color::White,
-                },
-            })
-            .collect();
-
-        let hex_map: FxHashMap<Hex, String> = placements_map
-            .into_iter()
-            .filter(|(_, token)| *token != "*")
-            .collect();
-        let hive = Hive::from_hex_map(&hex_map).unwrap();
-        let game = Game::from_hive(hive, Color::White);
+    #[test]
+    fn test_untimed_games_ignore_elapsed_time() {
+        let game = Game::default();
+        let turn = game.turns().next().unwrap();
+        let game = game.with_timed_turn_applied(turn, Duration::from_secs(1000));
+        assert_eq!(game.white_clock, None);
+        assert!(matches!(game.game_result(), GameResult::None));
+    }
 
-        let mut actual_placements: Vec<Turn> = game
-            .turns()
-            .into_iter()
-            .filter(|turn| matches!(turn, Placement { .. }))
-            .collect();
+    #[test]
+    fn test_for_each_turn_matches_turns() {
+        let game = Game::from_map_str(
+            r#"
+        Layer 0
+        .  .  .  A  .  .
+         .  s  b  a  .  .
+        .  G  Q  B  .  .
+         .  m  q  g  S  .
+        .  .  L  P  .  .
+         .  .  M  p  .  .
+        Layer 1
+        .  .  .  .  .  .
+         .  .  .  .  .  .
+        .  .  .  b  .  .
+         .  .  .  .  .  .
+        .  .  .  .  .  .
+        "#,
+        )
+        .unwrap();
 
-        expected_placements.sort();
-        actual_placements.sort();
+        let mut expected: Vec<Turn> = game.turns().collect();
+        let mut actual = vec![];
+        game.for_each_turn(|turn| actual.push(turn));
+        expected.sort();
+        actual.sort();
+        assert_eq!(expected, actual);
+    }
 
-        if expected_placements != actual_placements {
-            let actual_placements_map = turns_to_string(&hex_map, actual_placements);
-            let expected_placements_map = turns_to_string(&hex_map, expected_placements);
+    #[test]
+    fn test_placements_are_not_duplicated() {
+        let game = Game::from_map_str(
+            r#"
+            .  A  a  .
+             A  .  .  a
+            "#,
+        )
+        .unwrap();
 
-            pretty_assertions::assert_eq!(expected_placements_map, actual_placements_map);
-        }
+        let turns: Vec<Turn> = game.turns().collect();
+        assert_eq!(turns.len(), turns.iter().unique().count());
     }
 
-    fn assert_pillbug_pushes(moves: &str) {
-        let mut moves_map = parse_hex_map_string(moves).unwrap();
-        let push_target_hex = moves_map
-            .iter()
-            .find(|(_, char)| *char == "&")
-            .map(|(hex, _)| *hex)
-            .unwrap();
-
-        moves_map.insert(push_target_hex, "a".into());
+    #[test]
+    fn test_piece_numbers_follow_moves() {
+        let game = Game::from_uhp(
+            "Base",
+            &[
+                "wA1", "bA1 wA1-", "wA2 -wA1", "bQ bA1-", "wQ \\wA1", "bB1 bQ-",
+            ],
+        )
+        .unwrap();
+        let white_ant = Tile {
+            bug: Bug::Ant,
+            color: Color::White,
+        };
+        assert_eq!(
+            game.find_piece(white_ant, 1),
+            Some(Hex { q: 0, r: 0, h: 0 })
+        );
+        assert_eq!(
+            game.find_piece(white_ant, 2),
+            Some(Hex { q: -1, r: 0, h: 0 })
+        );
 
-        _assert_moves(&moves_map, push_target_hex, true)
+        // Scan order would number the second ant first, but placement order wins
+        let turn = Move {
+            from: Hex { q: -1, r: 0, h: 0 },
+            to: Hex { q: -1, r: 1, h: 0 },
+            freezes_piece: false,
+        };
+        assert!(game.turn_is_valid(turn));
+        let game = game.with_turn_applied(turn);
+        assert_eq!(
+            game.find_piece(white_ant, 1),
+            Some(Hex { q: 0, r: 0, h: 0 })
+        );
+        assert_eq!(
+            game.find_piece(white_ant, 2),
+            Some(Hex { q: -1, r: 1, h: 0 })
+        );
     }
 
-    fn assert_moves(moves: &str) {
-        let moves_map = parse_hex_map_string(moves).unwrap();
-        let (from, _) = moves_map
-            .iter()
-            .find(|(_, token)| token.chars().next().unwrap().is_uppercase())
-            .unwrap();
+    #[test]
+    fn test_queen_escape_squares() {
+        let game = Game::from_map_str(
+            r#"
+        Layer 0
+            .  a  .
+             a  Q  .
+            .  q  a
+        Layer 1
+            .  .  .
+             .  .  .
+            .  b  .
+        "#,
+        )
+        .unwrap();
 
-        _assert_moves(&moves_map, *from, false)
+        assert_eq!(game.queen_escape_squares(Color::White), Some(2));
+        assert_eq!(game.queen_escape_squares(Color::Black), Some(0));
+        assert!(game.queen_is_covered(Color::Black));
+        assert!(!game.queen_is_covered(Color::White));
     }
 
-    fn _assert_moves(moves_map: &FxHashMap<Hex, String>, for_hex: Hex, freezes_piece: bool) {
-        let mut expected_moves: Vec<Turn> = moves_map
-            .iter()
-            .filter(|(_, token)| *token == "*")
-            .map(|(hex, _)| Move {
-                from: for_hex,
-                to: *hex,
-                freezes_piece,
-            })
-            .collect();
+    #[test]
+    fn measures_the_distance_between_queens() {
+        assert_eq!(Game::default().queen_distance(), None);
+        let game = Game::from_uhp("Base", &["wS1", "bG1 wS1-", "wQ -wS1"]).unwrap();
+        assert_eq!(game.queen_distance(), None);
+        let game = Game::from_uhp("Base", &["wS1", "bG1 wS1-", "wQ -wS1", "bQ bG1-"]).unwrap();
+        assert_eq!(game.queen_distance(), Some(3));
+
+        // A beetle on top of a queen doesn't change how far away she is
+        let game = Game::from_map_str(
+            r#"
+        Layer 0
+            Q  a  q
+        Layer 1
+            .  .  B
+        "#,
+        )
+        .unwrap();
+        assert_eq!(game.queen_distance(), Some(2));
+    }
 
-        let hex_map: FxHashMap<Hex, String> = moves_map
-            .into_iter()
-            .filter(|(_, token)| *token != "*")
-            .map(|(key, value)| (key.clone(), value.clone()))
-            .collect();
-        let hive = Hive::from_hex_map(&hex_map).unwrap();
-        let game = Game::from_hive_with_reserves(hive, Color::White, vec![], vec![]);
+    #[test]
+    fn splits_queen_escapes_into_open_and_blocked() {
+        let game = Game::from_map_str(
+            r#"
+        Layer 0
+            .  a  .
+             a  Q  .
+            .  q  a
+        Layer 1
+            .  .  .
+             .  .  .
+            .  b  .
+        "#,
+        )
+        .unwrap();
 
-        let mut actual_moves: Vec<Turn> = game.turns().collect();
+        let white = game.queen_escapes(Color::White).unwrap();
+        assert_eq!(white.open.len(), 2);
+        assert_eq!(
+            white.open.len() + white.blocked.len(),
+            game.hive
+                .unoccupied_neighbors(&Hex { q: 1, r: 1, h: 0 })
+                .count()
+        );
+        // A covered queen can't go anywhere, so every empty neighbor is blocked
+        let black = game.queen_escapes(Color::Black).unwrap();
+        assert!(black.open.is_empty());
+        assert!(!black.blocked.is_empty());
+        assert_eq!(game.active_player, Color::White);
+        assert_eq!(game.enemy_queen_escape_count(), black.blocked.len());
+        assert_eq!(Game::default().queen_escapes(Color::White), None);
+    }
 
-        actual_moves.retain(|turn| match turn {
-            Move { from, .. } => *from == for_hex,
-            _ => false,
-        });
+    #[test]
+    fn test_queen_escape_squares_before_queen_is_placed() {
+        assert_eq!(Game::default().queen_escape_squares(Color::White), None);
+    }
 
-        expected_moves.sort();
-        actual_moves.sort();
+    #[test]
+    fn test_turns_are_placements_and_movements() {
+        let game = Game::from_map_str(
+            r#"
+            .  A  .
+             .  Q  .
+            .  q  a
+            "#,
+        )
+        .unwrap();
 
-        if expected_moves != actual_moves {
-            let expected_moves_map = turns_to_string(&hex_map, expected_moves);
-            let actual_moves_map = turns_to_string(&hex_map, actual_moves);
-            pretty_assertions::assert_str_eq!(expected_moves_map, actual_moves_map)
-        }
+        let placements: Vec<Turn> = game.placement_turns().collect();
+        let movements: Vec<Turn> = game.movement_turns().collect();
+        assert!(
+            placements
+                .iter()
+                .all(|turn| matches!(turn, Placement { .. }))
+        );
+        assert!(movements.iter().all(|turn| matches!(turn, Move { .. })));
+        assert!(!placements.is_empty() && !movements.is_empty());
+
+        let mut expected: Vec<Turn> = game.turns().collect();
+        let mut actual: Vec<Turn> = placements.into_iter().chain(movements).collect();
+        expected.sort();
+        actual.sort();
+        assert_eq!(expected, actual);
     }
 
     #[test]
-    fn test_placement() {
-        assert_placements(
+    fn queen_surrounded_partly_by_her_own_pieces_still_loses() {
+        let game = Game::from_map_str(
             r#"
-            .  a  .
-             .  B  *
-            .  *  *
-        "#,
+            .  A  a
+             G  Q  g
+            .  S  s
+            "#,
         )
-    }
+        .unwrap();
+        let black_wins = GameResult::Winner {
+            color: Color::Black,
+        };
+        assert_eq!(game.game_result(), black_wins);
 
-    #[test]
-    fn test_placement_with_multiple_layers() {
-        assert_placements(
+        // The beetle on top doesn't count as one of the six, but doesn't save the queen either
+        let buried = Game::from_map_str(
             r#"
-        Layer 0
-            .  a  .
-             .  B  .
+            Layer 0
+            .  A  a
+             G  Q  g
+            .  S  s
+            Layer 1
             .  .  .
-        Layer 1
-            .  a  .
              .  b  .
+            "#,
+        )
+        .unwrap();
+        assert_eq!(buried.game_result(), black_wins);
+        let buried_with_a_gap = Game::from_map_str(
+            r#"
+            Layer 0
+            .  A  a
+             G  Q  g
+            .  S  .
+            Layer 1
             .  .  .
-        "#,
+             .  b  .
+            "#,
         )
+        .unwrap();
+        assert_eq!(buried_with_a_gap.game_result(), GameResult::None);
     }
 
     #[test]
-    fn test_placement_not_allowed_above_layer_0() {
-        assert_placements(
+    fn test_surround_race() {
+        // White's ant can reach every empty square around the black queen, but the black queen can
+        // only slide into two of the squares around the white queen
+        let game = Game::from_map_str(
             r#"
-        Layer 0
-            .  a  .
-             .  b  .
-            .  .  a
-        Layer 1
-            .  .  .
-             .  B  .
-            .  .  .
-        "#,
+            A  Q  q
+            "#,
         )
+        .unwrap();
+
+        assert_eq!(game.surround_race(), 3);
+        assert_eq!(Game::from_hive(game.hive, Color::Black).surround_race(), -3);
     }
 
     #[test]
-    fn test_placement_uses_top_layer_for_hex_color() {
-        assert_placements(
+    fn test_board_hash_ignores_side_to_move() {
+        let white_to_move = Game::from_map_str(
             r#"
-        Layer 0
-            .  a  .
-             .  b  *
-            .  *  *
-        Layer 1
-            .  .  .
-             .  B  .
-            .  .  .
-        "#,
+            A  Q  q
+            "#,
         )
+        .unwrap();
+        let black_to_move = Game::from_hive(white_to_move.hive.clone(), Color::Black);
+
+        assert_ne!(
+            white_to_move.zobrist_hash.value(),
+            black_to_move.zobrist_hash.value()
+        );
+        assert_eq!(white_to_move.board_hash(), black_to_move.board_hash());
+        assert_eq!(
+            white_to_move
+                .with_turn_applied(Skip)
+                .with_turn_applied(Skip)
+                .board_hash(),
+            black_to_move.with_turn_applied(Skip).board_hash()
+        );
     }
 
     #[test]
-    fn test_must_place_queen_by_turn_four() {
-        let hex_map = parse_hex_map_string(
+    fn test_reply_count_after() {
+        let game = Game::from_map_str(
             r#"
             .  A  .
-             A  A  .
-            .  .  .
-        "#,
+             .  Q  .
+            .  q  a
+            "#,
         )
         .unwrap();
 
-        let hive = Hive::from_hex_map(&hex_map).unwrap();
-        let game = Game::from_hive(hive, Color::White);
+        for turn in game.turns() {
+            let after = game.with_turn_applied(turn);
+            assert_eq!(game.reply_count_after(turn), after.turns().count());
+        }
+    }
 
-        let moves = game.turns();
-        let (queen_placements, non_queen_placements): (Vec<Turn>, Vec<Turn>) =
-            moves.partition(|mv| {
+    #[test]
+    fn test_reply_count_after_winning_turn() {
+        let game = Game::from_map_str(
+            r#"
+            .  a  b  .
+             a  q  b  .
+            .  a  .  A
+             .  .  Q  .
+            "#,
+        )
+        .unwrap();
+        let winning_turn = game
+            .turns()
+            .find(|turn| {
                 matches!(
-                    mv,
-                    Placement {
-                        tile: Tile {
-                            bug: Bug::Queen,
-                            ..
-                        },
-                        ..
-                    }
+                    game.with_turn_applied(*turn).game_result(),
+                    GameResult::Winner { .. }
                 )
-            });
+            })
+            .unwrap();
 
-        assert!(queen_placements.len() > 0);
-        assert_eq!(non_queen_placements.len(), 0);
+        assert_eq!(game.reply_count_after(winning_turn), 0);
     }
 
     #[test]
-    fn test_queen_cannot_move_out_from_under_beetle() {
-        assert_moves(
+    fn test_destinations_ignoring_hive() {
+        // The white queen is pinned, moving it would split the black pieces apart
+        let game = Game::from_map_str(
             r#"
-        Layer 0
-            .  a  .
-             .  Q  .
-            .  .  .
-        Layer 1
-            .  .  .
-             .  b  .
-            .  .  .
-        "#,
+            .  a  .  .
+             .  Q  q  .
+            .  .  .  .
+            "#,
         )
+        .unwrap();
+        let queen = game
+            .find_piece(
+                Tile {
+                    bug: Bug::Queen,
+                    color: Color::White,
+                },
+                1,
+            )
+            .unwrap();
+
+        assert_eq!(game.valid_destinations_for_piece(&queen).count(), 0);
+        let ghost_destinations = game.destinations_ignoring_hive(&queen);
+        assert_eq!(ghost_destinations.len(), 3);
+        assert!(!game.ignore_hive_rule);
+        assert!(game.ignoring_hive_rule().ignore_hive_rule);
     }
 
     #[test]
-    fn test_queen_move_slide() {
-        assert_moves(
+    fn test_checked_turns_match_game_result() {
+        let game = Game::from_map_str(
             r#"
-            .  a  *
-             *  Q  .
-            .  .  .
-        "#,
-        );
+            .  a  b  .
+             a  q  b  .
+            .  a  .  A
+             .  .  Q  .
+            "#,
+        )
+        .unwrap();
+
+        for turn in game.turns() {
+            let (after, result) = game.with_turn_applied_checked(turn);
+            assert_eq!(result, after.game_result(), "{turn:?}");
+        }
     }
 
     #[test]
-    fn test_queen_move_does_not_break_hive() {
-        assert_moves(
-            r#"
-            .  a  .
-             .  Q  a
-            .  .  .
-        "#,
-        );
+    fn test_checked_turn_surrounding_both_queens_is_a_draw() {
+        let tile = |bug, color| Tile { bug, color };
+        let hex = |q, r| Hex { q, r, h: 0 };
+        // The queens are next to each other and share one empty neighbor, the white ant can fill it
+        let map = TileMap::from_iter([
+            (hex(0, 0), tile(Bug::Queen, Color::White)),
+            (hex(1, 0), tile(Bug::Queen, Color::Black)),
+            (hex(0, -1), tile(Bug::Beetle, Color::White)),
+            (hex(1, -1), tile(Bug::Beetle, Color::White)),
+            (hex(-1, 1), tile(Bug::Grasshopper, Color::White)),
+            (hex(-1, 0), tile(Bug::Grasshopper, Color::White)),
+            (hex(2, -1), tile(Bug::Grasshopper, Color::White)),
+            (hex(2, 0), tile(Bug::Beetle, Color::Black)),
+            (hex(1, 1), tile(Bug::Beetle, Color::Black)),
+            (hex(0, 2), tile(Bug::Ant, Color::White)),
+        ]);
+        let game = Game::from_hive(Hive { map }, Color::White);
+        let turn = Move {
+            from: hex(0, 2),
+            to: hex(0, 1),
+            freezes_piece: false,
+        };
+
+        assert!(game.turn_is_valid(turn));
+        let (after, result) = game.with_turn_applied_checked(turn);
+        assert_eq!(result, GameResult::Draw);
+        assert_eq!(after.game_result(), GameResult::Draw);
     }
 
     #[test]
-    fn test_queen_move_escapes_semicircle() {
-        assert_moves(
-            r#"
-            .  a  *
-             b  Q  *
-            .  b  a
-        "#,
-        );
+    fn test_must_pass_when_queen_has_nowhere_to_go() {
+        // Three white pieces completely ringed by black pieces on white's fourth turn
+        let white_hexes = [
+            Hex { q: 0, r: 0, h: 0 },
+            Hex { q: 1, r: 0, h: 0 },
+            Hex { q: 2, r: 0, h: 0 },
+        ];
+        let ring = white_hexes
+            .iter()
+            .flat_map(neighbors)
+            .filter(|hex| !white_hexes.contains(hex))
+            .unique();
+        let black_bugs = [
+            Bug::Queen,
+            Bug::Ant,
+            Bug::Ant,
+            Bug::Ant,
+            Bug::Beetle,
+            Bug::Beetle,
+            Bug::Grasshopper,
+            Bug::Grasshopper,
+            Bug::Grasshopper,
+            Bug::Spider,
+        ];
+        let map = white_hexes
+            .iter()
+            .zip([Bug::Ant, Bug::Ant, Bug::Grasshopper])
+            .map(|(hex, bug)| {
+                (
+                    *hex,
+                    Tile {
+                        bug,
+                        color: Color::White,
+                    },
+                )
+            })
+            .chain(ring.zip(black_bugs).map(|(hex, bug)| {
+                (
+                    hex,
+                    Tile {
+                        bug,
+                        color: Color::Black,
+                    },
+                )
+            }))
+            .collect();
+        let game = Game::from_hive(Hive { map }, Color::White);
+
+        assert!(game.must_pass_due_to_queen_rule());
+        assert_eq!(game.turns().collect::<Vec<_>>(), vec![Skip]);
+        let mut turns = vec![];
+        game.for_each_turn(|turn| turns.push(turn));
+        assert_eq!(turns, vec![Skip]);
+
+        assert!(!Game::default().must_pass_due_to_queen_rule());
     }
 
     #[test]
-    fn test_queen_move_does_not_temporarily_break_hive() {
-        assert_moves(
-            r#"
-            .  b  b  .
-             q  *  a  .
-            .  .  Q  *
-        "#,
-        );
+    fn test_last_move_hexes() {
+        let game = Game::default();
+        assert_eq!(game.last_move_hexes(), None);
+
+        let origin = Hex { q: 0, r: 0, h: 0 };
+        let right = Hex { q: 1, r: 0, h: 0 };
+        let placement = Placement {
+            hex: origin,
+            tile: Tile {
+                bug: Bug::Pillbug,
+                color: Color::White,
+            },
+        };
+        let game = game.with_turn_applied(placement);
+        assert_eq!(game.last_move_hexes(), Some((None, origin)));
+
+        let push = Move {
+            from: origin,
+            to: right,
+            freezes_piece: true,
+        };
+        let game = game.with_turn_applied(push);
+        assert_eq!(game.last_move_hexes(), Some((Some(origin), right)));
+
+        assert_eq!(game.with_turn_applied(Skip).last_move_hexes(), None);
     }
 
     #[test]
-    fn test_queen_move_can_escape_semicircle_in_top_left() {
-        assert_moves(
-            r#"
-            .  *  a
-             *  Q  a
-            .  b  b
-        "#,
+    fn test_spider_path_count() {
+        // Walking around the ant either way gets the spider to the far side of it
+        let hive: Hive = "S  a".parse().unwrap();
+        let game = Game::from_hive_with_reserves(hive, Color::White, vec![], vec![]);
+        let spider = game.hive.pieces_of(Bug::Spider, Color::White)[0];
+
+        let counts = game.spider_path_count(&spider);
+        let far_side = Hex {
+            q: spider.q + 2,
+            ..spider
+        };
+        assert_eq!(counts, FxHashMap::from_iter([(far_side, 2)]));
+        assert_eq!(
+            game.valid_destinations_for_piece(&spider)
+                .collect::<Vec<_>>(),
+            vec![far_side]
         );
     }
 
     #[test]
-    fn test_queen_move_can_escape_semicircle_in_top() {
-        assert_moves(
-            r#"
-            .  *  *
-             a  Q  a
-            .  b  b
-        "#,
-        );
+    fn test_all_openings_collapse_symmetric_lines() {
+        let openings = Game::all_openings(2, usize::MAX);
+
+        // Every non queen bug can go first, and every reply around it is the same up to rotation
+        let first_moves = openings.iter().filter(|(line, _)| line.len() == 1).count();
+        let replies = openings.iter().filter(|(line, _)| line.len() == 2).count();
+        assert_eq!(first_moves, 7);
+        assert_eq!(replies, 7 * 7);
+        assert!(openings.iter().all(|(line, game)| {
+            Game::from_turns(line.iter().copied())
+                .is_ok_and(|replayed| replayed.hive.map == game.hive.map)
+        }));
+
+        assert_eq!(Game::all_openings(2, 10).len(), 10);
     }
 
     #[test]
-    fn test_beetle_move_slide() {
-        assert_moves(
+    fn test_branching_factor() {
+        let game = Game::from_map_str(
             r#"
-        Layer 0
-            .  a  *
-             *  B  .
-            .  .  .
-        Layer 1
-            .  *  .
-             .  .  .
-            .  .  .
-        "#,
-        );
+            .  A  .
+             .  Q  .
+            .  q  a
+            "#,
+        )
+        .unwrap();
+        assert_eq!(game.branching_factor(), game.turns().count());
+        assert!(game.distinct_next_positions() <= game.branching_factor());
+        assert!(game.distinct_next_positions() > 0);
+
+        // Nothing to place and the queen is pinned, the only turn is a skip
+        let hive: Hive = "a  Q  a".parse().unwrap();
+        let stuck = Game::from_hive_with_reserves(hive, Color::White, vec![], vec![]);
+        assert_eq!(stuck.branching_factor(), 1);
+        assert_eq!(stuck.distinct_next_positions(), 1);
     }
 
     #[test]
-    fn test_beetle_can_slide_down() {
-        assert_moves(
-            r#"
-            Layer 0
-            .  *  a
-             a  q  *
-            .  *  *
-            Layer 1
-            .  .  *
-             *  B  .
-            .  .  .
-            "#,
+    fn test_from_parts() {
+        let hive: Hive = r#"
+            .  P  a
+             .  Q  q
+            "#
+        .parse()
+        .unwrap();
+        let ant = hive.pieces_of(Bug::Ant, Color::Black)[0];
+        let push = Move {
+            from: Hex {
+                q: ant.q + 1,
+                ..ant
+            },
+            to: ant,
+            freezes_piece: true,
+        };
+        let game = Game::from_parts(
+            hive.clone(),
+            Color::White,
+            vec![Bug::Ant],
+            vec![Bug::Beetle],
+            Some(ant),
+            Some(push),
+        )
+        .unwrap();
+        assert_eq!(game.immobilized_piece, Some(ant));
+        assert_eq!(game.last_turn, Some(push));
+        // The pushed ant can't be pushed again straight away
+        assert!(
+            game.moves_for_piece(&hive.pieces_of(Bug::Pillbug, Color::White)[0])
+                .all(|turn| !matches!(turn, Move { from, .. } if from == ant))
+        );
+
+        let too_many_queens = Game::from_parts(
+            hive.clone(),
+            Color::White,
+            vec![Bug::Queen],
+            vec![],
+            None,
+            None,
         );
+        assert!(matches!(
+            too_many_queens,
+            Err(PositionError::TooManyPieces { .. })
+        ));
+
+        let empty = Hex { q: 5, r: 5, h: 0 };
+        let missing_piece = Game::from_parts(hive, Color::White, vec![], vec![], Some(empty), None);
+        assert!(matches!(
+            missing_piece,
+            Err(PositionError::ImmobilizedPieceMissing { .. })
+        ));
     }
 
     #[test]
-    fn test_beetle_move_from_higher_layer() {
-        assert_moves(
-            r#"
-        Layer 0
-            .  a  *
-             *  a  *
-            .  *  *
-        Layer 1
-            .  *  .
-             .  B  .
-            .  .  .
-        "#,
-        );
+    fn test_validate() {
+        assert!(Game::default().validate().is_ok());
+        assert!(Game::from_map_str("A  q  Q").unwrap().validate().is_ok());
+        for seed in 0..5 {
+            assert!(random_game(seed, 30).validate().is_ok());
+        }
+        // Base game without any expansion pieces
+        assert!(Game::from_uhp("Base", &["wA1"]).unwrap().validate().is_ok());
+
+        assert!(matches!(
+            Game::from_map_str("A  .  q").unwrap().validate(),
+            Err(PositionError::Disconnected { components: 2 })
+        ));
+        assert!(matches!(
+            Game::from_map_str("Q  q  Q").unwrap().validate(),
+            Err(PositionError::TooManyPieces { .. })
+        ));
+        let hive: Hive = "A  q  Q".parse().unwrap();
+        assert!(matches!(
+            Game::from_hive_with_reserves(hive, Color::White, vec![], vec![]).validate(),
+            Err(PositionError::ReserveMismatch { .. })
+        ));
+
+        let mut game = Game::from_map_str("A  q  Q").unwrap();
+        game.immobilized_piece = Some(Hex { q: 5, r: 5, h: 0 });
+        assert!(matches!(
+            game.validate(),
+            Err(PositionError::ImmobilizedPieceMissing { .. })
+        ));
     }
 
     #[test]
-    fn test_beetle_move_does_not_break_hive() {
-        assert_moves(
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "assertion `left == right` failed")]
+    fn validate_catches_stale_queen_surrounds() {
+        let mut game = Game::from_map_str("A  q  Q").unwrap();
+        let ant = game.hive.pieces_of(Bug::Ant, Color::White)[0];
+        game.hive.map.remove(&ant);
+        game.white_reserve.push(Bug::Ant);
+        let _ = game.validate();
+    }
+
+    #[test]
+    fn preview_rejects_illegal_turns() {
+        let game = Game::from_map_str(
             r#"
-        Layer 0
-            .  a  .
-             .  B  a
-            .  b  .
-        "#,
+            .  A  Q  q  a
+            "#,
+        )
+        .unwrap();
+        let turn = game.turns().next().unwrap();
+        let preview = game.preview(turn).unwrap();
+        assert_eq!(preview.hive.map, game.with_turn_applied(turn).hive.map);
+        assert_eq!(preview.active_player, Color::Black);
+
+        let black_ant = game.hive.pieces_of(Bug::Ant, Color::Black)[0];
+        let enemy_move = Move {
+            from: black_ant,
+            to: Hex {
+                r: black_ant.r + 1,
+                ..black_ant
+            },
+            freezes_piece: false,
+        };
+        assert_eq!(
+            game.preview(enemy_move).err(),
+            Some(ApplyError::IllegalTurn { turn: enemy_move })
         );
+        assert_eq!(
+            game.preview(Skip).err(),
+            Some(ApplyError::IllegalTurn { turn: Skip })
+        );
+
+        let over = Game::from_map_str(
+            r#"
+            .  a  a  .
+             g  Q  g
+            .  a  g  .
+            "#,
+        )
+        .unwrap();
+        assert_eq!(over.preview(Skip).err(), Some(ApplyError::GameOver));
+    }
+
+    #[test]
+    fn each_turn_changes_one_tile() {
+        for seed in 0..3 {
+            let game = random_game(seed, 20);
+            for turn in game.turns() {
+                let diff = game.hive.diff(&game.with_turn_applied(turn).hive);
+                match turn {
+                    Placement { hex, tile } => {
+                        assert_eq!(diff.added, vec![(hex, tile)]);
+                        assert!(diff.removed.is_empty() && diff.moved.is_empty());
+                    }
+                    Move { from, to, .. } => {
+                        assert_eq!(diff.moved, vec![(game.hive.map[&from], from, to)]);
+                        assert!(diff.added.is_empty() && diff.removed.is_empty());
+                    }
+                    Skip => assert!(diff.is_empty()),
+                }
+            }
+        }
     }
 
     #[test]
-    fn test_beetle_move_can_slide_or_mount() {
-        assert_moves(
-            r#"
-        Layer 0
-            *  a  .
-             B  b  .
-            *  a  .
-        Layer 1
-            .  *  .
-           .  *  .
-            .  *  .
-        "#,
+    fn stuck_player_passes_or_loses() {
+        let hive: Hive = "a  Q  a".parse().unwrap();
+        let game = Game::from_hive_with_reserves(hive, Color::White, vec![], vec![]);
+        assert_eq!(game.turns().collect_vec(), vec![Skip]);
+        assert_eq!(game.game_result(), GameResult::None);
+
+        let variant = Game {
+            stuck_player_loses: true,
+            ..game
+        };
+        assert_eq!(variant.turns().count(), 0);
+        assert_eq!(variant.branching_factor(), 0);
+        assert_eq!(
+            variant.game_result(),
+            GameResult::Winner {
+                color: Color::Black
+            }
         );
+
+        // Black still has moves, so the variant doesn't change anything for them
+        let black_to_move = Game {
+            active_player: Color::Black,
+            ..variant
+        };
+        assert_eq!(black_to_move.game_result(), GameResult::None);
+        assert!(black_to_move.turns().all(|turn| turn != Skip));
     }
 
     #[test]
-    fn test_grasshopper_move_jumps() {
-        assert_moves(
-            r#"
-            .  *  .
-             .  a  .
-            *  a  G
-        "#,
+    fn games_compare_by_position() {
+        let game = random_game(3, 12);
+        assert_eq!(game, game.clone());
+
+        let mut shuffled = game.clone();
+        shuffled.white_reserve.reverse();
+        shuffled.piece_numbers.clear();
+        assert_eq!(game, shuffled);
+
+        assert_ne!(game, game.with_turn_applied(game.turns().next().unwrap()));
+        assert_ne!(
+            game,
+            Game {
+                last_turn: None,
+                ..game.clone()
+            }
         );
+        let moved_over = Game::from_map_str(". A  q  Q").unwrap();
+        assert_eq!(Game::from_map_str("A  q  Q").unwrap(), moved_over);
+        assert_ne!(Game::from_map_str("A  Q  q").unwrap(), moved_over);
+
+        let mut fewer_bugs = game.clone();
+        fewer_bugs.black_reserve.pop();
+        assert_ne!(game, fewer_bugs);
     }
 
     #[test]
-    fn test_ant_move_unlimited_slides() {
-        assert_moves(
-            r#"
-            .  A  *
-             *  q  *
-            .  *  *
-        "#,
+    fn test_pieces_in_reserve() {
+        let game = Game::from_map_str("A  q  Q").unwrap();
+        assert_eq!(
+            game.pieces_in_reserve(Color::White),
+            DEFAULT_RESERVE.len() - 2
+        );
+        assert_eq!(
+            game.pieces_in_reserve(Color::Black),
+            DEFAULT_RESERVE.len() - 1
         );
     }
 
     #[test]
-    fn test_valid_spider_moves() {
-        assert_moves(
-            r#"
-            .  S  .
-             .  b  .
-            .  .  *
-        "#,
+    fn replaces_one_reserve() {
+        let game = Game::from_map_str("A  q  Q").unwrap();
+        let ants_only = game.with_reserve(Color::White, vec![Bug::Ant]).unwrap();
+        assert_eq!(ants_only.pieces_in_reserve(Color::White), 1);
+        assert_eq!(ants_only.black_reserve, game.black_reserve);
+        assert_eq!(ants_only.zobrist_hash.value(), game.zobrist_hash.value());
+        assert!(
+            ants_only
+                .placement_turns()
+                .all(|turn| matches!(turn, Placement { tile, .. } if tile.bug == Bug::Ant))
         );
+
+        assert!(matches!(
+            game.with_reserve(Color::White, vec![Bug::Queen]),
+            Err(PositionError::TooManyPieces { .. })
+        ));
+        assert!(matches!(
+            game.with_reserve(Color::Black, vec![Bug::Ant; 4]),
+            Err(PositionError::TooManyPieces { .. })
+        ));
     }
 
     #[test]
-    fn test_spider_moves_finds_multiple_paths_to_same_location() {
-        assert_moves(
-            r#"
-            *  *  *  *
-             a  .  a  .
-            b  .  S  g
-             g  g  a  .
-        "#,
-        );
+    fn spider_can_slide_back_past_its_starting_hex() {
+        // The spider's second step slides between its own starting hex and the next hex clockwise,
+        // which wraps around the end of the neighbor list
+        let hive = HiveBuilder::new()
+            .place(0, 0, 0, Bug::Ladybug, Color::White)
+            .place(1, 0, 0, Bug::Beetle, Color::White)
+            .place(-2, 1, 0, Bug::Beetle, Color::Black)
+            .place(-1, 1, 0, Bug::Pillbug, Color::Black)
+            .place(-3, 2, 0, Bug::Mosquito, Color::Black)
+            .place(-2, 2, 0, Bug::Queen, Color::Black)
+            .place(0, -1, 0, Bug::Pillbug, Color::White)
+            .place(-1, -1, 0, Bug::Spider, Color::White)
+            .place(2, -1, 0, Bug::Queen, Color::White)
+            .build()
+            .unwrap();
+        let game = Game::from_hive_with_reserves(hive, Color::White, vec![], vec![]);
+
+        let spider = Hex { q: -1, r: -1, h: 0 };
+        let destinations: Vec<Hex> = game
+            .spider_moves(&spider)
+            .filter_map(|turn| match turn {
+                Turn::Move {
+                    to,
+                    freezes_piece: false,
+                    ..
+                } => Some(to),
+                _ => None,
+            })
+            .collect();
+        assert!(destinations.contains(&Hex { q: -3, r: 1, h: 0 }));
     }
 
     #[test]
-    fn test_grasshopper_does_not_break_hive() {
-        assert_moves(
-            r#"
-            .  a  .
-             .  G  .
-            .  .  a
-        "#,
-        );
+    fn renders_history_with_captions_and_highlights() {
+        let start = Game::from_uhp("Base", &[]).unwrap();
+        let mut game = start.clone();
+        let mut turns = vec![];
+        for move_string in ["wS1", "bG1 wS1-", "wQ -wS1", "bQ bG1-"] {
+            game = game.apply_move_string(move_string).unwrap();
+            turns.push(game.last_turn.unwrap());
+        }
+        let queen_move = game
+            .turns()
+            .find(|turn| matches!(turn, Move { .. }))
+            .unwrap();
+        turns.push(queen_move);
+
+        let boards = start.render_history(&turns);
+        assert_eq!(boards.len(), 5);
+        assert!(boards[0].starts_with("1. wS1\n"));
+        assert!(boards[3].starts_with("4. bQ bG1-\n"));
+        assert!(boards[3].contains("[q]"));
+        // Placements highlight where the piece went, moves where it came from too
+        assert_eq!(boards[3].matches('[').count(), 1);
+        assert_eq!(boards[4].matches('[').count(), 2);
+        assert!(boards[4].contains("[.]"));
+
+        let transcript = start.history_transcript(&turns);
+        assert_eq!(transcript.matches("\n---").count(), 4);
     }
 
     #[test]
-    fn test_spider_cannot_make_illegal_slides() {
-        assert_moves(
-            r#"
-            q  .
-             a  .
-            .  q
-             S  .
-            .  q
-             a  .
-        "#,
-        );
+    fn turns_round_trip_through_u32() {
+        let turns = [
+            Skip,
+            Placement {
+                hex: Hex {
+                    q: -32,
+                    r: 31,
+                    h: 0,
+                },
+                tile: Tile {
+                    bug: Bug::Pillbug,
+                    color: Color::Black,
+                },
+            },
+            Placement {
+                hex: Hex { q: 0, r: 0, h: 0 },
+                tile: Tile {
+                    bug: Bug::Ant,
+                    color: Color::White,
+                },
+            },
+            Move {
+                from: Hex { q: 3, r: -2, h: 7 },
+                to: Hex {
+                    q: -21,
+                    r: 21,
+                    h: 0,
+                },
+                freezes_piece: true,
+            },
+            Move {
+                from: Hex { q: 0, r: 1, h: 0 },
+                to: Hex { q: 1, r: 0, h: 1 },
+                freezes_piece: false,
+            },
+        ];
+        let packed: FxHashSet<u32> = turns.iter().map(|turn| turn.to_u32().unwrap()).collect();
+        assert_eq!(packed.len(), turns.len());
+        for turn in turns {
+            assert_eq!(turn.to_u32().and_then(Turn::from_u32), Some(turn));
+        }
+
+        // Too far out or too high to fit
+        let off_board = |hex| Move {
+            from: Hex { q: 0, r: 0, h: 0 },
+            to: hex,
+            freezes_piece: false,
+        };
+        assert_eq!(off_board(Hex { q: 32, r: 0, h: 0 }).to_u32(), None);
+        assert_eq!(off_board(Hex { q: 0, r: -33, h: 0 }).to_u32(), None);
+        assert_eq!(off_board(Hex { q: 0, r: 0, h: 8 }).to_u32(), None);
+
+        // Unused bits set
+        assert_eq!(Turn::from_u32(1), None);
+        assert_eq!(Turn::from_u32(1 << 30 | 1 << 20), None);
     }
 
     #[test]
-    fn test_spider_cannot_temporarily_break_hive() {
-        assert_moves(
-            r#"
-            .  a  q
-             .  .  a
-            s  S  a
-        "#,
+    fn previews_moves_of_a_placement() {
+        let game = Game::from_uhp("Base", &["wS1", "bG1 wS1-", "wQ -wS1", "bQ bG1-"]).unwrap();
+        let ant = Hex { q: -2, r: 0, h: 0 };
+        let placed_ant = game.with_turn_applied(Placement {
+            hex: ant,
+            tile: Tile {
+                bug: Bug::Ant,
+                color: Color::White,
+            },
+        });
+        let expected: Vec<Hex> = placed_ant
+            .with_turn_applied(Skip)
+            .valid_destinations_for_piece(&ant)
+            .sorted()
+            .collect();
+
+        let moves = game.hypothetical_moves(ant, Bug::Ant);
+        assert!(!moves.is_empty());
+        assert_eq!(moves, expected);
+        // The real game is untouched
+        assert!(!game.hive.is_occupied(&ant));
+        // Next to a black piece, and a second queen
+        assert_eq!(
+            game.hypothetical_moves(Hex { q: 1, r: -1, h: 0 }, Bug::Ant),
+            vec![]
         );
+        assert_eq!(game.hypothetical_moves(ant, Bug::Queen), vec![]);
     }
 
     #[test]
-    fn test_ant_cannot_temporarily_break_hive() {
-        assert_moves(
-            r#"
-            .  a  q
-             .  .  a
-            s  A  a
-        "#,
-        );
+    fn counts_adjacent_pieces_by_top_color() {
+        let hive = HiveBuilder::new()
+            .place(0, 0, 0, Bug::Queen, Color::White)
+            .place(1, 0, 0, Bug::Ant, Color::White)
+            .place(1, 0, 1, Bug::Beetle, Color::Black)
+            .place(0, 1, 0, Bug::Spider, Color::White)
+            .place(-1, 0, 0, Bug::Queen, Color::Black)
+            .build()
+            .unwrap();
+        let game = Game::from_hive(hive, Color::White);
+        let queen = Hex { q: 0, r: 0, h: 0 };
+
+        // The ant under the black beetle counts for black
+        assert_eq!(game.adjacent_friendly(&queen, Color::White), 1);
+        assert_eq!(game.adjacent_enemy(&queen, Color::White), 2);
+        assert_eq!(game.adjacent_friendly(&queen, Color::Black), 2);
+        assert_eq!(game.adjacent_enemy(&queen, Color::Black), 1);
     }
 
     #[test]
-    fn test_beetle_cannot_temporarily_break_hive() {
-        assert_moves(
-            r#"
-            .  .  .
-             .  a  a
-            .  .  Q
-             .  .  a
-        "#,
+    fn finds_turns_that_do_not_pin_own_pieces() {
+        let game = Game::from_uhp("Base", &["wS1", "bG1 wS1-", "wQ -wS1", "bQ bG1-"]).unwrap();
+        // Only the spider holds the two halves of the hive together
+        assert_eq!(
+            game.hive.pinned_pieces(Color::White),
+            vec![Hex { q: 0, r: 0, h: 0 }]
         );
+
+        let ant_at = |q, r| Placement {
+            hex: Hex { q, r, h: 0 },
+            tile: Tile {
+                bug: Bug::Ant,
+                color: Color::White,
+            },
+        };
+        let safe = game.non_pinning_turns();
+        // Hanging a piece off the end of the queen pins her, tucking it between the queen and the
+        // spider doesn't
+        assert!(!safe.contains(&ant_at(-2, 0)));
+        assert!(safe.contains(&ant_at(0, -1)));
+        for turn in game.turns() {
+            let pinned = game
+                .with_turn_applied(turn)
+                .hive
+                .pinned_pieces(Color::White);
+            assert_eq!(safe.contains(&turn), pinned.len() <= 1, "{turn:?}");
+        }
     }
 
     #[test]
-    fn test_beetle_cannot_break_slide_rules_when_mounting() {
-        assert_moves(
-            r#"
-            Layer 0
-            .  a  a
-             a  B  *
-            .  *  .
-            Layer 1
-            .  .  b
-             b  .  .
-            .  .  .
-            Layer 2
-            .  .  *
-             *  .  .
-            .  .  .
-            "#,
-        )
+    fn finds_turns_into_a_region() {
+        let hive: Hive = r#"
+        Layer 0
+        .  .  .  .
+         Q  q  P  .
+        .  A  B  a
+        Layer 1
+        .  .  .  .
+         .  .  .  .
+        .  .  .  b
+        "#
+        .parse()
+        .unwrap();
+        let game = Game::from_hive(hive, Color::White);
+        let queen = game.hive.pieces_of(Bug::Queen, Color::Black)[0];
+        let region: FxHashSet<Hex> = game.hive.unoccupied_neighbors(&queen).collect();
+
+        let turns = game.turns_into(&region);
+        let expected: Vec<Turn> = game
+            .turns()
+            .filter(|turn| match turn {
+                Placement { hex, .. } => region.contains(hex),
+                Move { to, .. } => region.contains(&to.base_level()),
+                Skip => false,
+            })
+            .collect();
+        assert_eq!(turns, expected);
+        // The white ant can run up to the queen, and the pillbug can push a piece next to it
+        assert!(turns.iter().any(|turn| matches!(turn, Move { from, .. }
+            if game.hive.tile_at(from).unwrap().bug == Bug::Ant)));
+        assert!(turns.iter().any(|turn| matches!(
+            turn,
+            Move {
+                freezes_piece: true,
+                ..
+            }
+        )));
+        assert_eq!(game.turns_into(&FxHashSet::default()), vec![]);
     }
 
     #[test]
-    fn test_ladybug_movement() {
-        assert_moves(
-            r#"
-            .  *  *
-             *  a  *
-            .  a  *
-             .  L  .
-            "#,
-        );
+    fn finds_opponent_winning_replies() {
+        let hive: Hive = r#"
+             Q  A  G  .
+              B  q  G  .
+             .  S  .  A
+            "#
+        .parse()
+        .unwrap();
+        let white_to_move = Game::from_hive_with_reserves(hive, Color::White, vec![], vec![]);
+        let winning_move = Move {
+            from: Hex { q: 2, r: 2, h: 0 },
+            to: Hex { q: 1, r: 2, h: 0 },
+            freezes_piece: false,
+        };
+        assert_eq!(white_to_move.opponent_winning_replies(winning_move), vec![]);
+
+        let black_to_move = Game::from_hive(white_to_move.hive.clone(), Color::Black);
+        let white_wins = GameResult::Winner {
+            color: Color::White,
+        };
+        let mut threatened = 0;
+        for turn in black_to_move.turns() {
+            let after = black_to_move.with_turn_applied(turn);
+            let expected: Vec<Turn> = after
+                .turns()
+                .filter(|reply| after.with_turn_applied(*reply).game_result() == white_wins)
+                .collect();
+            assert_eq!(black_to_move.opponent_winning_replies(turn), expected);
+            threatened += (!expected.is_empty()) as usize;
+        }
+        assert!(threatened > 0);
     }
 
     #[test]
-    fn test_ladybug_can_traverse_any_height() {
-        assert_moves(
-            r#"
-            Layer 0
-            .  *  *
-             *  a  *
-            .  a  *
-             .  L  .
-            Layer 1
-            .  .  .
-             .  B  .
-            .  .  .
-             .  .  .
-            Layer 2
-            .  .  .
-             .  b  .
-            .  .  .
-             .  .  .
-            "#,
-        );
+    fn finds_opponent_winning_replies_that_push_the_queen() {
+        let hive = HiveBuilder::new()
+            .place(0, 0, 0, Bug::Pillbug, Color::Black)
+            .place(1, 0, 0, Bug::Queen, Color::White)
+            .place(-1, -1, 0, Bug::Ant, Color::White)
+            .place(0, -1, 0, Bug::Beetle, Color::White)
+            .place(-1, 1, 0, Bug::Ant, Color::Black)
+            .place(-2, 1, 0, Bug::Beetle, Color::Black)
+            .place(-2, 0, 0, Bug::Grasshopper, Color::White)
+            .place(-3, 1, 0, Bug::Queen, Color::Black)
+            .build()
+            .unwrap();
+        let game = Game::from_hive(hive, Color::White);
+        // The black pillbug can push the white queen into the hole surrounded on all sides
+        let push = Move {
+            from: Hex { q: 1, r: 0, h: 0 },
+            to: Hex { q: -1, r: 0, h: 0 },
+            freezes_piece: true,
+        };
+        let black_wins = GameResult::Winner {
+            color: Color::Black,
+        };
+        let mut pushed = 0;
+        for turn in game.turns() {
+            let after = game.with_turn_applied(turn);
+            let expected: Vec<Turn> = after
+                .turns()
+                .filter(|reply| after.with_turn_applied(*reply).game_result() == black_wins)
+                .collect();
+            assert_eq!(game.opponent_winning_replies(turn), expected, "{turn:?}");
+            pushed += expected.contains(&push) as usize;
+        }
+        assert!(pushed > 0);
     }
 
     #[test]
-    fn test_ladybug_cant_make_illegal_slides() {
-        assert_moves(
-            r#"
-            Layer 0
-            .  .  a  .
-             q  a  *  .
-            .  *  a  .
-             .  .  L  .
-            Layer 1
-            .  .  b  .
-             b  .  .  .
-            .  .  .  .
-             .  .  .  .
-            "#,
+    fn finds_forced_wins() {
+        let hive: Hive = r#"
+             Q  A  G  .
+              B  q  G  .
+             .  S  .  A
+            "#
+        .parse()
+        .unwrap();
+        let game = Game::from_hive_with_reserves(hive, Color::White, vec![], vec![]);
+
+        let line = game.forced_win_in(Color::White, 3).unwrap();
+        assert_eq!(
+            line,
+            vec![Move {
+                from: Hex { q: 2, r: 2, h: 0 },
+                to: Hex { q: 1, r: 2, h: 0 },
+                freezes_piece: false,
+            }]
         );
+        assert_eq!(game.forced_win_in(Color::White, 0), None);
+        assert_eq!(game.forced_win_in(Color::Black, 3), None);
     }
 
     #[test]
-    fn test_ladybug_cant_break_hive() {
-        assert_moves(
-            r#"
-            .  .  .  .
-             .  a  .  .
-            .  .  a  .
-             .  .  L  .
-            .  .  .  a
-            "#,
-        );
+    fn forced_wins_tell_apart_positions_that_only_differ_by_the_last_move() {
+        let hive: Hive = r#"
+        Layer 0
+             .  a  a  l  .
+              s  q  .  P  .
+             Q  g  g  a  .
+        Layer 1
+             .  .  .  .  .
+              .  .  .  .  .
+             .  .  .  b  .
+            "#
+        .parse()
+        .unwrap();
+        let game = Game::from_hive_with_reserves(hive, Color::White, vec![], vec![]);
+        let moved_last = |bug| Game {
+            last_turn: Some(Move {
+                from: Hex { q: 5, r: 5, h: 0 },
+                to: game.hive.pieces_of(bug, Color::Black)[0],
+                freezes_piece: false,
+            }),
+            ..game.clone()
+        };
+        // The pillbug can only win by pushing the ladybug next to the black queen
+        let ladybug_moved = moved_last(Bug::Ladybug);
+        let spider_moved = moved_last(Bug::Spider);
+        assert_eq!(ladybug_moved.forced_win_in(Color::White, 1), None);
+        let win = spider_moved.forced_win_in(Color::White, 1);
+        assert!(win.is_some());
+
+        let mut ai = Ai::deterministic(0, 1);
+        let mut proven = FxHashMap::default();
+        let ladybug_line = ladybug_moved.forced_win_line(Color::White, 1, &mut ai, &mut proven);
+        assert_eq!(ladybug_line, None);
+        let spider_line = spider_moved.forced_win_line(Color::White, 1, &mut ai, &mut proven);
+        assert_eq!(spider_line, win);
     }
 
-    #[test]
-    fn test_ant_can_go_in_and_out_of_pocket() {
-        assert_moves(
-            r#"
-            .  *  *  *
-             *  a  a  A
-            .  *  *  a  *
-             .  *  a  *
-            .  .  *  *
-        "#,
+    #[test]
+    fn turns_signature_only_depends_on_the_set_of_turns() {
+        let game = random_game(5, 8);
+        let mut shuffled = game.clone();
+        shuffled.white_reserve.reverse();
+        shuffled.black_reserve.reverse();
+        shuffled.placement_hexes = Default::default();
+        assert_ne!(
+            game.turns().collect_vec(),
+            shuffled.turns().collect_vec(),
+            "the reserves should change the order turns come out in"
+        );
+        assert_eq!(game.turns_signature(), shuffled.turns_signature());
+
+        let turn = game.turns().next().unwrap();
+        assert_ne!(
+            game.turns_signature(),
+            game.with_turn_applied(turn).turns_signature()
         );
     }
 
     #[test]
-    fn test_mosquito_can_copy_queen() {
-        assert_moves(
-            r#"
-        .  .  .
-         .  q  *
-        .  *  M
-        "#,
-        )
+    fn sorted_turns_do_not_depend_on_storage_order() {
+        let game = random_game(11, 10);
+        let mut shuffled = game.clone();
+        shuffled.white_reserve.reverse();
+        shuffled.black_reserve.reverse();
+        shuffled.placement_hexes = Default::default();
+
+        assert_ne!(game.turns().collect_vec(), shuffled.turns().collect_vec());
+        let sorted = game.turns_sorted();
+        assert!(sorted.is_sorted());
+        assert_eq!(sorted, shuffled.turns_sorted());
+        assert_eq!(sorted, game.turns().sorted().collect_vec());
     }
 
     #[test]
-    fn test_mosquito_can_copy_multiple_abilities() {
-        assert_moves(
-            r#"
-        .  *  .  *
-         .  q  g  .
-        .  *  M  *
-        "#,
-        )
+    fn canonical_key_ignores_symmetry_but_not_reserves() {
+        let game = random_game(3, 8);
+        let mirrored = Game {
+            hive: Hive {
+                map: game
+                    .hive
+                    .map
+                    .iter()
+                    .map(|(hex, tile)| (hex.reflected(), *tile))
+                    .collect(),
+            },
+            immobilized_piece: game.immobilized_piece.map(|hex| hex.reflected()),
+            last_turn: game.last_turn.map(|turn| match turn {
+                Move {
+                    from,
+                    to,
+                    freezes_piece,
+                } => Move {
+                    from: from.reflected(),
+                    to: to.reflected(),
+                    freezes_piece,
+                },
+                Placement { hex, tile } => Placement {
+                    hex: hex.reflected(),
+                    tile,
+                },
+                Skip => Skip,
+            }),
+            ..game.clone()
+        };
+        assert_eq!(game.canonical_key(), mirrored.canonical_key());
+        assert_eq!(
+            game.canonical_key(),
+            game.rotated_by(RotationDegrees::OneTwenty).canonical_key()
+        );
+
+        let mut fewer_ants = game.clone();
+        fewer_ants.white_reserve.retain(|bug| *bug != Bug::Ant);
+        assert_ne!(game.canonical_key(), fewer_ants.canonical_key());
+
+        let other_player = Game {
+            active_player: game.active_player.opposite(),
+            ..game.clone()
+        };
+        assert_ne!(game.canonical_key(), other_player.canonical_key());
     }
 
     #[test]
-    fn test_mosquito_cannot_copy_another_mosquito() {
-        assert_moves(
-            r#"
-        .  q  .
-         .  m  .
-        .  .  M
-        "#,
-        );
+    fn debug_prints_board_and_reserves() {
+        let game = Game::from_turns([Placement {
+            hex: Hex { q: 0, r: 0, h: 0 },
+            tile: Tile {
+                bug: Bug::Ant,
+                color: Color::White,
+            },
+        }])
+        .unwrap();
+        let debug = format!("{game:?}");
+
+        assert!(debug.contains("active player: black"));
+        assert!(debug.contains("white reserve: Q A A B B"));
+        assert!(debug.contains("black reserve: Q A A A"));
+        assert!(debug.contains(" A "));
     }
 
     #[test]
-    fn test_pillbug_can_use_special_ability() {
-        assert_pillbug_pushes(
-            r#"
-        .  *  *
-         *  P  *
-        .  &  *
-        "#,
+    fn finds_covered_enemy_pieces() {
+        let hive = HiveBuilder::new()
+            .place(0, 0, 0, Bug::Queen, Color::Black)
+            .place(1, 0, 0, Bug::Ant, Color::Black)
+            .place(1, 0, 1, Bug::Mosquito, Color::White)
+            .place(-1, 0, 0, Bug::Queen, Color::White)
+            .place(-1, 0, 1, Bug::Beetle, Color::Black)
+            .place(-1, 0, 2, Bug::Beetle, Color::White)
+            .build()
+            .unwrap();
+        let game = Game::from_hive(hive, Color::White);
+
+        // The black beetle is covered too, but isn't on the ground
+        assert_eq!(
+            game.covered_enemy_pieces(Color::White),
+            vec![Hex { q: 1, r: 0, h: 0 }]
+        );
+        assert_eq!(
+            game.covered_enemy_pieces(Color::Black),
+            vec![Hex { q: -1, r: 0, h: 0 }]
         );
     }
 
     #[test]
-    fn test_pillbug_can_slide() {
-        assert_moves(
-            r#"
-            .  .  .
-             .  q  *
-            .  *  P
-        "#,
-        )
+    fn bug_metadata_matches_move_generation() {
+        for seed in 0..20 {
+            let game = random_game(seed, 30);
+            for turn in game.turns() {
+                if let Move {
+                    from,
+                    to,
+                    freezes_piece,
+                } = turn
+                {
+                    let bug = game.hive.tile_at(&from).unwrap().bug;
+                    assert!(to.h == 0 || bug.can_climb(), "{bug:?} climbed to {to:?}");
+                    // Pushed pieces move two spaces no matter what they are
+                    if bug.movement_range() == Some(1) && !freezes_piece {
+                        assert!(
+                            is_adjacent(&from, &to),
+                            "{bug:?} moved from {from:?} to {to:?}"
+                        );
+                    }
+                }
+            }
+        }
     }
 
     #[test]
-    fn test_pillbug_cannot_pull_through_blocked_gap() {
-        assert_pillbug_pushes(
+    fn space_control_counts_each_hex_once() {
+        let game = Game::from_hive_with_reserves(
             r#"
-        Layer 0
-        .  .  .
-         .  P  a
-        .  a  &
-        Layer 1
-        .  .  .
-         .  .  b
-        .  b  .
-        "#,
-        )
+            .  A  .  .
+             Q  q  A
+            "#
+            .parse()
+            .unwrap(),
+            Color::White,
+            vec![],
+            vec![],
+        );
+        let white_moves = game
+            .movement_turns()
+            .filter(|turn| matches!(turn, Move { to, .. } if to.h == 0))
+            .count();
+
+        // Both ants can get almost everywhere around the hive
+        assert!(game.space_control(Color::White) < white_moves);
+        assert_eq!(game.space_control(Color::White), 11);
+        assert_eq!(game.space_control(Color::Black), 0);
     }
 
     #[test]
-    fn test_pillbug_cannot_push_through_blocked_gap() {
-        assert_pillbug_pushes(
-            r#"
-        Layer 0
-        .  .  a
-         a  P  *
-        .  *  &
-        Layer 1
-        .  .  b
-         b  .  .
-        .  .  .
-        "#,
-        )
+    fn phase_goes_from_zero_to_one() {
+        assert_eq!(Game::default().phase(), 0.0);
+        let game = random_game(5, 6);
+        assert_eq!(game.phase(), 6.0 / (DEFAULT_RESERVE.len() * 2) as f32);
+        let full = Game::from_hive_with_reserves(game.hive.clone(), Color::White, vec![], vec![]);
+        assert_eq!(full.phase(), 1.0);
     }
 
     #[test]
-    fn test_pillbug_cannot_move_piece_that_just_moved() {
-        let hex_map = parse_hex_map_string(
-            r#"
-        .  .  .
-         q  Q  .
-        .  .  P
-        "#,
-        )
-        .unwrap();
-        let hive = Hive::from_hex_map(&hex_map).unwrap();
-        let mut game = Game::from_hive(hive, Color::Black);
-
-        // Do this move
-        // .  .  .
-        //  _  Q  .
-        // .  q  P
-        game = game.with_turn_applied(Move {
-            from: Hex { q: 0, r: 1, h: 0 },
-            to: Hex { q: 0, r: 2, h: 0 },
-            freezes_piece: false,
-        });
+    fn beetle_cant_climb_through_a_gate() {
+        let hive = HiveBuilder::new()
+            .place(0, 0, 0, Bug::Beetle, Color::White)
+            .place(1, 0, 0, Bug::Queen, Color::White)
+            .place(1, -1, 0, Bug::Ant, Color::White)
+            .place(1, -1, 1, Bug::Beetle, Color::Black)
+            .place(0, 1, 0, Bug::Ant, Color::White)
+            .place(0, 1, 1, Bug::Beetle, Color::Black)
+            .build()
+            .unwrap();
+        let game = Game::from_hive_with_reserves(hive, Color::White, vec![], vec![]);
 
-        // Find all the moves that move the black queen (at q: 0, r: 2)
-        let moves = game
-            .pillbug_moves(&Hex { q: 1, r: 2, h: 0 })
-            .filter(|turn| match turn {
-                Move {
-                    from: Hex { q: 0, r: 2, h: 0 },
-                    ..
-                } => true,
-                _ => false,
-            });
+        let mut climbs = game.beetle_climb_destinations(&Hex::default());
+        climbs.sort();
+        assert_eq!(
+            climbs,
+            vec![Hex { q: 0, r: 1, h: 2 }, Hex { q: 1, r: -1, h: 2 }]
+        );
+    }
 
-        // There shouldn't be any
-        assert_eq!(moves.count(), 0);
+    fn random_game(seed: u64, turns: usize) -> Game {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut game = Game::default();
+        for _ in 0..turns {
+            let turns: Vec<Turn> = game.turns().collect();
+            game = game.with_turn_applied(*turns.choose(&mut rng).unwrap());
+        }
+        game
     }
 
     #[test]
-    fn test_cannot_move_piece_just_moved_by_pillbug() {
-        let hex_map = parse_hex_map_string(
-            r#"
-        .  .  .
-         .  P  .
-        .  q  Q
-        "#,
-        )
-        .unwrap();
+    fn replay_checked_finds_the_first_illegal_turn() {
+        // Random games that push with a pillbug whenever they can, until one of them pushes a
+        // piece its owner could otherwise move next turn. One of the first few seeds does, the bound
+        // is there so a change to move generation fails the test instead of hanging it.
+        let (turns, frozen_move) = (0..20)
+            .find_map(|seed| {
+                let mut rng = StdRng::seed_from_u64(seed);
+                let mut game = Game::default();
+                let mut turns = vec![];
+                while turns.len() < 60 && !game.is_game_over() {
+                    let pushes = game.ability_turns();
+                    let options = if pushes.is_empty() {
+                        game.turns().collect()
+                    } else {
+                        pushes
+                    };
+                    let turn = *options.choose(&mut rng).unwrap();
+                    game = game.with_turn_applied(turn);
+                    turns.push(turn);
+                    if let Move { to, .. } = turn
+                        && !game.is_game_over()
+                        && game.hive.tile_at(&to).unwrap().color == game.active_player
+                    {
+                        let unfrozen = Game {
+                            immobilized_piece: None,
+                            ..game.clone()
+                        };
+                        if let Some(escape) = unfrozen.moves_for_piece(&to).next() {
+                            return Some((turns, escape));
+                        }
+                    }
+                }
+                None
+            })
+            .expect("none of the seeds pushed a piece that could otherwise move");
 
-        let hive = Hive::from_hex_map(&hex_map).unwrap();
-        let mut game = Game::from_hive(hive, Color::White);
+        let game = Game::replay_checked(&turns).unwrap();
+        assert_eq!(game, Game::from_turns(turns.iter().copied()).unwrap());
+        assert!(game.immobilized_piece.is_some());
 
-        // Move black queen from bottom left of pillbug to top right:
-        // .  .  q
-        //  .  P  .
-        // .  _  Q
-        game = game.with_turn_applied(Move {
-            from: Hex { q: 0, r: 2, h: 0 },
-            to: Hex { q: 2, r: 0, h: 0 },
-            freezes_piece: true,
-        });
+        let mut tampered = turns.clone();
+        tampered.push(frozen_move);
+        assert_eq!(
+            Game::replay_checked(&tampered).unwrap_err(),
+            (turns.len(), ApplyError::IllegalTurn { turn: frozen_move })
+        );
 
-        // Black should not be able to move anything
-        assert_eq!(game.moves().count(), 0);
+        let mut tampered = turns.clone();
+        tampered.insert(2, Skip);
+        assert_eq!(
+            Game::replay_checked(&tampered).unwrap_err(),
+            (2, ApplyError::IllegalTurn { turn: Skip })
+        );
     }
 
-    #[test]
-    fn test_pillbug_can_use_ability_when_frozen() {
-        let hex_map = parse_hex_map_string(
-            r#"
-            .  Q  .
-             p  q  .
-            .  P  .
-            "#,
-        )
-        .unwrap();
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn queen_surrounds_match_full_scan(seed in any::<u64>(), turns in 0..60usize) {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut game = Game::default();
+            for _ in 0..turns {
+                prop_assert_eq!(game.queens, QueenSurrounds::scan(&game.hive));
+                if game.game_result() != GameResult::None {
+                    break;
+                }
+                let turns: Vec<Turn> = game.turns().collect();
+                game = game.with_turn_applied(*turns.choose(&mut rng).unwrap());
+            }
+            prop_assert_eq!(game.queens, QueenSurrounds::scan(&game.hive));
+        }
 
-        let hive = Hive::from_hex_map(&hex_map).unwrap();
-        let mut game = Game::from_hive(hive, Color::White);
+        #[test]
+        fn cached_placement_hexes_match_fresh_ones(seed in any::<u64>(), turns in 0..40usize) {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut game = Game::default();
+            for _ in 0..turns {
+                if game.game_result() != GameResult::None {
+                    break;
+                }
+                // Generating turns fills in the active player's cache, and a skip carries both
+                // colors' caches over to the next game
+                let turns: Vec<Turn> = game.turns().collect();
+                for color in [Color::White, Color::Black] {
+                    if let Some(cached) = game.placement_hexes.get(color) {
+                        let fresh = game.find_placement_hexes(color);
+                        prop_assert_eq!(cached, fresh.as_slice());
+                    }
+                }
+                game = game.with_turn_applied(*turns.choose(&mut rng).unwrap());
+            }
+        }
 
-        // Use white pillbug to move black pillbug, freezing it
-        // .  Q  .
-        //  _  q  .
-        // .  P  p
-        game = game.with_turn_applied(Move {
-            from: Hex { q: 0, r: 1, h: 0 },
-            to: Hex { q: 1, r: 2, h: 0 },
-            freezes_piece: true,
-        });
+        #[test]
+        fn has_any_turn_agrees_with_generating_them(seed in any::<u64>(), turns in 0..50usize) {
+            let game = random_game(seed, turns);
+            let mut found = false;
+            game.for_each_turn(|turn| found |= turn != Skip);
+            prop_assert_eq!(game.has_any_turn(), found);
+            prop_assert_eq!(game.turns().any(|turn| turn != Skip), found);
+        }
 
-        // Use black pillbug to move white pillbug, even though the black pillbug is frozen
-        // .  Q  .
-        //  .  q  P
-        // .  _  p
-        assert!(game.turn_is_valid(Move {
-            from: Hex { q: 0, r: 2, h: 0 },
-            to: Hex { q: 2, r: 1, h: 0 },
-            freezes_piece: true
-        }))
+        #[test]
+        fn preview_agrees_with_turns(seed in any::<u64>(), turns in 0..30usize) {
+            let game = random_game(seed, turns);
+            prop_assume!(game.game_result() == GameResult::None);
+            let legal: FxHashSet<Turn> = game.turns().collect();
+
+            let outline = game.hive.outline();
+            // Legal pushes are already in `legal`, so plain moves are enough to catch illegal ones
+            let moves = game.hive.toplevel_pieces().flat_map(|(from, _)| {
+                outline.iter().map(move |to| Move {
+                    from: *from,
+                    to: Hex { h: 0, ..*to },
+                    freezes_piece: false,
+                })
+            });
+            let placements = outline.iter().flat_map(|hex| {
+                game.active_reserve().iter().map(|bug| Placement {
+                    hex: *hex,
+                    tile: Tile {
+                        bug: *bug,
+                        color: game.active_player,
+                    },
+                })
+            });
+            for turn in moves.chain(placements).chain(legal.iter().copied()).chain([Skip]) {
+                prop_assert_eq!(game.preview(turn).is_ok(), legal.contains(&turn), "{:?}", turn);
+            }
+        }
     }
 
+    proptest! {
+        #[test]
+        fn move_generation_is_rotation_invariant(
+            seed in any::<u64>(),
+            turns in 0..16usize,
+            rotation in proptest::sample::select(RotationDegrees::iter().collect::<Vec<_>>()),
+        ) {
+            let game = random_game(seed, turns);
+            let rotated = game.rotated_by(rotation);
+
+            let expected: FxHashSet<Turn> =
+                game.turns().map(|turn| turn.rotated_by(rotation)).collect();
+            let actual: FxHashSet<Turn> = rotated.turns().collect();
+            prop_assert_eq!(expected, actual);
+        }
+    }
+}
+
+#[cfg(test)]
+mod perft_tests {
+    use super::*;
+
+    fn perft(game_type: &str, depth: usize) -> u64 {
+        Game::from_uhp(game_type, &[]).unwrap().perft(depth)
+    }
+
+    // Worked out by hand. The first piece can be any bug but the queen, the second goes on any of
+    // the 6 hexes around it, and each player's second piece on one of the 3 hexes touching only
+    // their own color. By then a player can place any kind of bug they have left, including the
+    // queen, which with the expansion bugs is one fewer kind if their first piece was a ladybug,
+    // mosquito or pillbug. Nothing can move yet since neither queen is on the board.
     #[test]
-    fn test_mosquito_can_use_pillbug_ability_even_if_pillbug_is_frozen() {
-        let hex_map = parse_hex_map_string(
-            r#"
-        .  P  .
-         p  q  Q
-        .  .  M
-        "#,
-        )
-        .unwrap();
+    fn base_game() {
+        assert_eq!(perft("Base", 1), 4);
+        assert_eq!(perft("Base", 2), 4 * 6 * 4);
+        assert_eq!(perft("Base", 3), 4 * 6 * 4 * 3 * 5);
+        assert_eq!(perft("Base", 4), 4 * 6 * 4 * 3 * 5 * 3 * 5);
+    }
 
-        let hive = Hive::from_hex_map(&hex_map).unwrap();
-        let mut game = Game::from_hive(hive, Color::Black);
+    fn resulting_positions(game: &Game, turns: impl Iterator<Item = Turn>) -> FxHashSet<u64> {
+        turns
+            .map(|turn| game.with_turn_applied(turn).canonical_key())
+            .collect()
+    }
 
-        // Use black pillbug to move white pillbug to the hex next to the white mosquito
-        // .  _  .
-        //  p  q  Q
-        // .  P  M
-        game = game.with_turn_applied(Move {
-            from: Hex { q: 1, r: 0, h: 0 },
-            to: Hex { q: 0, r: 2, h: 0 },
-            freezes_piece: true,
-        });
+    #[test]
+    fn symmetry_merges_mirror_image_placements() {
+        let reduced = |moves: &[&str]| {
+            let game = Game::from_uhp("Base", moves).unwrap();
+            let turns = game.symmetry_reduced_turns();
+            // Every turn left out leads somewhere one of the kept turns does
+            assert_eq!(
+                resulting_positions(&game, turns.iter().copied()),
+                resulting_positions(&game, game.turns())
+            );
+            (game.turns().count(), turns.len())
+        };
 
-        // Make sure we can move the white pillbug to the other side of the white mosquito by having
-        // the white mosquito copy the white pillbug's ability
-        // .  .  .  .
-        //  p  q  _  .
-        // .  P  M  Q
-        assert!(game.turn_is_valid(Move {
-            from: Hex { q: 2, r: 1, h: 0 },
-            to: Hex { q: 2, r: 2, h: 0 },
-            freezes_piece: true,
-        }));
+        assert_eq!(reduced(&[]), (4, 4));
+        // All 6 hexes around the first piece are the same
+        assert_eq!(reduced(&["wA1"]), (4 * 6, 4));
+        // Of the 3 hexes white can place on, the two on the sides mirror each other
+        assert_eq!(reduced(&["wA1", "bA1 wA1-"]), (5 * 3, 5 * 2));
+    }
+
+    #[test]
+    fn all_expansions() {
+        assert_eq!(perft("Base+MLP", 1), 7);
+        assert_eq!(perft("Base+MLP", 2), 7 * 6 * 7);
+        assert_eq!(perft("Base+MLP", 3), 6 * 7 * 3 * (4 * 8 + 3 * 7));
+        assert_eq!(
+            perft("Base+MLP", 4),
+            6 * 3 * 3 * (4 * 8 + 3 * 7) * (4 * 8 + 3 * 7)
+        );
     }
 }
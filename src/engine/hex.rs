@@ -1,5 +1,5 @@
 use std::ops;
-use strum::{EnumIter, IntoEnumIterator};
+use strum::EnumIter;
 
 #[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, Ord, PartialOrd, Default)]
 pub struct Hex {
@@ -34,6 +34,15 @@ impl Hex {
         Hex { h: 0, ..*self }
     }
 
+    /// The mirror image of this hex across the line through the origin where r == s
+    pub fn reflected(&self) -> Hex {
+        Hex {
+            q: self.q,
+            r: self.s(),
+            h: self.h,
+        }
+    }
+
     pub fn rotated_by(&self, degrees: RotationDegrees) -> Hex {
         // To rotate 60 degrees clockwise you multiply q, r, and s by negative one and shift the coordinate
         // one to the left. Repeat the process on the result to go another 60 deg.
@@ -103,15 +112,20 @@ impl ops::Sub<&Hex> for &Hex {
 }
 
 impl Direction {
+    /// Every direction, going around a hex in a circle
+    pub fn all() -> [Direction; 6] {
+        [
+            Direction::UpLeft,
+            Direction::UpRight,
+            Direction::Right,
+            Direction::DownRight,
+            Direction::DownLeft,
+            Direction::Left,
+        ]
+    }
+
     pub fn vector(&self) -> Hex {
-        match *self {
-            Direction::UpLeft => Hex { q: 0, r: -1, h: 0 },
-            Direction::UpRight => Hex { q: 1, r: -1, h: 0 },
-            Direction::Right => Hex { q: 1, r: 0, h: 0 },
-            Direction::DownRight => Hex { q: 0, r: 1, h: 0 },
-            Direction::DownLeft => Hex { q: -1, r: 1, h: 0 },
-            Direction::Left => Hex { q: -1, r: 0, h: 0 },
-        }
+        DIRECTIONS[*self as usize]
     }
 }
 
@@ -121,8 +135,48 @@ pub fn flat_distance(lhs: &Hex, rhs: &Hex) -> i32 {
     (vec.q.abs() + vec.r.abs() + vec.s().abs()) / 2
 }
 
-pub fn neighbors(hex: &Hex) -> impl Iterator<Item = Hex> {
-    Direction::iter().map(|d| neighbor(hex, &d))
+/// The fractional axial coordinates `t` of the way from `a` to `b`, ignoring height. `t` of 0 is
+/// `a` and 1 is `b`.
+pub fn lerp(a: &Hex, b: &Hex, t: f32) -> (f32, f32) {
+    let q = a.q as f32 + (b.q - a.q) as f32 * t;
+    let r = a.r as f32 + (b.r - a.r) as f32 * t;
+    (q, r)
+}
+
+/// The center of a hex in a pointy-top layout where each hex has a corner radius of `size`. This
+/// lines up with `RowCol::pixel_center`, so odd rows end up shifted half a hex to the right the
+/// same way the board is printed.
+pub fn to_pixel(hex: &Hex, size: f32) -> (f32, f32) {
+    fractional_to_pixel(hex.q as f32, hex.r as f32, size)
+}
+
+/// `to_pixel` for fractional axial coordinates, like the ones `lerp` returns
+pub fn fractional_to_pixel(q: f32, r: f32, size: f32) -> (f32, f32) {
+    let x = size * 3f32.sqrt() * (q + r / 2.0);
+    let y = size * 1.5 * r;
+    (x, y)
+}
+
+/// The offset to each of the six neighbors of a hex, in the same order as `Direction` so they go
+/// around the hex in a circle
+pub const DIRECTIONS: [Hex; 6] = [
+    Hex { q: 0, r: -1, h: 0 },
+    Hex { q: 1, r: -1, h: 0 },
+    Hex { q: 1, r: 0, h: 0 },
+    Hex { q: 0, r: 1, h: 0 },
+    Hex { q: -1, r: 1, h: 0 },
+    Hex { q: -1, r: 0, h: 0 },
+];
+
+/// The six hexes around `hex` at the same height, in `Direction` order
+#[inline]
+pub fn neighbor_array(hex: &Hex) -> [Hex; 6] {
+    DIRECTIONS.map(|offset| *hex + offset)
+}
+
+#[inline]
+pub fn neighbors(hex: &Hex) -> impl Iterator<Item = Hex> + use<> {
+    neighbor_array(hex).into_iter()
 }
 
 pub fn neighbor(hex: &Hex, direction: &Direction) -> Hex {
@@ -147,6 +201,8 @@ pub enum Direction {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::engine::row_col::RowCol;
+    use strum::IntoEnumIterator;
     #[test]
     fn test_distance_identity() {
         assert_eq!(
@@ -193,6 +249,19 @@ mod tests {
         assert_eq!(1, Hex { q: -1, r: 0, h: 0 }.s());
     }
 
+    #[test]
+    fn test_neighbors_go_in_a_circle() {
+        let hex = Hex { q: 3, r: -2, h: 1 };
+        let neighbors = neighbor_array(&hex);
+        for i in 0..6 {
+            assert_eq!(neighbors[i].h, hex.h);
+            assert!(is_adjacent(&neighbors[i], &hex));
+            assert!(is_adjacent(&neighbors[i], &neighbors[(i + 1) % 6]));
+        }
+        let directions: Vec<Hex> = Direction::iter().map(|d| neighbor(&hex, &d)).collect();
+        assert_eq!(neighbors.to_vec(), directions);
+    }
+
     #[test]
     fn test_neighbor() {
         pretty_assertions::assert_eq!(
@@ -200,4 +269,38 @@ mod tests {
             Hex { q: 0, r: -1, h: 0 }
         )
     }
+
+    #[test]
+    fn test_directions_match_direction_vectors() {
+        assert_eq!(
+            Direction::all().to_vec(),
+            Direction::iter().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Direction::all().map(|direction| direction.vector()),
+            DIRECTIONS
+        );
+    }
+
+    #[test]
+    fn pixels_match_row_col_layout() {
+        for q in -3..=3 {
+            for r in -3..=3 {
+                let hex = Hex { q, r, h: 0 };
+                let (x, y) = to_pixel(&hex, 10.0);
+                let (expected_x, expected_y) = RowCol::from_hex(&hex).pixel_center(10.0);
+                assert!((x as f64 - expected_x).abs() < 1e-3, "{hex:?}");
+                assert!((y as f64 - expected_y).abs() < 1e-3, "{hex:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn lerp_goes_from_start_to_end() {
+        let a = Hex { q: 1, r: -2, h: 0 };
+        let b = Hex { q: 3, r: 0, h: 1 };
+        assert_eq!(lerp(&a, &b, 0.0), (1.0, -2.0));
+        assert_eq!(lerp(&a, &b, 0.5), (2.0, -1.0));
+        assert_eq!(lerp(&a, &b, 1.0), (3.0, 0.0));
+    }
 }
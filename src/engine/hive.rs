@@ -1,8 +1,11 @@
 use crate::engine::bug::{Bug, BugParseError};
-use crate::engine::hex::{neighbors, Hex};
+use crate::engine::hex::{
+    flat_distance, neighbor, neighbor_array, neighbors, Direction, Hex, RotationDegrees,
+};
 use crate::engine::parse::{hex_map_to_string, parse_hex_map_string, HexMapParseError};
-use crate::engine::row_col::{dimensions, RowColDimensions};
-use rustc_hash::FxHashMap;
+use crate::engine::row_col::{dimensions, RowCol, RowColDimensions};
+use itertools::Itertools;
+use rustc_hash::{FxHashMap, FxHashSet};
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 use strum::{Display, EnumString};
@@ -43,29 +46,79 @@ impl Display for Tile {
     }
 }
 
+const SVG_HEX_SIZE: f64 = 20.0;
+
+impl FromStr for Tile {
+    type Err = BugParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bug = s.to_uppercase().parse()?;
+        let color = if s.chars().next().is_some_and(char::is_uppercase) {
+            Color::White
+        } else {
+            Color::Black
+        };
+        Ok(Tile { bug, color })
+    }
+}
+
+/// The tiles that changed between two boards, see `Hive::diff`. Each list is sorted.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct HiveDiff {
+    pub added: Vec<(Hex, Tile)>,
+    pub removed: Vec<(Hex, Tile)>,
+    /// Tiles that disappeared from one hex and showed up at another, as `(tile, from, to)`
+    pub moved: Vec<(Tile, Hex, Hex)>,
+}
+
+impl HiveDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.moved.is_empty()
+    }
+}
+
+/// What's on each occupied hex. Normally a plain hash map, which is the fastest to look things up
+/// in and to copy while the board is small. With the `persistent-hive` feature it's a persistent
+/// map instead, so the copies made while searching share everything that didn't change.
+#[cfg(not(feature = "persistent-hive"))]
+pub type TileMap = FxHashMap<Hex, Tile>;
+#[cfg(feature = "persistent-hive")]
+pub type TileMap = im::HashMap<Hex, Tile, std::hash::BuildHasherDefault<rustc_hash::FxHasher>>;
+
 #[derive(Debug, Clone)]
 pub struct Hive {
-    pub map: FxHashMap<Hex, Tile>,
+    pub map: TileMap,
 }
 
 impl Hive {
     pub fn from_hex_map(hex_map: &FxHashMap<Hex, String>) -> Result<Hive, HiveParseError> {
-        let mut map: FxHashMap<Hex, Tile> = FxHashMap::default();
+        let mut map = TileMap::default();
         for (hex, token) in hex_map {
             if token == "." {
                 continue;
             }
 
-            let bug = token.to_uppercase().parse()?;
-            let token_char = token.chars().next().unwrap();
-            let color = if token_char.is_uppercase() {
-                Color::White
-            } else {
-                Color::Black
-            };
-            map.insert(*hex, Tile { bug, color });
+            map.insert(*hex, token.parse()?);
+        }
+        let hive = Hive { map };
+        hive.validate()?;
+        Ok(hive)
+    }
+
+    /// Check that every tile above the ground sits on top of another tile
+    pub fn validate(&self) -> Result<(), HiveParseError> {
+        let below = |hex: &Hex| Hex {
+            h: hex.h - 1,
+            ..*hex
+        };
+        match self
+            .map
+            .keys()
+            .find(|hex| hex.h > 0 && !self.map.contains_key(&below(hex)))
+        {
+            Some(hex) => Err(HiveParseError::FloatingStack { hex: *hex }),
+            None => Ok(()),
         }
-        Ok(Hive { map })
     }
 
     pub fn to_hex_map(&self) -> FxHashMap<Hex, String> {
@@ -93,6 +146,23 @@ impl Hive {
         height
     }
 
+    /// Every occupied column as its ground level hex and how many pieces are stacked there, in no
+    /// particular order. An empty column has a height of 0 and is left out.
+    pub fn column_heights(&self) -> impl Iterator<Item = (Hex, i32)> {
+        self.map
+            .keys()
+            .filter(|hex| hex.h == 0)
+            .map(|hex| (*hex, self.stack_height(hex)))
+    }
+
+    /// The height of the tallest stack on the board, 0 if the board is empty
+    pub fn tallest_stack(&self) -> i32 {
+        self.column_heights()
+            .map(|(_, height)| height)
+            .max()
+            .unwrap_or(0)
+    }
+
     pub fn toplevel_pieces(&self) -> impl Iterator<Item = (&Hex, &Tile)> {
         self
             .map
@@ -119,6 +189,26 @@ impl Hive {
         }
     }
 
+    /// `top_tile_at` for UI code that works in rows and columns
+    pub fn top_tile_at_rc(&self, row_col: RowCol) -> Option<Tile> {
+        self.top_tile_at(&row_col.into())
+    }
+
+    /// `stack_height` for UI code that works in rows and columns
+    pub fn stack_height_rc(&self, row_col: RowCol) -> i32 {
+        self.stack_height(&row_col.into())
+    }
+
+    /// `topmost_occupied_hex` for UI code that works in rows and columns
+    pub fn topmost_occupied_hex_rc(&self, row_col: RowCol) -> Option<Hex> {
+        self.topmost_occupied_hex(&row_col.into())
+    }
+
+    /// `bottommost_unoccupied_hex` for UI code that works in rows and columns
+    pub fn bottommost_unoccupied_hex_rc(&self, row_col: RowCol) -> Hex {
+        self.bottommost_unoccupied_hex(&row_col.into())
+    }
+
     pub fn stack_at(&self, hex: &Hex) -> impl Iterator<Item = &Tile> {
         let mut topmost_tile = self.map.get(&Hex { h: 0, ..*hex });
         let mut height = 0;
@@ -136,8 +226,11 @@ impl Hive {
         neighbors(hex)
     }
 
+    #[inline]
     pub fn occupied_neighbors_at_same_level(&self, hex: &Hex) -> impl Iterator<Item = Hex> {
-        neighbors(hex).filter(|h| self.map.contains_key(h))
+        neighbor_array(hex)
+            .into_iter()
+            .filter(|h| self.map.contains_key(h))
     }
 
     pub fn topmost_occupied_neighbors(&self, hex: &Hex) -> impl Iterator<Item = Hex> {
@@ -149,21 +242,311 @@ impl Hive {
         neighbors(hex).filter(|neighbor| !self.map.contains_key(neighbor))
     }
 
+    /// A copy of the hive rotated about the origin. Each tile ends up at `hex.rotated_by(degrees)`,
+    /// so hexes in the rotated hive can be found from the original ones without renormalizing.
+    pub fn rotated_by(&self, degrees: RotationDegrees) -> Hive {
+        Hive {
+            map: self
+                .map
+                .iter()
+                .map(|(hex, tile)| (hex.rotated_by(degrees), *tile))
+                .collect(),
+        }
+    }
+
+    /// How many pieces are on the board, counting each piece in a stack
+    pub fn piece_count(&self) -> usize {
+        self.map.len()
+    }
+
+    /// How many pieces of the given color are on the board, counting each piece in a stack
+    pub fn piece_count_for(&self, color: Color) -> usize {
+        self.map.values().filter(|tile| tile.color == color).count()
+    }
+
     pub fn is_occupied(&self, hex: &Hex) -> bool {
         self.map.contains_key(hex)
     }
 
+    /// The first empty ground level hex walking from `hex` in `direction`, i.e. where a
+    /// grasshopper lands. The walk stays on the ground so stacks along the way count as a single
+    /// occupied hex no matter what level `hex` or `direction` are at.
     pub fn next_unoccupied_spot_in_direction(&self, hex: &Hex, direction: &Hex) -> Hex {
-        let mut current: Hex = *hex;
+        let direction = Hex { h: 0, ..*direction };
+        let mut current = Hex { h: 0, ..*hex };
         while self.map.contains_key(&current) {
-            current = current + *direction;
+            current = current + direction;
         }
         current
     }
 
+    /// Every hex holding a piece of the given bug and color, including pieces buried in stacks.
+    ///
+    /// The hexes are sorted by `Hex`'s ordering (q, then r, then h), so the same board always
+    /// produces the same order no matter how the map was built. A game numbers its pieces, the
+    /// `1` in `A1`, in the order they were placed, and only falls back to this order for a board
+    /// with no history, like one from `Game::from_hive`.
+    pub fn pieces_of(&self, bug: Bug, color: Color) -> Vec<Hex> {
+        let mut hexes: Vec<Hex> = self
+            .map
+            .iter()
+            .filter(|(_, tile)| tile.bug == bug && tile.color == color)
+            .map(|(hex, _)| *hex)
+            .collect();
+        hexes.sort();
+        hexes
+    }
+
+    /// The given player's piece of the given bug that's closest to `target` along the ground, or
+    /// `None` if they don't have one on the board. Ties go to the lowest hex in `Hex`'s ordering.
+    pub fn nearest_piece(&self, bug: Bug, color: Color, target: &Hex) -> Option<Hex> {
+        // `min_by_key` keeps the first of equal minimums, and `pieces_of` is sorted
+        self.pieces_of(bug, color)
+            .into_iter()
+            .min_by_key(|hex| flat_distance(hex, target))
+    }
+
+    /// The groups of ground level hexes that are connected to each other. A legal hive always has
+    /// exactly one component. Each component is sorted, and components are ordered by their
+    /// smallest hex.
+    pub fn components(&self) -> Vec<Vec<Hex>> {
+        self.components_without(None)
+    }
+
+    /// The components that would be left if the piece at `hex` were picked up, for checking by
+    /// hand whether moving it really breaks the hive. Picking a piece off a stack leaves the hex
+    /// occupied, so only a piece alone on the ground can split anything. Ordered like
+    /// `components`.
+    pub fn connectivity_after_removing(&self, hex: &Hex) -> Vec<FxHashSet<Hex>> {
+        let removed = (hex.h == 0 && self.stack_height(hex) == 1).then_some(*hex);
+        self.components_without(removed)
+            .into_iter()
+            .map(|component| component.into_iter().collect())
+            .collect()
+    }
+
+    /// The pieces of the given color that can't move at all, either because they're buried under
+    /// another piece or because picking them up would break the hive. Sorted. Checks the hive once
+    /// per piece, so it's too slow to call on every node of a search.
+    pub fn pinned_pieces(&self, color: Color) -> Vec<Hex> {
+        self.map
+            .iter()
+            .filter(|(_, tile)| tile.color == color)
+            .map(|(hex, _)| *hex)
+            .filter(|hex| {
+                let buried = self.stack_height(hex) > hex.h + 1;
+                buried || self.connectivity_after_removing(hex).len() > 1
+            })
+            .sorted()
+            .collect()
+    }
+
+    fn components_without(&self, removed: Option<Hex>) -> Vec<Vec<Hex>> {
+        let mut seen: FxHashSet<Hex> = removed.into_iter().collect();
+        let mut ground_hexes: Vec<Hex> =
+            self.map.keys().filter(|hex| hex.h == 0).copied().collect();
+        ground_hexes.sort();
+
+        let mut components = vec![];
+        for start in ground_hexes {
+            if !seen.insert(start) {
+                continue;
+            }
+            let mut component = vec![start];
+            let mut frontier = vec![start];
+            while let Some(current) = frontier.pop() {
+                for neighbor in self.occupied_neighbors_at_same_level(&current) {
+                    if seen.insert(neighbor) {
+                        component.push(neighbor);
+                        frontier.push(neighbor);
+                    }
+                }
+            }
+            component.sort();
+            components.push(component);
+        }
+        components
+    }
+
+    /// For a hive that has been split into several components, the closest pair of hexes between
+    /// components that would need to be bridged to join everything back together. Returns one
+    /// pair per missing connection, or nothing if the hive is already connected.
+    pub fn nearest_connection_gaps(&self) -> Vec<(Hex, Hex)> {
+        let components = self.components();
+        let closest_pair = |left: &[Hex], right: &[Hex]| {
+            left.iter()
+                .cartesian_product(right.iter())
+                .min_by_key(|(l, r)| flat_distance(l, r))
+                .map(|(l, r)| (*l, *r))
+                .unwrap()
+        };
+
+        // Prim's algorithm over the components, joining the closest unconnected component each step
+        let mut connected = vec![0];
+        let mut gaps = vec![];
+        while connected.len() < components.len() {
+            let (gap, next) = (0..components.len())
+                .filter(|i| !connected.contains(i))
+                .flat_map(|i| connected.iter().map(move |j| (*j, i)))
+                .map(|(j, i)| (closest_pair(&components[j], &components[i]), i))
+                .min_by_key(|((l, r), _)| flat_distance(l, r))
+                .unwrap();
+            connected.push(next);
+            gaps.push(gap);
+        }
+        gaps
+    }
+
+    /// The empty ground level hexes around the outside of the hive, in order walking counter
+    /// clockwise around it. Consecutive hexes are adjacent, and the last hex is adjacent to the
+    /// first, so the outline can be drawn as a closed path. Pockets that are open to the outside
+    /// are followed in and back out, a hex that touches the hive in two separate places shows up
+    /// once for each time the walk passes it. Holes that are completely enclosed by the hive aren't
+    /// part of the outline. If the hive is split only the component with the topmost piece is
+    /// walked.
+    pub fn outline(&self) -> Vec<Hex> {
+        let Some(topmost) = self
+            .map
+            .keys()
+            .filter(|hex| hex.h == 0)
+            .min_by_key(|hex| (hex.r, hex.q))
+        else {
+            return vec![];
+        };
+
+        // Nothing is above the topmost piece, so the hex up and to the left of it is outside the
+        // hive. Walk from there keeping the hive on one side, tracking which neighbor is the
+        // piece being walked along.
+        let start = (
+            neighbor(topmost, &Direction::UpLeft),
+            Direction::DownRight as usize,
+        );
+        let (mut current, mut wall) = start;
+        let mut outline = vec![];
+        loop {
+            outline.push(current);
+            let around = neighbor_array(&current);
+            let step = (1..6)
+                .find(|step| !self.map.contains_key(&around[(wall + step) % 6]))
+                .expect("Hexes on the outside of the hive can't be surrounded");
+            let next = around[(wall + step) % 6];
+            let wall_hex = around[(wall + step - 1) % 6];
+            wall = neighbor_array(&next)
+                .iter()
+                .position(|hex| *hex == wall_hex)
+                .unwrap();
+            current = next;
+            if (current, wall) == start {
+                return outline;
+            }
+        }
+    }
+
+    /// What changed going from this board to `other`. A tile that was removed from one hex and
+    /// added at another is reported as moved rather than as a separate removal and addition. If
+    /// several identical tiles changed, they're paired up in hex order.
+    pub fn diff(&self, other: &Hive) -> HiveDiff {
+        let changed = |from: &Hive, to: &Hive| -> Vec<(Hex, Tile)> {
+            from.map
+                .iter()
+                .filter(|(hex, tile)| to.map.get(hex) != Some(tile))
+                .map(|(hex, tile)| (*hex, *tile))
+                .sorted()
+                .collect()
+        };
+        let mut removed = changed(self, other);
+        let mut added = changed(other, self);
+
+        let mut moved = vec![];
+        removed.retain(|(from, tile)| {
+            match added.iter().position(|(_, added_tile)| added_tile == tile) {
+                Some(index) => {
+                    let (to, _) = added.remove(index);
+                    moved.push((*tile, *from, to));
+                    false
+                }
+                None => true,
+            }
+        });
+        moved.sort();
+
+        HiveDiff {
+            added,
+            removed,
+            moved,
+        }
+    }
+
     pub fn row_col_dimensions(&self) -> RowColDimensions {
         dimensions(self.map.keys())
     }
+
+    /// Render the board as a standalone SVG image. Only the top tile of each stack is drawn,
+    /// stacks taller than one are marked with their height in the corner of the hex.
+    pub fn to_svg(&self) -> String {
+        let mut top_tiles: Vec<(&Hex, &Tile)> = self.toplevel_pieces().collect();
+        top_tiles.sort();
+
+        let half_width = SVG_HEX_SIZE * 3f64.sqrt() / 2.0;
+        let mut min_x = f64::MAX;
+        let mut min_y = f64::MAX;
+        let mut max_x = f64::MIN;
+        let mut max_y = f64::MIN;
+        for (hex, _) in top_tiles.iter() {
+            let (x, y) = RowCol::from_hex(hex).pixel_center(SVG_HEX_SIZE);
+            min_x = min_x.min(x - half_width);
+            max_x = max_x.max(x + half_width);
+            min_y = min_y.min(y - SVG_HEX_SIZE);
+            max_y = max_y.max(y + SVG_HEX_SIZE);
+        }
+        if top_tiles.is_empty() {
+            (min_x, min_y, max_x, max_y) = (0.0, 0.0, 0.0, 0.0);
+        }
+
+        let mut svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{:.1} {:.1} {:.1} {:.1}">"#,
+            min_x,
+            min_y,
+            max_x - min_x,
+            max_y - min_y
+        );
+        svg.push('\n');
+        for (hex, tile) in top_tiles {
+            let row_col = RowCol::from_hex(hex);
+            let (x, y) = row_col.pixel_center(SVG_HEX_SIZE);
+            let points = row_col
+                .pixel_corners(SVG_HEX_SIZE)
+                .iter()
+                .map(|(x, y)| format!("{x:.1},{y:.1}"))
+                .join(" ");
+            let (fill, text_fill) = match tile.color {
+                Color::White => ("#f5f5f5", "#222222"),
+                Color::Black => ("#222222", "#f5f5f5"),
+            };
+            svg.push_str(&format!(
+                r##"<polygon points="{points}" fill="{fill}" stroke="#888888"/>"##
+            ));
+            svg.push('\n');
+            svg.push_str(&format!(
+                r#"<text x="{x:.1}" y="{y:.1}" fill="{text_fill}" font-size="{:.1}" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+                SVG_HEX_SIZE,
+                tile.bug
+            ));
+            svg.push('\n');
+            if hex.h > 0 {
+                svg.push_str(&format!(
+                    r#"<text x="{:.1}" y="{:.1}" fill="{text_fill}" font-size="{:.1}" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+                    x + half_width / 2.0,
+                    y - SVG_HEX_SIZE / 2.0,
+                    SVG_HEX_SIZE / 2.0,
+                    hex.h + 1
+                ));
+                svg.push('\n');
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
 }
 
 impl Display for Hive {
@@ -178,6 +561,41 @@ pub enum HiveParseError {
     InvalidMap(#[from] HexMapParseError),
     #[error("Invalid bug type")]
     InvalidBugType(#[from] BugParseError),
+    #[error("More than one tile at {hex:?}")]
+    DuplicateTile { hex: Hex },
+    #[error("Tile at {hex:?} has nothing underneath it")]
+    FloatingStack { hex: Hex },
+}
+
+/// Builds a hive from explicit coordinates, for tests and tools that would rather not draw the
+/// board as ASCII art
+#[derive(Debug, Default)]
+pub struct HiveBuilder {
+    tiles: Vec<(Hex, Tile)>,
+}
+
+impl HiveBuilder {
+    pub fn new() -> HiveBuilder {
+        HiveBuilder::default()
+    }
+
+    pub fn place(mut self, q: i32, r: i32, h: i32, bug: Bug, color: Color) -> HiveBuilder {
+        self.tiles.push((Hex { q, r, h }, Tile { bug, color }));
+        self
+    }
+
+    /// Fails if two tiles were placed on the same hex, or a tile was placed above an empty hex
+    pub fn build(self) -> Result<Hive, HiveParseError> {
+        let mut map = TileMap::default();
+        for (hex, tile) in self.tiles {
+            if map.insert(hex, tile).is_some() {
+                return Err(HiveParseError::DuplicateTile { hex });
+            }
+        }
+        let hive = Hive { map };
+        hive.validate()?;
+        Ok(hive)
+    }
 }
 
 impl FromStr for Hive {
@@ -188,3 +606,364 @@ impl FromStr for Hive {
         Hive::from_hex_map(&hex_map)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::hex::is_adjacent;
+    use crate::engine::parse::parse_stacked_hex_map_string;
+    use std::collections::BTreeSet;
+
+    fn stacked_hive(map: &str) -> Hive {
+        Hive::from_hex_map(&parse_stacked_hex_map_string(map).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn pieces_of_returns_sorted_hexes() {
+        let hive: Hive = r#"
+        Layer 0
+        A  a  A
+         .  Q  A
+        Layer 1
+        .  .  .
+         .  A  .
+        "#
+        .parse()
+        .unwrap();
+
+        assert_eq!(
+            hive.pieces_of(Bug::Ant, Color::White),
+            vec![
+                Hex { q: 0, r: 0, h: 0 },
+                Hex { q: 1, r: 1, h: 1 },
+                Hex { q: 2, r: 0, h: 0 },
+                Hex { q: 2, r: 1, h: 0 },
+            ]
+        );
+        assert_eq!(
+            hive.pieces_of(Bug::Ant, Color::Black),
+            vec![Hex { q: 1, r: 0, h: 0 }]
+        );
+        assert!(hive.pieces_of(Bug::Spider, Color::White).is_empty());
+    }
+
+    #[test]
+    fn connected_hive_has_no_gaps() {
+        let hive: Hive = r#"
+        .  a  .
+         .  Q  A
+        "#
+        .parse()
+        .unwrap();
+
+        assert_eq!(hive.components().len(), 1);
+        assert!(hive.nearest_connection_gaps().is_empty());
+    }
+
+    #[test]
+    fn removing_a_piece_reports_what_is_left_connected() {
+        let hive = HiveBuilder::new()
+            .place(0, 0, 0, Bug::Queen, Color::White)
+            .place(1, 0, 0, Bug::Ant, Color::Black)
+            .place(2, 0, 0, Bug::Ant, Color::White)
+            .place(2, 0, 1, Bug::Beetle, Color::Black)
+            .build()
+            .unwrap();
+        let hex = |q, h| Hex { q, r: 0, h };
+
+        assert_eq!(
+            hive.connectivity_after_removing(&hex(1, 0)),
+            vec![
+                FxHashSet::from_iter([hex(0, 0)]),
+                FxHashSet::from_iter([hex(2, 0)])
+            ]
+        );
+        assert_eq!(
+            hive.connectivity_after_removing(&hex(0, 0)),
+            vec![FxHashSet::from_iter([hex(1, 0), hex(2, 0)])]
+        );
+        // Lifting the beetle, or trying to pull the ant out from under it, leaves the stack there
+        for hex in [hex(2, 1), hex(2, 0)] {
+            assert_eq!(hive.connectivity_after_removing(&hex).len(), 1);
+        }
+    }
+
+    #[test]
+    fn finds_closest_gaps_between_components() {
+        let hive: Hive = r#"
+        A  .  Q  .  .  .  .  q
+         .  .  .  .  .  .  .  .
+        .  .  .  .  a  .  .  .
+        "#
+        .parse()
+        .unwrap();
+
+        assert_eq!(hive.components().len(), 4);
+        let gaps = hive.nearest_connection_gaps();
+        assert_eq!(gaps.len(), 3);
+        assert!(gaps.contains(&(Hex { q: 0, r: 0, h: 0 }, Hex { q: 2, r: 0, h: 0 })));
+        assert!(gaps.contains(&(Hex { q: 2, r: 0, h: 0 }, Hex { q: 3, r: 2, h: 0 })));
+        assert!(gaps.contains(&(Hex { q: 3, r: 2, h: 0 }, Hex { q: 7, r: 0, h: 0 })));
+    }
+
+    #[test]
+    fn svg_has_one_hex_per_stack() {
+        let hive: Hive = r#"
+        Layer 0
+        .  a  .
+         .  Q  B
+        Layer 1
+        .  .  .
+         .  b  .
+        "#
+        .parse()
+        .unwrap();
+
+        let svg = hive.to_svg();
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<polygon").count(), 3);
+        // The beetle on top of the queen hides it and is labelled with the stack height
+        assert!(!svg.contains(">Q<"));
+        assert_eq!(svg.matches(">B<").count(), 2);
+        assert!(svg.contains(">2<"));
+    }
+
+    #[test]
+    fn svg_view_box_fits_board() {
+        let hive: Hive = r#"
+        Q
+        "#
+        .parse()
+        .unwrap();
+
+        let svg = hive.to_svg();
+        assert!(svg.contains(r#"viewBox="-17.3 -20.0 34.6 40.0""#));
+    }
+
+    #[test]
+    fn outline_walks_around_a_single_piece() {
+        let hive: Hive = "Q".parse().unwrap();
+        let outline = hive.outline();
+
+        assert_eq!(outline.len(), 6);
+        assert_eq!(
+            outline.iter().copied().collect::<FxHashSet<_>>(),
+            neighbors(&Hex::default()).collect()
+        );
+    }
+
+    #[test]
+    fn outline_skips_enclosed_holes() {
+        let hive: Hive = r#"
+        .  A  A
+         A  .  A
+        .  A  A
+        "#
+        .parse()
+        .unwrap();
+        let hole = hive
+            .map
+            .keys()
+            .flat_map(neighbors)
+            .find(|hex| !hive.is_occupied(hex) && hive.unoccupied_neighbors(hex).count() == 0)
+            .unwrap();
+        let outline = hive.outline();
+
+        assert_eq!(outline.len(), 12);
+        assert!(!outline.contains(&hole));
+        assert!(outline.iter().all(|hex| !hive.is_occupied(hex)));
+        assert!(
+            outline
+                .iter()
+                .all(|hex| hive.occupied_neighbors_at_same_level(hex).count() > 0)
+        );
+        assert!(
+            outline
+                .iter()
+                .circular_tuple_windows()
+                .all(|(a, b)| is_adjacent(a, b))
+        );
+    }
+
+    #[test]
+    fn builder_places_tiles_at_coordinates() {
+        let hive = HiveBuilder::new()
+            .place(0, 0, 0, Bug::Queen, Color::White)
+            .place(1, 0, 0, Bug::Pillbug, Color::Black)
+            .place(1, 0, 1, Bug::Beetle, Color::White)
+            .build()
+            .unwrap();
+
+        assert_eq!(hive.map.len(), 3);
+        assert_eq!(hive.stack_height(&Hex { q: 1, r: 0, h: 0 }), 2);
+        assert_eq!(
+            hive.top_tile_at(&Hex { q: 1, r: 0, h: 0 }),
+            Some(Tile {
+                bug: Bug::Beetle,
+                color: Color::White
+            })
+        );
+    }
+
+    #[test]
+    fn reports_column_heights() {
+        let hive = stacked_hive("Qbb  a  Gb");
+        let heights: BTreeSet<(Hex, i32)> = hive.column_heights().collect();
+        assert_eq!(
+            heights,
+            BTreeSet::from([
+                (Hex { q: 0, r: 0, h: 0 }, 3),
+                (Hex { q: 1, r: 0, h: 0 }, 1),
+                (Hex { q: 2, r: 0, h: 0 }, 2),
+            ])
+        );
+        assert_eq!(hive.tallest_stack(), 3);
+
+        let empty: Hive = ".  .".parse().unwrap();
+        assert_eq!(empty.column_heights().count(), 0);
+        assert_eq!(empty.tallest_stack(), 0);
+    }
+
+    #[test]
+    fn finds_the_nearest_piece() {
+        let hive: Hive = "A  a  Q  q  A  a  A".parse().unwrap();
+        let black_queen = Hex { q: 3, r: 0, h: 0 };
+        assert_eq!(
+            hive.nearest_piece(Bug::Ant, Color::White, &black_queen),
+            Some(Hex { q: 4, r: 0, h: 0 })
+        );
+        // The first two white ants are both two away from the white queen, so the lower hex wins
+        let white_queen = Hex { q: 2, r: 0, h: 0 };
+        assert_eq!(
+            hive.nearest_piece(Bug::Ant, Color::White, &white_queen),
+            Some(Hex { q: 0, r: 0, h: 0 })
+        );
+        assert_eq!(
+            hive.nearest_piece(Bug::Beetle, Color::White, &white_queen),
+            None
+        );
+    }
+
+    #[test]
+    fn finds_pinned_pieces() {
+        let hive = stacked_hive("Ab  G  a");
+        // The ant is buried and the grasshopper holds the hive together
+        assert_eq!(
+            hive.pinned_pieces(Color::White),
+            vec![Hex { q: 0, r: 0, h: 0 }, Hex { q: 1, r: 0, h: 0 }]
+        );
+        assert_eq!(hive.pinned_pieces(Color::Black), vec![]);
+    }
+
+    #[test]
+    fn builder_rejects_invalid_stacks() {
+        let duplicate = HiveBuilder::new()
+            .place(0, 0, 0, Bug::Queen, Color::White)
+            .place(0, 0, 0, Bug::Ant, Color::Black)
+            .build();
+        assert!(matches!(
+            duplicate,
+            Err(HiveParseError::DuplicateTile {
+                hex: Hex { q: 0, r: 0, h: 0 }
+            })
+        ));
+
+        let floating = HiveBuilder::new()
+            .place(0, 0, 0, Bug::Queen, Color::White)
+            .place(1, 0, 1, Bug::Beetle, Color::Black)
+            .build();
+        assert!(matches!(
+            floating,
+            Err(HiveParseError::FloatingStack {
+                hex: Hex { q: 1, r: 0, h: 1 }
+            })
+        ));
+    }
+
+    #[test]
+    fn parsing_rejects_floating_stacks() {
+        let result: Result<Hive, _> = r#"
+        Layer 0
+        Q  .
+        Layer 1
+        .  B
+        "#
+        .parse();
+
+        assert!(matches!(
+            result,
+            Err(HiveParseError::FloatingStack {
+                hex: Hex { h: 1, .. }
+            })
+        ));
+    }
+
+    #[test]
+    fn piece_counts_include_stacked_pieces() {
+        let hive: Hive = r#"
+        Layer 0
+        A  a  Q
+        Layer 1
+        .  B  .
+        "#
+        .parse()
+        .unwrap();
+
+        assert_eq!(hive.piece_count(), 4);
+        assert_eq!(hive.piece_count_for(Color::White), 3);
+        assert_eq!(hive.piece_count_for(Color::Black), 1);
+    }
+
+    #[test]
+    fn diff_reports_moves_instead_of_add_and_remove() {
+        let before = HiveBuilder::new()
+            .place(0, 0, 0, Bug::Ant, Color::White)
+            .place(1, 0, 0, Bug::Queen, Color::Black)
+            .place(1, 0, 1, Bug::Beetle, Color::White)
+            .build()
+            .unwrap();
+        let after = HiveBuilder::new()
+            .place(1, 0, 0, Bug::Queen, Color::Black)
+            .place(2, 0, 0, Bug::Ant, Color::White)
+            .build()
+            .unwrap();
+        let ant = Tile {
+            bug: Bug::Ant,
+            color: Color::White,
+        };
+        let beetle = Tile {
+            bug: Bug::Beetle,
+            color: Color::White,
+        };
+        let from = before.pieces_of(Bug::Ant, Color::White)[0];
+        let to = after.pieces_of(Bug::Ant, Color::White)[0];
+        let beetle_hex = before.pieces_of(Bug::Beetle, Color::White)[0];
+
+        let diff = before.diff(&after);
+        assert_eq!(diff.moved, vec![(ant, from, to)]);
+        assert_eq!(diff.removed, vec![(beetle_hex, beetle)]);
+        assert!(diff.added.is_empty());
+        assert_eq!(after.diff(&before).added, vec![(beetle_hex, beetle)]);
+        assert!(before.diff(&before).is_empty());
+    }
+
+    #[test]
+    fn walks_to_unoccupied_spot_on_the_ground() {
+        let hive: Hive = r#"
+        Layer 0
+        A  a  Q
+        Layer 1
+        B  b  .
+        "#
+        .parse()
+        .unwrap();
+        let start = hive.pieces_of(Bug::Beetle, Color::White)[0];
+        let direction = hive.pieces_of(Bug::Ant, Color::Black)[0]
+            - hive.pieces_of(Bug::Ant, Color::White)[0];
+
+        let spot = hive.next_unoccupied_spot_in_direction(&start, &direction);
+        assert_eq!(spot.h, 0);
+        assert!(!hive.is_occupied(&spot));
+        assert!(is_adjacent(&spot, &hive.pieces_of(Bug::Queen, Color::White)[0]));
+    }
+}
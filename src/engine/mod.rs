@@ -1,5 +1,6 @@
 pub mod ai;
 pub mod bug;
+mod canonicalizer;
 pub mod game;
 pub mod hex;
 pub mod hive;
@@ -7,7 +8,5 @@ pub mod parse;
 mod pathfinding;
 pub mod row_col;
 pub mod save_game;
+pub mod uhp;
 mod zobrist;
-
-#[cfg(test)]
-mod canonicalizer;
@@ -1,5 +1,7 @@
 use crate::engine::hex::Hex;
-use crate::engine::parse::HexMapParseError::{InvalidHexContents, MissingLayerNumber};
+use crate::engine::parse::HexMapParseError::{
+    EmptyStackLevel, InvalidHexContents, MissingLayerNumber,
+};
 use crate::engine::row_col;
 use crate::engine::row_col::RowCol;
 use itertools::Itertools;
@@ -15,30 +17,74 @@ pub enum HexMapParseError {
     MissingLayerNumber,
     #[error("Hex contents can only be a single character, got: {contents}")]
     InvalidHexContents { contents: String },
+    #[error("A stack can't have an empty level in it, got: {contents}")]
+    EmptyStackLevel { contents: String },
 }
 
-pub fn parse_hex_map_string(s: &str) -> Result<FxHashMap<Hex, String>, HexMapParseError> {
-    let mut map: FxHashMap<Hex, String> = FxHashMap::default();
+/// How many columns a tab in the indentation of a hex map counts for
+const TAB_WIDTH: usize = 4;
+
+/// Whether a line of a hex map is a comment, i.e. its first non-whitespace character is `#`
+fn is_comment(line: &str) -> bool {
+    line.trim_start().starts_with('#')
+}
 
-    let rows = s.split("\n").map(|row| row.split_whitespace());
-
-    let mut starting_row_num = 0;
-    let first_two_lines: Vec<&str> = s
-        .split("\n")
-        .filter(|row| !row.is_empty() && !row.contains("Layer"))
-        .take(2)
-        .collect();
-    if first_two_lines.len() >= 2 {
-        let first_line_whitespace_count = first_two_lines[0]
-            .chars()
-            .find_position(|char| !char::is_whitespace(*char));
-        let second_line_whitespace_count = first_two_lines[1]
-            .chars()
-            .find_position(|char| !char::is_whitespace(*char));
-        if first_line_whitespace_count > second_line_whitespace_count {
-            starting_row_num = 1;
+/// How far a line is indented, with a tab reaching the next multiple of `TAB_WIDTH` columns
+fn indentation(line: &str) -> usize {
+    line.chars()
+        .take_while(|char| char.is_whitespace())
+        .fold(0, |width, char| match char {
+            '\t' => (width / TAB_WIDTH + 1) * TAB_WIDTH,
+            _ => width + 1,
+        })
+}
+
+/// Whether the first row of each layer is one of the odd rows, which are shifted half a hex to
+/// the right. Rows alternate between shifted and not, so whichever half of the first layer's rows
+/// is the least indented is taken to be the even rows, and the other layers are assumed to line up
+/// the same way. Going by every row instead of just the first two means a stray space at the start
+/// of a row, or a mix of tabs and spaces, doesn't shift the whole board.
+fn starts_on_odd_row<'a>(lines: impl Iterator<Item = &'a str>) -> bool {
+    let mut least_indented = [usize::MAX; 2];
+    let mut row_num = 0;
+    for line in lines {
+        if line.contains("Layer") {
+            if row_num > 0 {
+                break;
+            }
+        } else if !line.trim().is_empty() {
+            let parity = row_num % 2;
+            least_indented[parity] = least_indented[parity].min(indentation(line));
+            row_num += 1;
         }
     }
+    least_indented[1] != usize::MAX && least_indented[0] > least_indented[1]
+}
+
+/// Parse a hex map string into the contents of each hex. Lines starting with `#`, after any
+/// indentation, are comments and are skipped entirely, as are blank lines. Neither counts as a
+/// row, so they can go anywhere, including between layers.
+///
+/// Hexes are numbered by their position in the row, so only whether a row is indented more or
+/// less than the rows around it matters, not by how much. Tabs count as `TAB_WIDTH` columns.
+pub fn parse_hex_map_string(s: &str) -> Result<FxHashMap<Hex, String>, HexMapParseError> {
+    parse(s, false)
+}
+
+/// Like `parse_hex_map_string`, but a token longer than one character is a whole stack, listed
+/// from the bottom up starting at the current layer, so `Qb` on layer 0 is a white queen with a
+/// black beetle on top of it. Stacks and layers can be mixed in the same map.
+pub fn parse_stacked_hex_map_string(s: &str) -> Result<FxHashMap<Hex, String>, HexMapParseError> {
+    parse(s, true)
+}
+
+fn parse(s: &str, inline_stacks: bool) -> Result<FxHashMap<Hex, String>, HexMapParseError> {
+    let mut map: FxHashMap<Hex, String> = FxHashMap::default();
+
+    let lines = || s.split("\n").filter(|line| !is_comment(line));
+    let rows = lines().map(|row| row.split_whitespace());
+
+    let starting_row_num = starts_on_odd_row(lines()) as i32;
 
     let mut height = 0;
     let mut row_num = 0;
@@ -56,15 +102,22 @@ pub fn parse_hex_map_string(s: &str) -> Result<FxHashMap<Hex, String>, HexMapPar
                 "." => {
                     should_increment_row = true;
                 }
-                token if token.chars().count() == 1 => {
+                token if inline_stacks || token.chars().count() == 1 => {
+                    if token.contains('.') {
+                        return Err(EmptyStackLevel {
+                            contents: token.to_string(),
+                        });
+                    }
                     should_increment_row = true;
-                    let hex = RowCol {
-                        row: row_num,
-                        col: col_num,
-                        height,
+                    for (level, char) in token.chars().enumerate() {
+                        let hex = RowCol {
+                            row: row_num,
+                            col: col_num,
+                            height: height + level as i32,
+                        }
+                        .to_hex();
+                        map.insert(hex, char.to_string());
                     }
-                    .to_hex();
-                    map.insert(hex, token.to_string());
                 }
                 contents => {
                     return Err(InvalidHexContents {
@@ -82,6 +135,16 @@ pub fn parse_hex_map_string(s: &str) -> Result<FxHashMap<Hex, String>, HexMapPar
 }
 
 pub fn hex_map_to_string(hex_map: &FxHashMap<Hex, String>) -> String {
+    hex_map_to_highlighted_string(hex_map, &[])
+}
+
+/// Like `hex_map_to_string`, but with the `highlighted` hexes in square brackets, i.e. `[Q]`, for
+/// pointing things out in plain text. Empty hexes can be highlighted too, as `[.]`, as long as
+/// they're within the area the map covers.
+pub fn hex_map_to_highlighted_string(
+    hex_map: &FxHashMap<Hex, String>,
+    highlighted: &[Hex],
+) -> String {
     if hex_map.is_empty() {
         return "<empty>".to_owned();
     }
@@ -101,10 +164,13 @@ pub fn hex_map_to_string(hex_map: &FxHashMap<Hex, String>) -> String {
             }
             for col in dimensions.col_min..=dimensions.col_max {
                 let default = ".".to_string();
-                let token = hex_map
-                    .get(&RowCol { row, col, height }.to_hex())
-                    .unwrap_or(&default);
-                map_str.push_str(&format!(" {} ", token));
+                let hex = RowCol { row, col, height }.to_hex();
+                let token = hex_map.get(&hex).unwrap_or(&default);
+                if highlighted.contains(&hex) {
+                    map_str.push_str(&format!("[{}]", token));
+                } else {
+                    map_str.push_str(&format!(" {} ", token));
+                }
             }
             map_str.push('\n')
         }
@@ -113,6 +179,47 @@ pub fn hex_map_to_string(hex_map: &FxHashMap<Hex, String>) -> String {
     map_str
 }
 
+/// Like `hex_map_to_string`, but with each stack written as one token from the bottom up, the way
+/// `parse_stacked_hex_map_string` reads them, instead of one layer at a time. Stacks are assumed
+/// not to have gaps in them.
+pub fn hex_map_to_stacked_string(hex_map: &FxHashMap<Hex, String>) -> String {
+    if hex_map.is_empty() {
+        return "<empty>".to_owned();
+    }
+
+    let mut stacks: FxHashMap<Hex, String> = FxHashMap::default();
+    for (hex, token) in hex_map.iter().sorted_by_key(|(hex, _)| hex.h) {
+        stacks.entry(hex.base_level()).or_default().push_str(token);
+    }
+    let width = stacks
+        .values()
+        .map(|stack| stack.chars().count())
+        .max()
+        .unwrap_or(1);
+    let dimensions = row_col::dimensions(stacks.keys());
+
+    let mut map_str = String::new();
+    for row in dimensions.row_min..=dimensions.row_max {
+        // Odd rows are shifted half a column, see hex_map_to_highlighted_string
+        if row & 1 == 1 {
+            map_str.push_str(&" ".repeat(width.div_ceil(2)))
+        }
+        for col in dimensions.col_min..=dimensions.col_max {
+            let hex = RowCol {
+                row,
+                col,
+                height: 0,
+            }
+            .to_hex();
+            let token = stacks.get(&hex).map_or(".", String::as_str);
+            map_str.push_str(&format!(" {token:<width$} "));
+        }
+        map_str.push('\n')
+    }
+
+    map_str
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -195,6 +302,142 @@ mod test {
         )
     }
 
+    #[test]
+    fn skips_comments_and_blank_lines() {
+        let commented_map = r#"
+        # The queen is about to be surrounded
+        Layer 0
+        .  a  .
+
+        # Comments can have any number of tokens in them
+         m  Q  r
+        .  .  .
+
+
+        # Layer 1 is just the beetles
+        Layer 1
+            # Even indented ones
+        .  B  .
+         .  b  .
+        .  .  .
+        "#;
+        let map = r#"
+        Layer 0
+        .  a  .
+         m  Q  r
+        .  .  .
+        Layer 1
+        .  B  .
+         .  b  .
+        .  .  .
+        "#;
+
+        assert_eq!(
+            parse_hex_map_string(map).unwrap(),
+            parse_hex_map_string(commented_map).unwrap()
+        );
+    }
+
+    #[test]
+    fn parses_stacks_written_inline() {
+        let layers = r#"
+        Layer 0
+        .  a  .
+         m  Q  r
+        .  .  .
+        Layer 1
+        .  B  .
+         .  b  .
+        .  .  .
+        Layer 2
+        .  .  .
+         .  B  .
+        .  .  .
+        "#;
+        let stacks = r#"
+        .   aB   .
+          m   QbB  r
+        .   .    .
+        "#;
+        let mixed = r#"
+        Layer 0
+        .  aB  .
+         m  Q  r
+        Layer 1
+        .  .  .
+         .  bB  .
+        "#;
+
+        let expected = parse_hex_map_string(layers).unwrap();
+        assert_eq!(parse_stacked_hex_map_string(layers).unwrap(), expected);
+        assert_eq!(parse_stacked_hex_map_string(stacks).unwrap(), expected);
+        assert_eq!(parse_stacked_hex_map_string(mixed).unwrap(), expected);
+
+        let written = hex_map_to_stacked_string(&expected);
+        assert_eq!(parse_stacked_hex_map_string(&written).unwrap(), expected);
+        assert!(matches!(
+            parse_stacked_hex_map_string("Q.b"),
+            Err(EmptyStackLevel { .. })
+        ));
+
+        // Only the stacked parse reads them, the default one still wants a character per hex
+        assert!(matches!(
+            parse_hex_map_string(stacks),
+            Err(InvalidHexContents { .. })
+        ));
+    }
+
+    #[test]
+    fn tabs_and_stray_spaces_do_not_shift_rows() {
+        let spaces = "
+        Layer 0
+        .  a  .
+         m  Q  r
+        .  .  .
+        Layer 1
+        .  B  .
+         .  b  .
+        ";
+        let tabs = "
+\tLayer 0
+\t.\ta\t.
+\t m\tQ\tr
+\t.\t.\t.
+\tLayer 1
+\t.\tB\t.
+\t .\tb\t.
+";
+        let mixed = "
+        Layer 0
+        .  a  .
+\t\t m  Q  r
+          .  .  .
+        Layer 1
+        .  B  .
+\t\t .  b  .
+        ";
+        let stray_first_space = "
+          .  a  .
+         m  Q  r
+        .  .  .
+        ";
+
+        let expected = parse_hex_map_string(spaces).unwrap();
+        assert_eq!(parse_hex_map_string(tabs).unwrap(), expected);
+        assert_eq!(parse_hex_map_string(mixed).unwrap(), expected);
+        assert_eq!(
+            parse_hex_map_string(stray_first_space).unwrap(),
+            parse_hex_map_string(
+                "
+        .  a  .
+         m  Q  r
+        .  .  .
+        "
+            )
+            .unwrap()
+        );
+    }
+
     proptest! {
         #[test]
         fn parse_doesnt_crash(s in r"[\PC*]") {
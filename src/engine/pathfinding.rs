@@ -52,10 +52,13 @@ pub fn move_would_break_hive(hive: &Hive, from: &Hex, to: &Hex) -> bool {
         return true;
     }
 
-    let mut connected_pieces = FxHashSet::default();
+    // A piece with at most one neighbor is at the edge of the hive, removing it can't split it
     let mut neighbors = hive.occupied_neighbors_at_same_level(from);
-    let first = neighbors.next().unwrap();
+    let Some(first) = neighbors.next() else {
+        return false;
+    };
 
+    let mut connected_pieces = FxHashSet::default();
     neighbors.any(|neighbor| {
         !pieces_are_connected(hive, &neighbor, &first, from, &mut connected_pieces).unwrap()
     })
@@ -121,3 +124,63 @@ fn pieces_are_connected(
     }
     Ok(false)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn two_piece_hive_only_breaks_when_sliding_away() {
+        let hive: Hive = r#"
+        Q  a
+        "#
+        .parse()
+        .unwrap();
+        let queen = Hex { q: 0, r: 0, h: 0 };
+
+        let around_ant = Hex { q: 1, r: -1, h: 0 };
+        let away_from_ant = Hex { q: 0, r: -1, h: 0 };
+        let above_queen = Hex { h: 100, ..queen };
+
+        assert!(!move_would_break_hive(&hive, &queen, &around_ant));
+        assert!(move_would_break_hive(&hive, &queen, &away_from_ant));
+        assert!(!move_would_break_hive(&hive, &queen, &above_queen));
+    }
+
+    #[test]
+    fn breaking_the_hive_agrees_with_remaining_components() {
+        let hive: Hive = r#"
+        .  .  .  A  .  .
+         .  s  b  a  .  .
+        .  G  Q  B  .  .
+         .  m  q  g  S  .
+        .  .  L  P  .  .
+         .  .  M  p  .  .
+        "#
+        .parse()
+        .unwrap();
+        let far_away = Hex { q: 100, r: 0, h: 0 };
+
+        for hex in hive.map.keys() {
+            let breaks = hive.connectivity_after_removing(hex).len() > 1;
+            assert_eq!(
+                move_would_break_hive(&hive, hex, &far_away),
+                breaks,
+                "{hex:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn isolated_piece_does_not_panic() {
+        let hive: Hive = r#"
+        Q
+        "#
+        .parse()
+        .unwrap();
+        let queen = Hex { q: 0, r: 0, h: 0 };
+        let above_queen = Hex { h: 100, ..queen };
+
+        assert!(!move_would_break_hive(&hive, &queen, &above_queen));
+    }
+}
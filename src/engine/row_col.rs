@@ -1,6 +1,8 @@
 use std::cmp::{max, min};
 use crate::engine::hex::Hex;
 
+const SQRT_3: f64 = 1.732_050_807_568_877_2;
+
 #[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Default, Copy, Clone, Hash)]
 pub struct RowCol {
     pub row: i32,
@@ -20,6 +22,25 @@ impl RowCol {
         }
     }
 
+    /// The center of this hex in a pointy-top layout where each hex has a corner radius of
+    /// `size`. Odd rows are shifted half a hex to the right, the same way the board is printed.
+    pub fn pixel_center(&self, size: f64) -> (f64, f64) {
+        let odd_row_offset = if self.row & 1 == 1 { 0.5 } else { 0.0 };
+        let x = size * SQRT_3 * (self.col as f64 + odd_row_offset);
+        let y = size * 1.5 * self.row as f64;
+        (x, y)
+    }
+
+    /// The six corners of this hex in a pointy-top layout, starting from the top and going
+    /// clockwise
+    pub fn pixel_corners(&self, size: f64) -> [(f64, f64); 6] {
+        let (x, y) = self.pixel_center(size);
+        std::array::from_fn(|i| {
+            let angle = (60.0 * i as f64 - 90.0).to_radians();
+            (x + size * angle.cos(), y + size * angle.sin())
+        })
+    }
+
     pub fn from_hex(hex: &Hex) -> RowCol {
         let parity = hex.r & 1;
         let col = hex.q + (hex.r - parity) / 2;
@@ -77,4 +98,27 @@ pub fn dimensions<'a>(hexes: impl Iterator<Item=&'a Hex>) -> RowColDimensions {
             height_max: max(dims.height_max, oddr.height),
         }
     })
+}
+
+/// The smallest box containing all of `hexes`, or `None` if there are none. Unlike `dimensions`,
+/// the box doesn't have to reach the origin.
+pub fn bounding_box<'a>(hexes: impl Iterator<Item=&'a Hex>) -> Option<RowColDimensions> {
+    hexes.map(RowCol::from_hex).fold(None, |dims, oddr| {
+        let dims = dims.unwrap_or(RowColDimensions {
+            row_min: oddr.row,
+            row_max: oddr.row,
+            col_min: oddr.col,
+            col_max: oddr.col,
+            height_min: oddr.height,
+            height_max: oddr.height,
+        });
+        Some(RowColDimensions {
+            row_min: min(dims.row_min, oddr.row),
+            row_max: max(dims.row_max, oddr.row),
+            col_min: min(dims.col_min, oddr.col),
+            col_max: max(dims.col_max, oddr.col),
+            height_min: min(dims.height_min, oddr.height),
+            height_max: max(dims.height_max, oddr.height),
+        })
+    })
 }
\ No newline at end of file
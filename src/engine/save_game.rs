@@ -1,9 +1,16 @@
-use crate::engine::game::Game;
-use crate::engine::hive::{Color, Hive, HiveParseError};
+use crate::engine::game::{Game, GameResult, PositionError};
+use crate::engine::hex::Hex;
+use crate::engine::hive::{Color, Hive, HiveParseError, Tile};
+use crate::engine::uhp::UhpError;
+use humantime::{format_duration, parse_duration};
+use itertools::Itertools;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 use std::fs::{self, File};
 use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 
 /// Custom error type for save/load operations
@@ -27,13 +34,65 @@ pub enum SaveGameError {
     #[error("Failed to parse active player: {0}")]
     ParseColorError(String),
 
+    #[error("Failed to parse clock: {0}")]
+    ParseClockError(String),
+
+    #[error("Failed to parse piece numbers: {0}")]
+    ParsePieceNumbersError(String),
+
     #[error("Failed to parse game")]
     ParseGameError(#[from] HiveParseError),
+
+    #[error("Failed to parse UHP game string")]
+    ParseUhpError(#[from] UhpError),
+
+    #[error("Saved position isn't valid: {0}")]
+    InvalidPosition(#[from] PositionError),
+
+    #[error("Unrecognized save format, tried: {0}")]
+    UnknownFormat(String),
+
+    #[error("Failed to parse verified replay: {0}")]
+    ParseReplayError(#[from] toml::de::Error),
+
+    #[error("Failed to write verified replay: {0}")]
+    WriteReplayError(#[from] toml::ser::Error),
+
+    #[error(
+        "Replay doesn't match at ply {ply} ({move_string}): recorded position {expected}, \
+        replayed position {actual}"
+    )]
+    ReplayMismatch {
+        ply: usize,
+        move_string: String,
+        expected: String,
+        actual: String,
+    },
 }
 
-pub fn save_game(game: &Game, directory_path: impl AsRef<Path>) -> Result<PathBuf, SaveGameError> {
-    let dir_path = directory_path.as_ref();
+const WHITE_CLOCK_PREFIX: &str = "WhiteClock:";
+const BLACK_CLOCK_PREFIX: &str = "BlackClock:";
+const PIECE_NUMBERS_PREFIX: &str = "PieceNumbers:";
+const RESIGNED_PREFIX: &str = "Resigned:";
+
+/// A transcript where each move carries the `Game::canonical_key` of the position it leads to, so
+/// whoever loads it can check it still plays out the same way
+#[derive(Debug, Serialize, Deserialize)]
+struct VerifiedReplay {
+    game_type: String,
+    moves: Vec<VerifiedMove>,
+}
 
+#[derive(Debug, Serialize, Deserialize)]
+struct VerifiedMove {
+    #[serde(rename = "move")]
+    move_string: String,
+    /// The canonical key after the move, as hex since TOML integers are signed
+    position: String,
+}
+
+/// Pick a file name in `dir_path` that isn't taken yet, creating the directory if needed
+fn new_save_path(dir_path: &Path) -> Result<PathBuf, SaveGameError> {
     // Ensure directory exists
     fs::create_dir_all(dir_path)
         .map_err(|e| SaveGameError::CreateDirError(dir_path.display().to_string(), e))?;
@@ -49,27 +108,210 @@ pub fn save_game(game: &Game, directory_path: impl AsRef<Path>) -> Result<PathBu
         file_path = dir_path.join(&filename);
         counter += 1;
     }
+    Ok(file_path)
+}
+
+fn write_save_file(path: &Path, contents: &str) -> Result<(), SaveGameError> {
+    File::create(path)
+        .map_err(|e| SaveGameError::CreateFileError(path.display().to_string(), e))?
+        .write_all(contents.as_bytes())
+        .map_err(|e| SaveGameError::WriteFileError(path.display().to_string(), e))
+}
 
-    // Write file: first line = active player, rest = game state
-    let mut file = File::create(&file_path)
-        .map_err(|e| SaveGameError::CreateFileError(file_path.display().to_string(), e))?;
-    let contents = format!("ActivePlayer: {}\n{}", game.active_player, game.hive);
-    file.write_all(contents.as_bytes())
-        .map_err(|e| SaveGameError::WriteFileError(file_path.display().to_string(), e))?;
+pub fn save_game(game: &Game, directory_path: impl AsRef<Path>) -> Result<PathBuf, SaveGameError> {
+    let file_path = new_save_path(directory_path.as_ref())?;
+    write_save_file(&file_path, &full_state_string(game))?;
 
     Ok(file_path)
 }
 
+/// The contents `save_game` writes
+pub fn full_state_string(game: &Game) -> String {
+    // First line = active player, then any clocks and resignation, rest = game state
+    let mut contents = format!("ActivePlayer: {}\n", game.active_player);
+    if let Some(clock) = game.white_clock {
+        contents.push_str(&format!(
+            "{WHITE_CLOCK_PREFIX} {}\n",
+            format_duration(clock)
+        ));
+    }
+    if let Some(clock) = game.black_clock {
+        contents.push_str(&format!(
+            "{BLACK_CLOCK_PREFIX} {}\n",
+            format_duration(clock)
+        ));
+    }
+    if let Some(color) = game.resigned {
+        contents.push_str(&format!("{RESIGNED_PREFIX} {color}\n"));
+    }
+    contents.push_str(&format!(
+        "{PIECE_NUMBERS_PREFIX} {}\n",
+        format_piece_numbers(game)
+    ));
+    contents.push_str(&game.hive().to_string());
+    contents
+}
+
+/// Save a game as its UHP moves, each with the canonical key of the position it leads to, for
+/// exchanging transcripts that can be checked on the way back in. See `load_game_verified`.
+/// Fails if the moves aren't a legal game of `game_type`.
+pub fn save_game_verified(
+    game_type: &str,
+    moves: &[&str],
+    directory_path: impl AsRef<Path>,
+) -> Result<PathBuf, SaveGameError> {
+    let contents = verified_replay_string(game_type, moves)?;
+    let file_path = new_save_path(directory_path.as_ref())?;
+    write_save_file(&file_path, &contents)?;
+    Ok(file_path)
+}
+
+/// Load a game saved by `save_game_verified`, replaying every move and checking that it leads to
+/// the recorded position. A mismatch means the transcript was edited or mistyped, or that move
+/// generation has changed since it was written, and is reported at the first ply it happens.
+pub fn load_game_verified(file_path: impl AsRef<Path>) -> Result<Game, SaveGameError> {
+    parse_verified_replay(&read_save_file(file_path.as_ref())?)
+}
+
+/// The contents `save_game_verified` writes
+pub fn verified_replay_string(game_type: &str, moves: &[&str]) -> Result<String, SaveGameError> {
+    let mut game = Game::from_uhp(game_type, &[])?;
+    let mut replay = VerifiedReplay {
+        game_type: game_type.to_string(),
+        moves: vec![],
+    };
+    for (i, move_string) in moves.iter().enumerate() {
+        game = apply_ply(&game, i + 1, move_string)?;
+        replay.moves.push(VerifiedMove {
+            move_string: move_string.to_string(),
+            position: format_position_key(&game),
+        });
+    }
+    Ok(toml::to_string(&replay)?)
+}
+
+/// Parse and check the contents of a file written by `save_game_verified`
+pub fn parse_verified_replay(contents: &str) -> Result<Game, SaveGameError> {
+    let replay: VerifiedReplay = toml::from_str(contents)?;
+    let mut game = Game::from_uhp(&replay.game_type, &[])?;
+    for (i, recorded) in replay.moves.iter().enumerate() {
+        game = apply_ply(&game, i + 1, &recorded.move_string)?;
+        let actual = format_position_key(&game);
+        if actual != recorded.position {
+            return Err(SaveGameError::ReplayMismatch {
+                ply: i + 1,
+                move_string: recorded.move_string.clone(),
+                expected: recorded.position.clone(),
+                actual,
+            });
+        }
+    }
+    Ok(game)
+}
+
+fn apply_ply(game: &Game, ply: usize, move_string: &str) -> Result<Game, SaveGameError> {
+    game.apply_move_string(move_string)
+        .map_err(|e| e.on_turn(ply).into())
+}
+
+fn format_position_key(game: &Game) -> String {
+    format!("{:016x}", game.canonical_key())
+}
+
 pub fn load_game(file_path: impl AsRef<Path>) -> Result<Game, SaveGameError> {
-    let path = file_path.as_ref();
-    let mut contents = String::new();
+    let contents = read_save_file(file_path.as_ref())?;
+    let game = parse_full_state(&contents)?;
+    game.validate()?;
+    Ok(game)
+}
 
+/// Load a save file in any of the supported formats, detecting which one it is from its contents
+pub fn load_game_auto(file_path: impl AsRef<Path>) -> Result<Game, SaveGameError> {
+    let contents = read_save_file(file_path.as_ref())?;
+    parse_save(&contents)
+}
+
+/// The save formats `load_game_auto` knows how to read
+#[derive(Debug, Clone, Copy, Eq, PartialEq, strum::Display)]
+pub enum SaveFormat {
+    /// The format written by `save_game`, starting with an `ActivePlayer:` line
+    #[strum(serialize = "full state")]
+    FullState,
+    /// A Universal Hive Protocol game string, i.e. `Base+MLP;InProgress;White[2];wS1;bG1 wS1-`
+    #[strum(serialize = "UHP game string")]
+    UhpGameString,
+    /// Just the board, with white to move
+    #[strum(serialize = "board")]
+    Board,
+    /// The moves of a game along with the positions they lead to, written by `save_game_verified`
+    #[strum(serialize = "verified replay")]
+    VerifiedReplay,
+}
+
+impl SaveFormat {
+    pub fn sniff(contents: &str) -> Option<SaveFormat> {
+        let first_line = contents
+            .lines()
+            .find(|line| !line.trim().is_empty())?
+            .trim();
+        if first_line.starts_with("ActivePlayer:") {
+            Some(SaveFormat::FullState)
+        } else if first_line.starts_with("Base") && first_line.contains(';') {
+            Some(SaveFormat::UhpGameString)
+        } else if first_line.starts_with("game_type") {
+            Some(SaveFormat::VerifiedReplay)
+        } else if contents.parse::<Hive>().is_ok() {
+            Some(SaveFormat::Board)
+        } else {
+            None
+        }
+    }
+}
+
+/// Parse the contents of a save file in any of the supported formats, rejecting positions that
+/// couldn't have come from a real game
+pub fn parse_save(contents: &str) -> Result<Game, SaveGameError> {
+    let game = match SaveFormat::sniff(contents) {
+        Some(SaveFormat::FullState) => parse_full_state(contents)?,
+        Some(SaveFormat::UhpGameString) => parse_uhp_game_string(contents)?,
+        Some(SaveFormat::Board) => Game::from_hive(contents.parse()?, Color::White),
+        Some(SaveFormat::VerifiedReplay) => parse_verified_replay(contents)?,
+        None => {
+            return Err(SaveGameError::UnknownFormat(
+                [
+                    SaveFormat::FullState,
+                    SaveFormat::UhpGameString,
+                    SaveFormat::Board,
+                    SaveFormat::VerifiedReplay,
+                ]
+                .iter()
+                .join(", "),
+            ));
+        }
+    };
+    game.validate()?;
+    Ok(game)
+}
+
+fn read_save_file(path: &Path) -> Result<String, SaveGameError> {
+    let mut contents = String::new();
     File::open(path)
         .map_err(|e| SaveGameError::ReadFileError(path.display().to_string(), e))?
         .read_to_string(&mut contents)
         .map_err(|e| SaveGameError::ReadFileError(path.display().to_string(), e))?;
+    Ok(contents)
+}
+
+fn parse_uhp_game_string(contents: &str) -> Result<Game, SaveGameError> {
+    // GameTypeString;GameStateString;TurnString;MoveString1;MoveString2...
+    let mut fields = contents.trim().split(';');
+    let game_type = fields.next().unwrap_or_default();
+    let moves: Vec<&str> = fields.skip(2).collect();
+    Ok(Game::from_uhp(game_type, &moves)?)
+}
 
-    let mut lines = contents.lines();
+fn parse_full_state(contents: &str) -> Result<Game, SaveGameError> {
+    let mut lines = contents.lines().peekable();
 
     // Parse first line for active player
     let first_line = lines
@@ -85,14 +327,91 @@ pub fn load_game(file_path: impl AsRef<Path>) -> Result<Game, SaveGameError> {
         .parse::<Color>()
         .map_err(|e| SaveGameError::ParseColorError(e.to_string()))?;
 
+    // Optional clock, resignation, and piece number lines
+    let mut white_clock = None;
+    let mut black_clock = None;
+    let mut resigned = None;
+    let mut piece_numbers = None;
+    while let Some(line) = lines.peek() {
+        if let Some(clock) = line.strip_prefix(WHITE_CLOCK_PREFIX) {
+            white_clock = Some(parse_clock(clock)?);
+        } else if let Some(clock) = line.strip_prefix(BLACK_CLOCK_PREFIX) {
+            black_clock = Some(parse_clock(clock)?);
+        } else if let Some(color) = line.strip_prefix(RESIGNED_PREFIX) {
+            resigned = Some(
+                color
+                    .trim()
+                    .parse::<Color>()
+                    .map_err(|e| SaveGameError::ParseColorError(e.to_string()))?,
+            );
+        } else if let Some(numbers) = line.strip_prefix(PIECE_NUMBERS_PREFIX) {
+            piece_numbers = Some(numbers.to_string());
+        } else {
+            break;
+        }
+        lines.next();
+    }
+
     // Remaining lines form the game state
     let game_data: String = lines.collect::<Vec<_>>().join("\n");
     let hive: Hive = game_data.parse()?;
-    let game = Game::from_hive(hive, active_player);
+    let mut game = Game::from_hive(hive, active_player);
+    game.white_clock = white_clock;
+    game.black_clock = black_clock;
+    game.resigned = resigned;
+    if let Some(numbers) = piece_numbers {
+        game.piece_numbers = parse_piece_numbers(game.hive(), &numbers)?;
+    }
 
     Ok(game)
 }
 
+fn parse_clock(clock: &str) -> Result<Duration, SaveGameError> {
+    parse_duration(clock.trim()).map_err(|e| SaveGameError::ParseClockError(e.to_string()))
+}
+
+/// Piece numbers are saved per bug, in the order `Hive::pieces_of` returns that bug's hexes. Unlike
+/// the hexes themselves, that order survives the board being re-positioned when it's loaded, e.g.
+/// `A:2,1 s:1,2` means the first white ant in scan order is the second one that was placed.
+fn format_piece_numbers(game: &Game) -> String {
+    let tiles: BTreeSet<Tile> = game.hive().map.values().copied().collect();
+    tiles
+        .into_iter()
+        .map(|tile| {
+            let numbers = game
+                .hive()
+                .pieces_of(tile.bug, tile.color)
+                .iter()
+                .map(|hex| game.piece_numbers.get(hex).copied().unwrap_or(1))
+                .join(",");
+            format!("{tile}:{numbers}")
+        })
+        .join(" ")
+}
+
+fn parse_piece_numbers(hive: &Hive, numbers: &str) -> Result<FxHashMap<Hex, u8>, SaveGameError> {
+    let error = || SaveGameError::ParsePieceNumbersError(numbers.trim().to_string());
+    let mut piece_numbers = FxHashMap::default();
+    for entry in numbers.split_whitespace() {
+        let (tile, tile_numbers) = entry.split_once(':').ok_or_else(error)?;
+        let tile: Tile = tile.parse().map_err(|_| error())?;
+        let hexes = hive.pieces_of(tile.bug, tile.color);
+        let tile_numbers: Vec<u8> = tile_numbers
+            .split(',')
+            .map(|number| number.parse().map_err(|_| error()))
+            .collect::<Result<_, _>>()?;
+        if hexes.len() != tile_numbers.len() {
+            return Err(error());
+        }
+        piece_numbers.extend(hexes.into_iter().zip(tile_numbers));
+    }
+    if piece_numbers.len() != hive.map.len() {
+        return Err(error());
+    }
+
+    Ok(piece_numbers)
+}
+
 pub fn list_save_games(directory_path: impl AsRef<Path>) -> Result<Vec<String>, SaveGameError> {
     let dir_path = directory_path.as_ref();
 
@@ -116,3 +435,226 @@ pub fn list_save_games(directory_path: impl AsRef<Path>) -> Result<Vec<String>,
 
     Ok(saves)
 }
+
+/// What a load menu needs to show about a save game
+#[derive(Debug)]
+pub struct SaveGameInfo {
+    pub file_name: String,
+    pub active_player: Color,
+    pub piece_count: usize,
+    /// `GameResult::None` if the game is still in progress
+    pub result: GameResult,
+    pub modified: SystemTime,
+}
+
+/// The save games in a directory, from `list_save_games_detailed`
+#[derive(Debug, Default)]
+pub struct SaveGameListing {
+    pub saves: Vec<SaveGameInfo>,
+    /// Files that couldn't be loaded, along with why, for the caller to report however it likes
+    pub skipped: Vec<(String, SaveGameError)>,
+}
+
+/// Like `list_save_games`, but loads each save to describe it. Files that can't be loaded are
+/// skipped rather than failing the whole listing, and returned in `SaveGameListing::skipped`.
+pub fn list_save_games_detailed(
+    directory_path: impl AsRef<Path>,
+) -> Result<SaveGameListing, SaveGameError> {
+    let dir_path = directory_path.as_ref();
+
+    let mut listing = SaveGameListing::default();
+    for file_name in list_save_games(dir_path)? {
+        let path = dir_path.join(&file_name);
+        let game = match load_game_auto(&path) {
+            Ok(game) => game,
+            Err(e) => {
+                listing.skipped.push((file_name, e));
+                continue;
+            }
+        };
+        let modified = match fs::metadata(&path).and_then(|metadata| metadata.modified()) {
+            Ok(modified) => modified,
+            Err(e) => {
+                let error = SaveGameError::ReadFileError(path.display().to_string(), e);
+                listing.skipped.push((file_name, error));
+                continue;
+            }
+        };
+        listing.saves.push(SaveGameInfo {
+            file_name,
+            active_player: game.active_player,
+            piece_count: game.hive().piece_count(),
+            result: game.game_result(),
+            modified,
+        });
+    }
+
+    Ok(listing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::bug::Bug;
+    use crate::engine::game::Turn;
+
+    /// A directory for a test under the system temp dir, removed when it's dropped so a failing
+    /// test doesn't leave it behind
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new(name: &str) -> TestDir {
+            let id = std::process::id();
+            TestDir(std::env::temp_dir().join(format!("chive-{name}-test-{id}")))
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn save_and_load_keeps_clocks_and_piece_numbers() {
+        let game = Game::from_uhp(
+            "Base+MLP",
+            &[
+                "wA1", "bA1 wA1-", "wA2 -wA1", "bQ bA1-", "wQ \\wA1", "bB1 bQ-",
+            ],
+        )
+        .unwrap();
+        // Move the second ant so that scan order would number it first
+        let ant = Tile {
+            bug: Bug::Ant,
+            color: Color::White,
+        };
+        let first_ant = game.find_piece(ant, 1).unwrap();
+        let second_ant = game.find_piece(ant, 2).unwrap();
+        let turn = game
+            .moves_for_piece(&second_ant)
+            .find(|turn| matches!(turn, Turn::Move { to, .. } if *to < first_ant))
+            .unwrap();
+        let mut game = game.with_turn_applied(turn);
+        game.white_clock = Some(Duration::from_secs(90));
+        game.black_clock = Some(Duration::from_millis(1500));
+
+        let loaded = parse_save(&full_state_string(&game)).unwrap();
+
+        // Saves don't keep the last turn
+        let mut without_last_turn = game.clone();
+        without_last_turn.last_turn = None;
+        assert_eq!(loaded, without_last_turn);
+        assert_eq!(loaded.white_clock, game.white_clock);
+        assert_eq!(loaded.black_clock, game.black_clock);
+        assert_eq!(format_piece_numbers(&loaded), format_piece_numbers(&game));
+        assert!(format_piece_numbers(&game).contains("A:2,1"));
+    }
+
+    #[test]
+    fn save_and_load_keeps_resignation() {
+        let mut game = Game::from_uhp("Base", &["wA1", "bA1 wA1-"]).unwrap();
+        assert_eq!(
+            game.resign(Color::White),
+            GameResult::Winner {
+                color: Color::Black
+            }
+        );
+
+        let loaded = parse_save(&full_state_string(&game)).unwrap();
+
+        assert_eq!(loaded.resigned, Some(Color::White));
+        assert_eq!(loaded.game_result(), game.game_result());
+    }
+
+    #[test]
+    fn loading_a_finished_game_reports_the_winner() {
+        let board = r#"
+            .  A  A  .
+             G  q  G
+            .  A  B  Q
+            "#;
+        let loaded = parse_save(&format!("ActivePlayer: black\n{board}")).unwrap();
+
+        assert!(loaded.is_game_over());
+        assert_eq!(
+            loaded.game_result(),
+            GameResult::Winner {
+                color: Color::White
+            }
+        );
+    }
+
+    #[test]
+    fn sniffs_save_formats() {
+        let full_state = "ActivePlayer: black\n Q  a \n";
+        let uhp = "Base+MLP;InProgress;Black[2];wS1;bG1 wS1-;wQ -wS1";
+        let board = "\n  .  Q  a\n   .  q  .\n";
+
+        assert_eq!(SaveFormat::sniff(full_state), Some(SaveFormat::FullState));
+        assert_eq!(SaveFormat::sniff(uhp), Some(SaveFormat::UhpGameString));
+        assert_eq!(SaveFormat::sniff(board), Some(SaveFormat::Board));
+        assert_eq!(SaveFormat::sniff("hello world"), None);
+
+        assert_eq!(parse_save(full_state).unwrap().active_player, Color::Black);
+        assert_eq!(parse_save(uhp).unwrap().hive().map.len(), 3);
+        assert_eq!(parse_save(board).unwrap().hive().map.len(), 3);
+        assert!(matches!(
+            parse_save("hello world"),
+            Err(SaveGameError::UnknownFormat(_))
+        ));
+    }
+
+    #[test]
+    fn verified_replays_catch_the_first_changed_ply() {
+        let moves = ["wS1", "bG1 wS1-", "wQ -wS1", "bQ bG1-", "wA1 -wQ"];
+        let contents = verified_replay_string("Base", &moves).unwrap();
+        let loaded = parse_verified_replay(&contents).unwrap();
+        let auto_loaded = parse_save(&contents).unwrap();
+
+        let expected = Game::from_uhp("Base", &moves).unwrap();
+        assert_eq!(loaded.canonical_key(), expected.canonical_key());
+        assert_eq!(auto_loaded.canonical_key(), expected.canonical_key());
+
+        // Moving the ant somewhere else on ply 5 still replays, but not to the recorded position
+        let tampered = contents.replace("wA1 -wQ", "wA1 /wQ");
+        assert_ne!(tampered, contents);
+        match parse_verified_replay(&tampered) {
+            Err(SaveGameError::ReplayMismatch {
+                ply, move_string, ..
+            }) => {
+                assert_eq!(ply, 5);
+                assert_eq!(move_string, "wA1 /wQ");
+            }
+            other => panic!("expected a mismatch, got {other:?}"),
+        }
+
+        // A recorded move that isn't legal any more is reported at its ply too
+        let illegal = contents.replace("bQ bG1-", "bQ wQ-");
+        assert!(matches!(
+            parse_verified_replay(&illegal),
+            Err(SaveGameError::ParseUhpError(UhpError::IllegalMove {
+                turn_number: Some(4),
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn detailed_listing_skips_unreadable_saves() {
+        let directory = TestDir::new("list");
+        let game = Game::from_uhp("Base", &["wA1", "bA1 wA1-", "wQ -wA1"]).unwrap();
+        save_game(&game, &directory.0).unwrap();
+        fs::write(directory.0.join("garbage.txt"), "not a save game").unwrap();
+
+        let listing = list_save_games_detailed(&directory.0).unwrap();
+
+        let saves = listing.saves;
+        assert_eq!(saves.len(), 1);
+        assert_eq!(saves[0].active_player, Color::Black);
+        assert_eq!(saves[0].piece_count, 3);
+        assert_eq!(saves[0].result, GameResult::None);
+        assert_eq!(listing.skipped.len(), 1);
+        assert_eq!(listing.skipped[0].0, "garbage.txt");
+    }
+}
@@ -0,0 +1,455 @@
+use crate::engine::bug::Bug;
+use crate::engine::game::{Game, Turn};
+use crate::engine::hex::{Direction, Hex, neighbor};
+use crate::engine::hive::{Color, Tile};
+use strum::{Display, EnumString};
+use thiserror::Error;
+
+/// The game types defined by the Universal Hive Protocol, i.e. which expansion pieces are in play
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default, Display, EnumString)]
+pub enum GameType {
+    #[strum(serialize = "Base")]
+    Base,
+    #[strum(serialize = "Base+M")]
+    BaseM,
+    #[strum(serialize = "Base+L")]
+    BaseL,
+    #[strum(serialize = "Base+P")]
+    BaseP,
+    #[strum(serialize = "Base+ML")]
+    BaseML,
+    #[strum(serialize = "Base+MP")]
+    BaseMP,
+    #[strum(serialize = "Base+LP")]
+    BaseLP,
+    #[default]
+    #[strum(serialize = "Base+MLP")]
+    BaseMLP,
+}
+
+const BASE_RESERVE: [Bug; 11] = [
+    Bug::Queen,
+    Bug::Ant,
+    Bug::Ant,
+    Bug::Ant,
+    Bug::Beetle,
+    Bug::Beetle,
+    Bug::Grasshopper,
+    Bug::Grasshopper,
+    Bug::Grasshopper,
+    Bug::Spider,
+    Bug::Spider,
+];
+
+impl GameType {
+    pub fn expansion_bugs(&self) -> &'static [Bug] {
+        match self {
+            GameType::Base => &[],
+            GameType::BaseM => &[Bug::Mosquito],
+            GameType::BaseL => &[Bug::Ladybug],
+            GameType::BaseP => &[Bug::Pillbug],
+            GameType::BaseML => &[Bug::Ladybug, Bug::Mosquito],
+            GameType::BaseMP => &[Bug::Mosquito, Bug::Pillbug],
+            GameType::BaseLP => &[Bug::Ladybug, Bug::Pillbug],
+            GameType::BaseMLP => &[Bug::Ladybug, Bug::Mosquito, Bug::Pillbug],
+        }
+    }
+
+    /// The pieces each player starts the game with
+    pub fn reserve(&self) -> Vec<Bug> {
+        BASE_RESERVE
+            .iter()
+            .chain(self.expansion_bugs())
+            .copied()
+            .collect()
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum UhpError {
+    #[error("Invalid game type: {0}")]
+    InvalidGameType(String),
+    #[error("Invalid move string: {0}")]
+    InvalidMoveString(String),
+    #[error("Invalid piece: {0}")]
+    InvalidPiece(String),
+    #[error("Piece {0} is not on the board")]
+    PieceNotOnBoard(String),
+    /// `turn_number` counts from 1, and is only known when playing through a list of moves
+    #[error("Move {} is not legal", describe_move(.turn_number, .move_string))]
+    IllegalMove {
+        turn_number: Option<usize>,
+        move_string: String,
+    },
+}
+
+impl UhpError {
+    /// Fill in which move of a list of moves was illegal
+    pub(crate) fn on_turn(self, turn_number: usize) -> UhpError {
+        match self {
+            UhpError::IllegalMove { move_string, .. } => UhpError::IllegalMove {
+                turn_number: Some(turn_number),
+                move_string,
+            },
+            e => e,
+        }
+    }
+}
+
+fn describe_move(turn_number: &Option<usize>, move_string: &str) -> String {
+    match turn_number {
+        Some(turn_number) => format!("{turn_number} ({move_string})"),
+        None => move_string.to_string(),
+    }
+}
+
+/// Parse a single UHP move string, i.e. `pass`, `wS1` or `bG1 -wS1`, into the turns it could mean
+/// for the given game. Moves of a friendly piece are ambiguous between a regular move and a push by
+/// an adjacent pillbug, so both are returned with the regular move first.
+pub(crate) fn candidate_turns(game: &Game, move_string: &str) -> Result<Vec<Turn>, UhpError> {
+    let move_string = move_string.trim();
+    if move_string == "pass" {
+        return Ok(vec![Turn::Skip]);
+    }
+
+    let invalid_move_string = || UhpError::InvalidMoveString(move_string.to_string());
+    let mut tokens = move_string.split_whitespace();
+    let (tile, number) = parse_piece(tokens.next().ok_or_else(invalid_move_string)?)?;
+    let destination = match tokens.next() {
+        None if game.hive().map.is_empty() => Hex::default(),
+        None => return Err(invalid_move_string()),
+        Some(reference) => destination(game, reference)?,
+    };
+    if tokens.next().is_some() {
+        return Err(invalid_move_string());
+    }
+
+    match game.find_piece(tile, number) {
+        None => {
+            // Pieces have to be placed in order, wA2 can't be placed before wA1
+            let placed = game.hive().map.values().filter(|t| **t == tile).count();
+            if placed + 1 != number as usize {
+                return Err(invalid_move_string());
+            }
+            Ok(vec![Turn::Placement {
+                hex: destination,
+                tile,
+            }])
+        }
+        Some(from) => {
+            let push = Turn::Move {
+                from,
+                to: destination,
+                freezes_piece: true,
+            };
+            if tile.color == game.active_player {
+                let mv = Turn::Move {
+                    from,
+                    to: destination,
+                    freezes_piece: false,
+                };
+                Ok(vec![mv, push])
+            } else {
+                Ok(vec![push])
+            }
+        }
+    }
+}
+
+fn destination(game: &Game, reference: &str) -> Result<Hex, UhpError> {
+    let (piece, direction) = if let Some(piece) = reference.strip_prefix('-') {
+        (piece, Some(Direction::Left))
+    } else if let Some(piece) = reference.strip_prefix('/') {
+        (piece, Some(Direction::DownLeft))
+    } else if let Some(piece) = reference.strip_prefix('\\') {
+        (piece, Some(Direction::UpLeft))
+    } else if let Some(piece) = reference.strip_suffix('-') {
+        (piece, Some(Direction::Right))
+    } else if let Some(piece) = reference.strip_suffix('/') {
+        (piece, Some(Direction::UpRight))
+    } else if let Some(piece) = reference.strip_suffix('\\') {
+        (piece, Some(Direction::DownRight))
+    } else {
+        (reference, None)
+    };
+
+    let (tile, number) = parse_piece(piece)?;
+    let reference_hex = game
+        .find_piece(tile, number)
+        .ok_or_else(|| UhpError::PieceNotOnBoard(piece.to_string()))?
+        .base_level();
+
+    let column = match direction {
+        Some(direction) => neighbor(&reference_hex, &direction),
+        None => reference_hex,
+    };
+    Ok(game.hive().bottommost_unoccupied_hex(&column))
+}
+
+/// Format a turn as a UHP move string, the inverse of `candidate_turns`. A push of a friendly piece
+/// looks the same as a regular move of it, since UHP doesn't tell them apart.
+pub(crate) fn format_turn(game: &Game, turn: Turn) -> String {
+    let (tile, number, to, from) = match turn {
+        Turn::Skip => return "pass".to_string(),
+        Turn::Placement { hex, tile } => {
+            let placed = game.hive().map.values().filter(|t| **t == tile).count();
+            (tile, placed as u8 + 1, hex, None)
+        }
+        Turn::Move { from, to, .. } => {
+            let tile = game
+                .hive()
+                .tile_at(&from)
+                .expect("Moved piece must be on the board");
+            (tile, piece_number(game, &from), to, Some(from))
+        }
+    };
+    let piece = format_piece(&tile, number);
+
+    // Climbing onto a stack is written as the piece it ends up on top of
+    if to.h > 0 {
+        let below = Hex { h: to.h - 1, ..to };
+        return format!("{piece} {}", reference_piece(game, &below));
+    }
+    for direction in Direction::all() {
+        let column = to - direction.vector();
+        let Some(mut reference) = game.hive().topmost_occupied_hex(&column) else {
+            continue;
+        };
+        // The moving piece can't be its own reference, but whatever it's standing on can be
+        if Some(reference) == from {
+            if reference.h == 0 {
+                continue;
+            }
+            reference.h -= 1;
+        }
+        let reference = reference_piece(game, &reference);
+        return match direction {
+            Direction::Left => format!("{piece} -{reference}"),
+            Direction::DownLeft => format!("{piece} /{reference}"),
+            Direction::UpLeft => format!("{piece} \\{reference}"),
+            Direction::Right => format!("{piece} {reference}-"),
+            Direction::UpRight => format!("{piece} {reference}/"),
+            Direction::DownRight => format!("{piece} {reference}\\"),
+        };
+    }
+    // Only the first piece of the game has nothing next to it
+    piece
+}
+
+fn piece_number(game: &Game, hex: &Hex) -> u8 {
+    game.piece_numbers.get(hex).copied().unwrap_or(1)
+}
+
+fn reference_piece(game: &Game, hex: &Hex) -> String {
+    let tile = game
+        .hive()
+        .tile_at(hex)
+        .expect("Reference piece must be on the board");
+    format_piece(&tile, piece_number(game, hex))
+}
+
+/// Whether each player only has one of this bug, in which case its id has no number
+fn has_single_copy(bug: Bug) -> bool {
+    matches!(
+        bug,
+        Bug::Queen | Bug::Ladybug | Bug::Mosquito | Bug::Pillbug
+    )
+}
+
+/// Format a piece as a UHP piece id like `wQ` or `bA2`
+pub fn format_piece(tile: &Tile, number: u8) -> String {
+    let color = match tile.color {
+        Color::White => 'w',
+        Color::Black => 'b',
+    };
+    if has_single_copy(tile.bug) {
+        format!("{color}{}", tile.bug)
+    } else {
+        format!("{color}{}{number}", tile.bug)
+    }
+}
+
+/// Parse a UHP piece id like `wQ` or `bA2` into the tile it refers to and which copy of the bug it
+/// is. Bugs that only have a single copy are always number 1.
+pub fn parse_piece(piece: &str) -> Result<(Tile, u8), UhpError> {
+    let invalid_piece = || UhpError::InvalidPiece(piece.to_string());
+    let mut chars = piece.chars();
+    let color = match chars.next() {
+        Some('w') => Color::White,
+        Some('b') => Color::Black,
+        _ => return Err(invalid_piece()),
+    };
+    let bug: Bug = chars
+        .next()
+        .ok_or_else(invalid_piece)?
+        .to_string()
+        .parse()
+        .map_err(|_| invalid_piece())?;
+    let number = match chars.as_str() {
+        "" if has_single_copy(bug) => 1,
+        number if !has_single_copy(bug) => match number.parse() {
+            Ok(number @ 1..=3) => number,
+            _ => return Err(invalid_piece()),
+        },
+        _ => return Err(invalid_piece()),
+    };
+
+    Ok((Tile { bug, color }, number))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::hive::TileMap;
+    use rand::SeedableRng;
+    use rand::prelude::IndexedRandom;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn parses_game_types() {
+        assert_eq!("Base".parse::<GameType>().unwrap(), GameType::Base);
+        assert_eq!("Base+MLP".parse::<GameType>().unwrap(), GameType::BaseMLP);
+        assert!("Base+X".parse::<GameType>().is_err());
+        assert!(!GameType::Base.reserve().contains(&Bug::Mosquito));
+    }
+
+    #[test]
+    fn builds_game_from_move_strings() {
+        let game = Game::from_uhp(
+            "Base+MLP",
+            &[
+                "wS1", "bG1 wS1-", "wQ -wS1", "bQ bG1/", "wA1 \\wQ", "bA1 bQ\\",
+            ],
+        )
+        .unwrap();
+
+        let tile = |bug, color| Tile { bug, color };
+        let expected = TileMap::from_iter([
+            (Hex { q: 0, r: 0, h: 0 }, tile(Bug::Spider, Color::White)),
+            (
+                Hex { q: 1, r: 0, h: 0 },
+                tile(Bug::Grasshopper, Color::Black),
+            ),
+            (Hex { q: -1, r: 0, h: 0 }, tile(Bug::Queen, Color::White)),
+            (Hex { q: 2, r: -1, h: 0 }, tile(Bug::Queen, Color::Black)),
+            (Hex { q: -1, r: -1, h: 0 }, tile(Bug::Ant, Color::White)),
+            (Hex { q: 2, r: 0, h: 0 }, tile(Bug::Ant, Color::Black)),
+        ]);
+        assert_eq!(game.hive().map, expected);
+        assert_eq!(game.active_player, Color::White);
+    }
+
+    #[test]
+    fn moves_pieces_by_id() {
+        let game = Game::from_uhp(
+            "Base",
+            &[
+                "wS1", "bS1 wS1-", "wQ -wS1", "bQ bS1-", "wB1 -wQ", "bB1 bQ-", "wB1 wQ", "bB1 bQ",
+            ],
+        )
+        .unwrap();
+
+        let tile = |bug, color| Tile { bug, color };
+        let expected = TileMap::from_iter([
+            (Hex { q: 0, r: 0, h: 0 }, tile(Bug::Spider, Color::White)),
+            (Hex { q: 1, r: 0, h: 0 }, tile(Bug::Spider, Color::Black)),
+            (Hex { q: -1, r: 0, h: 0 }, tile(Bug::Queen, Color::White)),
+            (Hex { q: 2, r: 0, h: 0 }, tile(Bug::Queen, Color::Black)),
+            (Hex { q: -1, r: 0, h: 1 }, tile(Bug::Beetle, Color::White)),
+            (Hex { q: 2, r: 0, h: 1 }, tile(Bug::Beetle, Color::Black)),
+        ]);
+        assert_eq!(game.hive().map, expected);
+    }
+
+    #[test]
+    fn piece_ids_round_trip() {
+        for id in ["wQ", "bA2", "wS1", "bP"] {
+            let (tile, number) = parse_piece(id).unwrap();
+            assert_eq!(format_piece(&tile, number), id);
+        }
+        assert!(parse_piece("wQ1").is_err());
+        assert!(parse_piece("bA").is_err());
+        assert!(parse_piece("bA4").is_err());
+    }
+
+    #[test]
+    fn rejects_pieces_placed_out_of_order() {
+        let result = Game::from_uhp("Base", &["wS2"]);
+        assert!(matches!(result, Err(UhpError::InvalidMoveString(_))));
+    }
+
+    #[test]
+    fn rejects_illegal_moves() {
+        let result = Game::from_uhp("Base", &["wS1", "bG1 wS1-", "wQ bG1-"]);
+        assert!(matches!(
+            result,
+            Err(UhpError::IllegalMove {
+                turn_number: Some(3),
+                ..
+            })
+        ));
+
+        let game = Game::from_uhp("Base", &["wS1", "bG1 wS1-"]).unwrap();
+        assert!(matches!(
+            game.apply_move_string(" wQ bG1- "),
+            Err(UhpError::IllegalMove {
+                turn_number: None,
+                move_string,
+            }) if move_string == "wQ bG1-"
+        ));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Move 3 (wQ bG1-) is not legal"
+        );
+        assert_eq!(
+            game.apply_move_string("wQ bG1-").unwrap_err().to_string(),
+            "Move wQ bG1- is not legal"
+        );
+    }
+
+    #[test]
+    fn rejects_pieces_outside_game_type() {
+        let result = Game::from_uhp("Base", &["wM"]);
+        assert!(matches!(result, Err(UhpError::IllegalMove { .. })));
+        assert!(matches!(
+            Game::from_uhp("Chess", &[]),
+            Err(UhpError::InvalidGameType(_))
+        ));
+    }
+
+    #[test]
+    fn legal_move_strings_parse_back_to_their_turns() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut game = Game::from_uhp("Base+MLP", &[]).unwrap();
+        for _ in 0..40 {
+            for turn in game.turns() {
+                let move_string = game.to_move_string(turn);
+                assert!(
+                    candidate_turns(&game, &move_string)
+                        .unwrap()
+                        .contains(&turn),
+                    "{move_string} doesn't parse back to {turn:?}"
+                );
+            }
+            let turns: Vec<Turn> = game.turns().collect();
+            game = game.with_turn_applied(*turns.choose(&mut rng).unwrap());
+        }
+    }
+
+    #[test]
+    fn formats_moves_like_uhp() {
+        let game = Game::from_uhp(
+            "Base",
+            &["wS1", "bS1 wS1-", "wQ -wS1", "bQ bS1-", "wB1 -wQ"],
+        )
+        .unwrap();
+        let moves = game.legal_move_strings();
+        assert!(moves.contains(&"bB1 bQ-".to_string()));
+        assert!(moves.contains(&"bQ bS1/".to_string()));
+        assert_eq!(game.to_move_string(Turn::Skip), "pass");
+        assert_eq!(
+            Game::from_uhp("Base", &[]).unwrap().legal_move_strings()[0],
+            "wA1"
+        );
+    }
+}
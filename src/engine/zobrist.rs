@@ -1,7 +1,8 @@
 use crate::engine::bug::Bug;
 use crate::engine::hex::Hex;
 use crate::engine::hive::{Color, Hive, Tile};
-use rand::random;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use std::ops::{BitXor, BitXorAssign};
 use std::sync::OnceLock;
 use strum::EnumCount;
@@ -13,6 +14,9 @@ const MAX_AXIS_VALUE: i32 = 21;
 const AXIS_ARRAY_SIZE: usize = (MAX_AXIS_VALUE - MIN_AXIS_VALUE) as usize;
 const HEIGHT_ARRAY_SIZE: usize = MAX_HEIGHT - MIN_HEIGHT;
 static ZOBRIST_TABLE: OnceLock<ZobristTable> = OnceLock::new();
+// Fixed so hashes, and anything that depends on them like AI transposition tables, are the same
+// from run to run
+const ZOBRIST_SEED: u64 = 0x6869_7665;
 
 #[derive(Copy, Clone, Default)]
 pub struct ZobristHash(pub u64);
@@ -63,6 +67,7 @@ impl ZobristTable {
     }
 
     fn new() -> ZobristTable {
+        let mut rng = StdRng::seed_from_u64(ZOBRIST_SEED);
         let mut piece_table: Box<ZobristPieceTable> = Box::new(
             [[[[ZobristHash(0); AXIS_ARRAY_SIZE]; AXIS_ARRAY_SIZE]; HEIGHT_ARRAY_SIZE];
                 TILE_INDEX_COUNT],
@@ -72,7 +77,7 @@ impl ZobristTable {
             for h in 0..HEIGHT_ARRAY_SIZE {
                 for q in 0..AXIS_ARRAY_SIZE {
                     for r in 0..AXIS_ARRAY_SIZE {
-                        piece_table[tile_index][h][q][r] = ZobristHash(random())
+                        piece_table[tile_index][h][q][r] = ZobristHash(rng.random())
                     }
                 }
             }
@@ -80,7 +85,7 @@ impl ZobristTable {
 
         ZobristTable {
             piece_table,
-            black_to_move: ZobristHash(random()),
+            black_to_move: ZobristHash(rng.random()),
         }
     }
 
@@ -0,0 +1,39 @@
+use crate::config::ConfigError;
+use crate::engine::ai::AiError;
+use crate::engine::bug::BugParseError;
+use crate::engine::game::{ApplyError, GameParseError, IllegalTurnError, PositionError};
+use crate::engine::hive::HiveParseError;
+use crate::engine::parse::HexMapParseError;
+use crate::engine::save_game::SaveGameError;
+use crate::engine::uhp::UhpError;
+use thiserror::Error;
+
+/// Any error the engine can return, for callers that don't need to handle each kind separately.
+/// The module specific errors are still available for callers that do.
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Ai(#[from] AiError),
+    #[error(transparent)]
+    Apply(#[from] ApplyError),
+    #[error(transparent)]
+    BugParse(#[from] BugParseError),
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    #[error(transparent)]
+    GameParse(#[from] GameParseError),
+    #[error(transparent)]
+    IllegalTurn(#[from] IllegalTurnError),
+    #[error(transparent)]
+    HiveParse(#[from] HiveParseError),
+    #[error(transparent)]
+    HexMapParse(#[from] HexMapParseError),
+    #[error(transparent)]
+    Position(#[from] PositionError),
+    #[error(transparent)]
+    SaveGame(#[from] SaveGameError),
+    #[error(transparent)]
+    Uhp(#[from] UhpError),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
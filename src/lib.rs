@@ -1,2 +1,6 @@
+pub mod config;
 pub mod engine;
-mod graphics;
\ No newline at end of file
+mod error;
+mod graphics;
+
+pub use error::{Error, Result};